@@ -0,0 +1,159 @@
+//! The benchmark mode that measures the decode and update throughput
+//! on a packet dump file.
+
+use crate::{
+    config::UPDATE_BATCH_SIZE,
+    opts::Opts,
+    rtps::{CaptureStats, PacketDecoder, PacketKind, ReplayControl},
+    rtps_watcher,
+    state::State,
+    updater::Updater,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tokio_util::sync::CancellationToken;
+
+/// Reads the input file without throttling and prints a summary of
+/// the throughput to stdout.
+///
+/// A thread locks the state at the refresh rate like the TUI does, so
+/// that the time a frame waits for the lock is reported. With
+/// `--batch`, the events are applied in batches under one lock.
+pub fn run_benchmark(opts: &Opts) -> Result<()> {
+    let path = opts
+        .file
+        .as_ref()
+        .ok_or_else(|| anyhow!("--benchmark requires --file"))?;
+
+    let state = Arc::new(Mutex::new(State::new(opts.rate_window())));
+    let stats = CaptureStats::new();
+    let (_tx, rx) = flume::unbounded();
+    let mut updater = Updater::new(
+        rx,
+        CancellationToken::new(),
+        state.clone(),
+        ReplayControl::new(),
+        stats.clone(),
+        opts,
+    )?;
+
+    let config = opts.capture_config();
+    let cap = if opts.reads_stdin() {
+        config.open_stdin()?
+    } else {
+        config.open_file(path)?
+    };
+    let mut packets = cap.iter(PacketDecoder::new(&config, stats.clone()));
+    let mut total_packets = 0usize;
+    let mut rtps_packets = 0usize;
+    let mut total_events = 0usize;
+    let mut decode_time = Duration::ZERO;
+    let mut update_time = Duration::ZERO;
+    let batch_size = if opts.batch { UPDATE_BATCH_SIZE } else { 1 };
+    let mut pending = vec![];
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let frame_handle = {
+        let state = state.clone();
+        let stop = stop.clone();
+        let frame_interval = Duration::from_secs(1) / opts.refresh_rate;
+        thread::spawn(move || measure_lock_waits(&state, &stop, frame_interval))
+    };
+    let since = Instant::now();
+
+    loop {
+        // Decode the packet and interpret it into events.
+        let decode_since = Instant::now();
+        let Some(packet) = packets.next() else {
+            break;
+        };
+        total_packets += 1;
+        let events = match packet? {
+            PacketKind::Rtps(packet) => {
+                rtps_packets += 1;
+                rtps_watcher::handle_msg(&packet, opts.time_source)
+            }
+            PacketKind::Other(_) => {
+                decode_time += decode_since.elapsed();
+                continue;
+            }
+        };
+        decode_time += decode_since.elapsed();
+
+        // Apply the events to the state.
+        total_events += events.len();
+        pending.extend(events);
+        let update_since = Instant::now();
+        while pending.len() >= batch_size {
+            let batch: Vec<_> = pending.drain(..batch_size).collect();
+            updater.process(&batch)?;
+        }
+        update_time += update_since.elapsed();
+    }
+
+    let update_since = Instant::now();
+    updater.process(&mem::take(&mut pending))?;
+    update_time += update_since.elapsed();
+
+    let elapsed = since.elapsed();
+    stop.store(true, Ordering::Relaxed);
+    let lock_waits = frame_handle.join().unwrap();
+
+    let per_sec = |count: usize| count as f64 / elapsed.as_secs_f64();
+
+    println!("elapsed:      {:.3}s", elapsed.as_secs_f64());
+    println!(
+        "packets:      {total_packets} ({:.0}/s), {rtps_packets} RTPS ({:.0}/s)",
+        per_sec(total_packets),
+        per_sec(rtps_packets)
+    );
+    println!(
+        "events:       {total_events} ({:.0}/s)",
+        per_sec(total_events)
+    );
+    println!("decode time:  {:.3}s", decode_time.as_secs_f64());
+    println!("update time:  {:.3}s", update_time.as_secs_f64());
+    println!(
+        "evicted:      {} incomplete datagrams",
+        stats.evicted_datagrams()
+    );
+    if let Some(max_wait) = lock_waits.iter().max() {
+        let mean_wait = lock_waits.iter().sum::<Duration>() / lock_waits.len() as u32;
+        println!(
+            "lock wait:    {:.3}ms mean, {:.3}ms max over {} frames",
+            mean_wait.as_secs_f64() * 1000.0,
+            max_wait.as_secs_f64() * 1000.0,
+            lock_waits.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Locks the state once per frame interval until stopped, and returns
+/// the time each lock takes to acquire.
+fn measure_lock_waits(
+    state: &Mutex<State>,
+    stop: &AtomicBool,
+    frame_interval: Duration,
+) -> Vec<Duration> {
+    let mut waits = vec![];
+
+    while !stop.load(Ordering::Relaxed) {
+        let since = Instant::now();
+        let guard = state.lock().unwrap_or_else(PoisonError::into_inner);
+        waits.push(since.elapsed());
+        drop(guard);
+        thread::sleep(frame_interval);
+    }
+
+    waits
+}
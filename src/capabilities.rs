@@ -0,0 +1,74 @@
+//! Checks the privileges required by live captures.
+
+#[cfg(target_os = "linux")]
+use std::{env, fs};
+
+/// The CAP_NET_ADMIN capability bit.
+#[cfg(target_os = "linux")]
+const CAP_NET_ADMIN: u32 = 12;
+
+/// The CAP_NET_RAW capability bit.
+#[cfg(target_os = "linux")]
+const CAP_NET_RAW: u32 = 13;
+
+/// Checks if the process has the capabilities to capture on a
+/// network device. Returns the guidance message when any of them is
+/// missing. The check is skipped where the capabilities cannot be
+/// determined.
+#[cfg(target_os = "linux")]
+pub fn check_capture_capabilities() -> Option<String> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let cap_eff = status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))?
+        .trim();
+    let cap_eff = u64::from_str_radix(cap_eff, 16).ok()?;
+
+    let missing: Vec<_> = [
+        (CAP_NET_RAW, "CAP_NET_RAW"),
+        (CAP_NET_ADMIN, "CAP_NET_ADMIN"),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| cap_eff & (1 << bit) == 0)
+    .map(|(_, name)| name)
+    .collect();
+
+    if missing.is_empty() {
+        return None;
+    }
+
+    Some(guidance(&format!(
+        "missing {} to capture packets",
+        missing.join(" and ")
+    )))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn check_capture_capabilities() -> Option<String> {
+    None
+}
+
+/// Returns the guidance message if the error from opening a capture
+/// device is caused by insufficient privileges.
+pub fn permission_error_guidance(err: &pcap::Error) -> Option<String> {
+    let msg = err.to_string().to_lowercase();
+    let denied = msg.contains("permission") || msg.contains("not permitted");
+    denied.then(|| guidance(&err.to_string()))
+}
+
+#[cfg(target_os = "linux")]
+fn guidance(reason: &str) -> String {
+    let exe = env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| "<path-to-ddshark>".to_string());
+
+    format!(
+        "{reason}. Run as root or grant the capabilities by\n\n    \
+         sudo setcap cap_net_raw,cap_net_admin=eip {exe}\n"
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+fn guidance(reason: &str) -> String {
+    format!("{reason}. Run with administrator privileges.")
+}
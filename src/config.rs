@@ -1,3 +1,42 @@
 use std::time::Duration;
 
 pub const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The maximum number of queued events applied under one state lock
+/// with `--batch`.
+pub const UPDATE_BATCH_SIZE: usize = 64;
+
+/// The maximum number of packets read from a pipe and queued before
+/// they are decoded into events.
+pub const PIPED_PACKET_QUEUE_LEN: usize = 1024;
+
+/// The number of ticks kept in the bit rate history of writers and
+/// topics.
+pub const RATE_HISTORY_LEN: usize = 60;
+
+/// The number of recent sequence numbers whose send times are kept on
+/// each writer for the acknowledgment latency.
+pub const SENT_SN_HISTORY_LEN: usize = 1024;
+
+/// The number of recently seen instance key hashes kept on each
+/// writer.
+pub const KEY_HASH_HISTORY_LEN: usize = 256;
+
+/// The window over which the acknowledgment latency is summarized.
+pub const ACK_LATENCY_WINDOW: Duration = Duration::from_secs(10);
+
+/// The minimum interval between the abnormalities that report RTPS
+/// messages failing to parse.
+pub const PARSE_ERROR_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The minimum interval between the abnormalities that report events
+/// dropped on congestion.
+pub const DROPPED_EVENT_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Fragmented messages are dropped if no fragment arrives within
+/// this duration.
+pub const FRAG_MESSAGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The participant lease duration assumed when SPDP data does not
+/// carry one, which is the default in the RTPS specification.
+pub const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(100);
@@ -0,0 +1,203 @@
+//! The JSON Lines stream of decoded events.
+
+use crate::{
+    message::{DataPayload, ParticipantInfo, RtpsSubmsgEvent, RtpsSubmsgEventKind, UpdateEvent},
+    utils::{GUIDExt, GuidPrefixExt, LocatorExt},
+};
+use anyhow::{Context, Result};
+use rustdds::structure::locator::Locator;
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// Writes each event as a JSON object on its own line.
+pub struct EventStream {
+    writer: Box<dyn Write + Send>,
+}
+
+impl EventStream {
+    /// Opens the output file, or stdout if the path is `-`.
+    pub fn new(path: &Path) -> Result<Self> {
+        let writer: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(BufWriter::new(io::stdout()))
+        } else {
+            let file = File::create(path)
+                .with_context(|| format!("unable to create {}", path.display()))?;
+            Box::new(BufWriter::new(file))
+        };
+        Ok(Self { writer })
+    }
+
+    /// Writes the event. Internal events such as ticks are skipped.
+    pub fn write(&mut self, message: &UpdateEvent) -> Result<()> {
+        let record = match message {
+            UpdateEvent::RtpsSubmsg(msg) => submsg_record(msg),
+            UpdateEvent::ParticipantInfo(info) => participant_record(info),
+            UpdateEvent::Tick(_) | UpdateEvent::ToggleLogging => return Ok(()),
+        };
+
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// The serialized form of an event.
+#[derive(Debug, Serialize)]
+struct Record {
+    /// The receipt time in seconds.
+    recv_time: f64,
+    #[serde(flatten)]
+    event: WireEvent,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum WireEvent {
+    Data {
+        writer_guid: String,
+        writer_sn: i64,
+        payload_size: usize,
+        /// The kind of the discovery payload, if any.
+        payload: Option<&'static str>,
+    },
+    DataFrag {
+        writer_guid: String,
+        writer_sn: i64,
+        fragment_starting_num: u32,
+        fragments_in_submessage: u16,
+        data_size: u32,
+        fragment_size: u16,
+        payload_size: usize,
+    },
+    Gap {
+        writer_guid: String,
+        reader_guid: String,
+        gap_start: i64,
+        gap_list: Vec<i64>,
+    },
+    AckNack {
+        writer_guid: String,
+        reader_guid: String,
+        count: i32,
+        base_sn: i64,
+        missing_sn: Vec<i64>,
+    },
+    NackFrag {
+        writer_guid: String,
+        reader_guid: String,
+        writer_sn: i64,
+        count: i32,
+    },
+    Heartbeat {
+        writer_guid: String,
+        first_sn: i64,
+        last_sn: i64,
+        count: i32,
+    },
+    HeartbeatFrag {
+        writer_guid: String,
+        writer_sn: i64,
+        last_fragment_num: u32,
+        count: i32,
+    },
+    ParticipantInfo {
+        guid_prefix: String,
+        source_addr: Option<String>,
+        unicast_locators: Vec<String>,
+        multicast_locators: Vec<String>,
+    },
+}
+
+fn submsg_record(msg: &RtpsSubmsgEvent) -> Record {
+    let event = match &msg.kind {
+        RtpsSubmsgEventKind::Data(event) => WireEvent::Data {
+            writer_guid: event.writer_guid.display().to_string(),
+            writer_sn: event.writer_sn.0,
+            payload_size: event.payload_size,
+            payload: event.payload.as_ref().map(|payload| match payload {
+                DataPayload::Topic(_) => "topic",
+                DataPayload::Writer(_) => "writer",
+                DataPayload::Reader(_) => "reader",
+                DataPayload::Participant(_) => "participant",
+            }),
+        },
+        RtpsSubmsgEventKind::DataFrag(event) => WireEvent::DataFrag {
+            writer_guid: event.writer_guid.display().to_string(),
+            writer_sn: event.writer_sn.0,
+            fragment_starting_num: event.fragment_starting_num,
+            fragments_in_submessage: event.fragments_in_submessage,
+            data_size: event.data_size,
+            fragment_size: event.fragment_size,
+            payload_size: event.payload_size,
+        },
+        RtpsSubmsgEventKind::Gap(event) => WireEvent::Gap {
+            writer_guid: event.writer_guid.display().to_string(),
+            reader_guid: event.reader_guid.display().to_string(),
+            gap_start: event.gap_start.0,
+            gap_list: event.gap_list.iter().map(|sn| sn.0).collect(),
+        },
+        RtpsSubmsgEventKind::AckNack(event) => WireEvent::AckNack {
+            writer_guid: event.writer_guid.display().to_string(),
+            reader_guid: event.reader_guid.display().to_string(),
+            count: event.count,
+            base_sn: event.base_sn,
+            missing_sn: event.missing_sn.clone(),
+        },
+        RtpsSubmsgEventKind::NackFrag(event) => WireEvent::NackFrag {
+            writer_guid: event.writer_guid.display().to_string(),
+            reader_guid: event.reader_guid.display().to_string(),
+            writer_sn: event.writer_sn.0,
+            count: event.count,
+        },
+        RtpsSubmsgEventKind::Heartbeat(event) => WireEvent::Heartbeat {
+            writer_guid: event.writer_guid.display().to_string(),
+            first_sn: event.first_sn.0,
+            last_sn: event.last_sn.0,
+            count: event.count,
+        },
+        RtpsSubmsgEventKind::HeartbeatFrag(event) => WireEvent::HeartbeatFrag {
+            writer_guid: event.writer_guid.display().to_string(),
+            writer_sn: event.writer_sn.0,
+            last_fragment_num: event.last_fragment_num.0,
+            count: event.count,
+        },
+    };
+
+    Record {
+        recv_time: seconds(msg.recv_time),
+        event,
+    }
+}
+
+fn participant_record(info: &ParticipantInfo) -> Record {
+    Record {
+        recv_time: seconds(info.recv_time),
+        event: WireEvent::ParticipantInfo {
+            guid_prefix: info.guid_prefix.display().to_string(),
+            source_addr: info.source_addr.map(|addr| addr.to_string()),
+            unicast_locators: locator_strings(&info.unicast_locator_list),
+            multicast_locators: locator_strings(
+                info.multicast_locator_list.as_deref().unwrap_or(&[]),
+            ),
+        },
+    }
+}
+
+fn locator_strings(locators: &[Locator]) -> Vec<String> {
+    locators
+        .iter()
+        .map(|locator| locator.display().to_string())
+        .collect()
+}
+
+fn seconds(time: chrono::Duration) -> f64 {
+    time.num_microseconds().unwrap_or(i64::MAX) as f64 / 1e6
+}
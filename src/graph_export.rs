@@ -0,0 +1,113 @@
+//! Exports the topic graph in Graphviz DOT format.
+
+use crate::{
+    state::State,
+    utils::{GUIDExt, GuidPrefixExt},
+};
+use rustdds::GUID;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// Writes the participants, writers, readers and topics in the state
+/// to a DOT file. Writers are connected to the topics they write and
+/// topics are connected to the readers subscribing them.
+pub fn export_graph(state: &State, path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+
+    writeln!(out, "digraph ddshark {{")?;
+    writeln!(out, "    rankdir=LR;")?;
+
+    // Group the entities of each participant in a cluster.
+    let mut participants: Vec<_> = state.participants.iter().collect();
+    participants.sort_unstable_by_key(|(guid_prefix, _)| **guid_prefix);
+
+    for (index, (&guid_prefix, participant)) in participants.into_iter().enumerate() {
+        let label = match &participant.entity_name {
+            Some(name) => name.clone(),
+            None => guid_prefix.display().to_string(),
+        };
+
+        writeln!(out, "    subgraph cluster_{index} {{")?;
+        writeln!(out, "        label={};", quote(&label))?;
+
+        let mut writers: Vec<_> = participant.writers.iter().collect();
+        writers.sort_unstable_by_key(|(entity_id, _)| **entity_id);
+
+        for (&entity_id, writer) in writers {
+            let guid = GUID::new(guid_prefix, entity_id);
+            let label = writer
+                .entity_name
+                .clone()
+                .unwrap_or_else(|| guid.display().to_string());
+            writeln!(
+                out,
+                "        {} [label={}, shape=box];",
+                node_id(&guid),
+                quote(&label)
+            )?;
+        }
+
+        let mut readers: Vec<_> = participant.readers.iter().collect();
+        readers.sort_unstable_by_key(|(entity_id, _)| **entity_id);
+
+        for (&entity_id, reader) in readers {
+            let guid = GUID::new(guid_prefix, entity_id);
+            let label = reader
+                .entity_name
+                .clone()
+                .unwrap_or_else(|| guid.display().to_string());
+            writeln!(
+                out,
+                "        {} [label={}, shape=box, style=rounded];",
+                node_id(&guid),
+                quote(&label)
+            )?;
+        }
+
+        writeln!(out, "    }}")?;
+    }
+
+    // Add topics and the write/read edges.
+    let mut topics: Vec<_> = state.topics.iter().collect();
+    topics.sort_unstable_by_key(|(topic_name, _)| *topic_name);
+
+    for (index, (topic_name, topic)) in topics.into_iter().enumerate() {
+        let topic_id = format!("topic_{index}");
+        writeln!(
+            out,
+            "    {topic_id} [label={}, shape=ellipse];",
+            quote(topic_name)
+        )?;
+
+        let mut writers: Vec<_> = topic.writers.iter().collect();
+        writers.sort_unstable();
+        for guid in writers {
+            writeln!(out, "    {} -> {topic_id};", node_id(guid))?;
+        }
+
+        let mut readers: Vec<_> = topic.readers.iter().collect();
+        readers.sort_unstable();
+        for guid in readers {
+            writeln!(out, "    {topic_id} -> {};", node_id(guid))?;
+        }
+    }
+
+    writeln!(out, "}}")?;
+    out.flush()?;
+
+    Ok(())
+}
+
+/// Builds the node identifier of an entity.
+fn node_id(guid: &GUID) -> String {
+    format!("\"{}\"", guid.display())
+}
+
+/// Quotes and escapes a DOT string.
+fn quote(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{escaped}\"")
+}
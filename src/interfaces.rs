@@ -0,0 +1,46 @@
+//! Lists the network devices available for live captures.
+
+use crate::capabilities;
+use anyhow::Result;
+
+/// Prints the capture devices with their addresses and flags to
+/// stdout.
+pub fn print_interfaces() -> Result<()> {
+    // Devices may be hidden from unprivileged users, so give the hint
+    // along with the list.
+    if let Some(guidance) = capabilities::check_capture_capabilities() {
+        eprintln!("warning: {guidance}");
+    }
+
+    let devices = pcap::Device::list()?;
+    if devices.is_empty() {
+        println!("no capture devices found");
+        return Ok(());
+    }
+
+    for device in devices {
+        let mut flags = vec![];
+        if device.flags.is_up() {
+            flags.push("up");
+        }
+        if device.flags.is_running() {
+            flags.push("running");
+        }
+        if device.flags.is_loopback() {
+            flags.push("loopback");
+        }
+
+        println!("{} [{}]", device.name, flags.join(", "));
+        if let Some(desc) = &device.desc {
+            println!("    {desc}");
+        }
+        for address in &device.addresses {
+            match address.netmask {
+                Some(netmask) => println!("    {} netmask {netmask}", address.addr),
+                None => println!("    {}", address.addr),
+            }
+        }
+    }
+
+    Ok(())
+}
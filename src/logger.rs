@@ -9,7 +9,7 @@ use rustdds::{
 use serde::Serialize;
 
 use crate::{
-    state::{ReaderState, State, TopicState, WriterState},
+    state::{Abnormality, ReaderState, State, TopicState, WriterState},
     utils::{GUIDExt, GuidPrefixExt},
 };
 use std::{
@@ -22,6 +22,9 @@ use std::{
 
 type CsvWriter = csv::Writer<File>;
 
+/// The entries that the logger creates in the log directory.
+const LOG_DIR_ENTRIES: &[&str] = &["abnormalities.csv", "topic", "participant"];
+
 #[derive(Debug)]
 pub struct Logger {
     log_dir: PathBuf,
@@ -29,36 +32,62 @@ pub struct Logger {
     participant_dir: PathBuf,
     participants: HashMap<GuidPrefix, ParticipantLogger>,
     topics: HashMap<String, TopicLogger>,
+    abnormality_writer: CsvWriter,
+    /// The number of abnormalities already written, including the
+    /// ones dropped from the state since.
+    n_logged_abnormalities: usize,
 }
 
 impl Logger {
-    pub fn new() -> io::Result<Self> {
-        let cwd = env::current_dir().unwrap();
-        let log_dir = cwd.join("ddshark");
+    /// Creates the log directory. An existing directory is renamed to
+    /// `<dir>.old.N`, or removed if `overwrite` is set. Only a
+    /// directory written by a previous logger is removed.
+    pub fn new(log_dir: &Path, overwrite: bool) -> io::Result<Self> {
+        let log_dir = env::current_dir()?.join(log_dir);
 
         if log_dir.exists() {
-            let old_dir = (1..)
-                .find_map(|idx| {
-                    let old_dir = cwd.join(format!("ddshark.old.{idx}"));
-                    let ok = !old_dir.exists();
-                    ok.then_some(old_dir)
-                })
-                .unwrap();
-            fs::rename(&log_dir, old_dir).unwrap();
+            if overwrite {
+                if !is_log_dir(&log_dir)? {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!(
+                            "refuse to overwrite {}, which is not a ddshark log directory",
+                            log_dir.display()
+                        ),
+                    ));
+                }
+                fs::remove_dir_all(&log_dir)?;
+            } else {
+                let mut old_name = log_dir.as_os_str().to_owned();
+                old_name.push(".old.");
+                let old_dir = (1..)
+                    .map(|idx| {
+                        let mut old_name = old_name.clone();
+                        old_name.push(idx.to_string());
+                        PathBuf::from(old_name)
+                    })
+                    .find(|old_dir| !old_dir.exists())
+                    .unwrap();
+                fs::rename(&log_dir, old_dir)?;
+            }
         }
 
         let topic_dir = log_dir.join("topic");
         let participant_dir = log_dir.join("participant");
 
-        fs::create_dir(&log_dir).unwrap();
-        fs::create_dir(&participant_dir).unwrap();
-        fs::create_dir(&topic_dir).unwrap();
+        fs::create_dir_all(&log_dir)?;
+        fs::create_dir(&participant_dir)?;
+        fs::create_dir(&topic_dir)?;
+        let abnormality_writer = create_writer(log_dir.join("abnormalities.csv"))?;
+
         Ok(Self {
             log_dir,
             topic_dir,
             participant_dir,
             participants: HashMap::new(),
             topics: HashMap::new(),
+            abnormality_writer,
+            n_logged_abnormalities: 0,
         })
     }
 
@@ -76,9 +105,9 @@ impl Logger {
                         .join(format!("{}", guid_prefix.display()));
                     let writer_dir = participant_dir.join("writers");
                     let reader_dir = participant_dir.join("readers");
-                    fs::create_dir(&participant_dir).unwrap();
-                    fs::create_dir(&writer_dir).unwrap();
-                    fs::create_dir(&reader_dir).unwrap();
+                    fs::create_dir(&participant_dir)?;
+                    fs::create_dir(&writer_dir)?;
+                    fs::create_dir(&reader_dir)?;
 
                     let logger = ParticipantLogger {
                         writer_dir,
@@ -100,7 +129,7 @@ impl Logger {
                         let log_path = part_logger
                             .writer_dir
                             .join(format!("{}.csv", guid.display()));
-                        let writer = create_writer(log_path).unwrap();
+                        let writer = create_writer(log_path)?;
                         let logger = WriterLogger { writer };
                         entry.insert(logger)
                     }
@@ -132,7 +161,7 @@ impl Logger {
                     avg_bitrate,
                     topic_name,
                 };
-                writer_logger.writer.serialize(record).unwrap();
+                writer_logger.writer.serialize(record)?;
             }
 
             for (&reader_id, reader_state) in &part_state.readers {
@@ -144,7 +173,7 @@ impl Logger {
                         let log_path = part_logger
                             .reader_dir
                             .join(format!("{}.csv", guid.display()));
-                        let writer = create_writer(log_path).unwrap();
+                        let writer = create_writer(log_path)?;
                         let logger = ReaderLogger { writer };
                         entry.insert(logger)
                     }
@@ -165,7 +194,7 @@ impl Logger {
                     total_acknack_count,
                     avg_acknack_rate,
                 };
-                reader_logger.writer.serialize(record).unwrap();
+                reader_logger.writer.serialize(record)?;
             }
 
             for (topic_name, topic_state) in &state.topics {
@@ -178,6 +207,9 @@ impl Logger {
                     ref bit_rate_stat,
                     total_acknack_count,
                     ref acknack_rate_stat,
+                    written_sn_sum,
+                    acked_sn_sum,
+                    ..
                 } = *topic_state;
                 let n_readers = readers.len();
                 let n_writers = writers.len();
@@ -188,7 +220,7 @@ impl Logger {
                         let name = topic_name.replace('/', "|");
                         let file_name = format!("{name}.csv");
                         let path = self.topic_dir.join(file_name);
-                        let writer = create_writer(path).unwrap();
+                        let writer = create_writer(path)?;
                         let logger = TopicLogger { writer };
 
                         entry.insert(logger)
@@ -209,16 +241,46 @@ impl Logger {
                     avg_msgrate,
                     avg_bitrate,
                     avg_acknack_rate,
+                    written_sn_sum,
+                    acked_sn_sum,
                 };
 
-                topic_logger.writer.serialize(record).unwrap();
+                topic_logger.writer.serialize(record)?;
             }
         }
 
+        // The abnormalities are only appended to the state, so only
+        // the ones since the last save are written. The indices are
+        // shifted by the ones dropped from the front.
+        let start = self
+            .n_logged_abnormalities
+            .saturating_sub(state.dropped_abnormality_count);
+        let new_abnormalities = state.abnormalities.get(start..).unwrap_or(&[]);
+        for abnormality in new_abnormalities {
+            let Abnormality {
+                when,
+                writer_guid,
+                reader_guid,
+                ref topic_name,
+                ref desc,
+                ..
+            } = *abnormality;
+
+            let record = AbnormalityRecord {
+                when: when.with_timezone(&Utc),
+                writer_guid: writer_guid.map(|guid| guid.display().to_string()),
+                reader_guid: reader_guid.map(|guid| guid.display().to_string()),
+                topic_name: topic_name.clone(),
+                desc: desc.clone(),
+            };
+            self.abnormality_writer.serialize(record)?;
+        }
+        self.n_logged_abnormalities = state.dropped_abnormality_count + state.abnormalities.len();
+
         Ok(())
     }
 
-    pub fn close(self) -> io::Result<()> {
+    pub fn close(mut self) -> io::Result<()> {
         for (_, part) in self.participants {
             for (_, mut writer) in part.writers {
                 writer.writer.flush()?;
@@ -233,6 +295,8 @@ impl Logger {
             topic.writer.flush()?;
         }
 
+        self.abnormality_writer.flush()?;
+
         Ok(())
     }
 }
@@ -297,13 +361,42 @@ struct TopicRecord {
     pub avg_msgrate: f64,
     pub avg_bitrate: f64,
     pub avg_acknack_rate: f64,
+    pub written_sn_sum: i64,
+    pub acked_sn_sum: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct AbnormalityRecord {
+    #[serde(with = "chrono::serde::ts_microseconds")]
+    pub when: DateTime<Utc>,
+    pub writer_guid: Option<String>,
+    pub reader_guid: Option<String>,
+    pub topic_name: Option<String>,
+    pub desc: String,
 }
 
 fn create_writer<P>(path: P) -> io::Result<CsvWriter>
 where
     P: AsRef<Path>,
 {
-    let writer = File::create(path).unwrap();
+    let writer = File::create(path)?;
     let csv_wtr = csv::Writer::from_writer(writer);
     Ok(csv_wtr)
 }
+
+/// Checks if the directory only contains the entries written by the
+/// logger.
+fn is_log_dir(dir: &Path) -> io::Result<bool> {
+    if !dir.join("abnormalities.csv").is_file() {
+        return Ok(false);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        if !LOG_DIR_ENTRIES.iter().any(|&expect| name == expect) {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
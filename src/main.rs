@@ -1,11 +1,19 @@
+mod benchmark;
+mod capabilities;
 mod config;
+mod event_stream;
+mod graph_export;
+mod interfaces;
 mod logger;
 mod message;
 mod opts;
 mod otlp;
+mod prometheus;
 mod rtps;
 mod rtps_watcher;
 mod state;
+mod state_cleanup;
+mod summary;
 mod ui;
 mod updater;
 mod utils;
@@ -13,30 +21,64 @@ mod utils;
 // mod dds;
 
 use crate::{opts::Opts, state::State};
-use anyhow::{bail, Result};
-use clap::Parser;
+use anyhow::{bail, Context, Result};
 use futures::future;
-use rtps::PacketSource;
+use rtps::{CaptureStats, OverflowStrategy, PacketSource, ReplayControl};
 use std::{
     future::Future,
-    io, mem,
+    io, mem, process,
     sync::{Arc, Mutex},
     thread,
     time::Duration,
 };
-use tokio::runtime::Runtime;
+use tokio::{runtime::Runtime, select};
 use tokio_util::sync::CancellationToken;
 use ui::Tui;
 
+/// The exit code when `--fail-on` is triggered.
+const ANOMALY_EXIT_CODE: i32 = 2;
+
 fn main() -> Result<()> {
-    let opts = Opts::parse();
+    let opts = Opts::load()?;
 
     // If TUI is disabled, show debug messages.
     if opts.no_tui {
-        tracing_subscriber::fmt().with_writer(io::stderr).init();
+        let subscriber = tracing_subscriber::fmt().with_writer(io::stderr);
+
+        if opts.log_json {
+            subscriber.json().init();
+        } else {
+            subscriber.init();
+        }
+    }
+
+    // The TUI owns the terminal, so the events cannot share stdout
+    // with it.
+    if opts.writes_events_to_stdout() && !opts.no_tui {
+        bail!("--events-jsonl - requires --no-tui");
     }
 
-    let state = Arc::new(Mutex::new(State::default()));
+    // Reject a malformed capture filter before the TUI starts.
+    rtps::validate_filter(&opts.filter)
+        .with_context(|| format!("invalid capture filter '{}'", opts.filter))?;
+
+    if opts.list_interfaces {
+        return interfaces::print_interfaces();
+    }
+
+    if opts.benchmark {
+        return benchmark::run_benchmark(&opts);
+    }
+
+    // Warn before the TUI starts if a live capture may not be
+    // permitted. Opening the device reports the actual failure.
+    if opts.file.is_none() {
+        if let Some(guidance) = capabilities::check_capture_capabilities() {
+            eprintln!("warning: {guidance}");
+        }
+    }
+
+    let state = Arc::new(Mutex::new(State::new(opts.rate_window())));
     let cancel_token = CancellationToken::new();
 
     // Set Ctrl-C handler
@@ -48,6 +90,12 @@ fn main() -> Result<()> {
     }
 
     let (tx, rx) = flume::bounded(64);
+    let replay = if opts.replays_file() {
+        ReplayControl::with_speed(opts.replay_speed)
+    } else {
+        ReplayControl::new()
+    };
+    let capture_stats = CaptureStats::new();
 
     let backend_handle = {
         let opts = opts.clone();
@@ -59,12 +107,23 @@ fn main() -> Result<()> {
                 (Some(_), Some(_)) => {
                     bail!("--file and --interface cannot be specified simultaneously")
                 }
+                (Some(_), None) if opts.reads_stdin() => PacketSource::Stdin,
                 (Some(file), None) => PacketSource::File { path: file.clone() },
                 (None, Some(interface)) => PacketSource::Interface(interface.clone()),
                 (None, None) => PacketSource::Default,
             };
 
-            let watcher = rtps_watcher::rtps_watcher(packet_src, tx.clone(), cancel_token.clone());
+            let capture_config = opts.capture_config();
+
+            let watcher = rtps_watcher::rtps_watcher(
+                packet_src,
+                tx.clone(),
+                (opts.overflow == OverflowStrategy::DropOldest).then(|| rx.clone()),
+                cancel_token.clone(),
+                capture_config,
+                replay.clone(),
+                capture_stats.clone(),
+            );
             spawn(cancel_token.clone(), watcher)
         };
 
@@ -72,11 +131,53 @@ fn main() -> Result<()> {
         let updater_task = {
             let state = state.clone();
 
-            let updater = crate::updater::Updater::new(rx, cancel_token.clone(), state, &opts)?;
+            let updater = crate::updater::Updater::new(
+                rx,
+                cancel_token.clone(),
+                state,
+                replay.clone(),
+                capture_stats.clone(),
+                &opts,
+            )?;
             spawn(cancel_token.clone(), updater.run())
         };
 
-        let future = future::try_join(rpts_watcher_task, updater_task);
+        // Start the Prometheus endpoint if requested
+        let prometheus_task = {
+            let state = state.clone();
+            let cancel_token = cancel_token.clone();
+            let addr = opts.prometheus_listen;
+
+            async move {
+                match addr {
+                    Some(addr) => prometheus::serve_metrics(addr, state, cancel_token).await,
+                    None => Ok(()),
+                }
+            }
+        };
+
+        // Stop the capture after the duration if requested
+        let duration_task = {
+            let cancel_token = cancel_token.clone();
+            let duration = opts.duration.map(Duration::from_secs);
+
+            async move {
+                if let Some(duration) = duration {
+                    select! {
+                        _ = cancel_token.cancelled() => {}
+                        _ = tokio::time::sleep(duration) => cancel_token.cancel(),
+                    }
+                }
+                Ok::<_, anyhow::Error>(())
+            }
+        };
+
+        let future = future::try_join4(
+            rpts_watcher_task,
+            updater_task,
+            prometheus_task,
+            duration_task,
+        );
 
         thread::spawn(move || -> Result<()> {
             let rt = Runtime::new()?;
@@ -87,8 +188,14 @@ fn main() -> Result<()> {
 
     // Run TUI
     if !opts.no_tui {
-        let tick_dur = Duration::from_secs(1) / opts.refresh_rate;
-        let tui = Tui::new(tick_dur, tx, cancel_token, state);
+        let tui = Tui::new(
+            &opts,
+            tx,
+            cancel_token,
+            state.clone(),
+            replay,
+            capture_stats,
+        );
         tui.run()?;
     } else {
         mem::drop(tx);
@@ -97,6 +204,37 @@ fn main() -> Result<()> {
     // Finalize
     backend_handle.join().unwrap()?;
 
+    if opts.no_tui {
+        let state = state.lock().unwrap();
+
+        // Keep the JSON Lines on stdout intact.
+        if opts.writes_events_to_stdout() {
+            summary::print_summary(&mut io::stderr(), &state, opts.summary_format)?;
+        } else {
+            summary::print_summary(&mut io::stdout(), &state, opts.summary_format)?;
+        }
+    }
+
+    if let Some(path) = &opts.export_graph {
+        let state = state.lock().unwrap();
+        graph_export::export_graph(&state, path)?;
+    }
+
+    // Report detected abnormalities through the exit code.
+    if let Some(min_severity) = opts.fail_on {
+        let state = state.lock().unwrap();
+        let failed = state
+            .abnormalities
+            .iter()
+            .map(|report| report.severity)
+            .chain(state.dropped_max_severity)
+            .any(|severity| severity >= min_severity);
+
+        if failed {
+            process::exit(ANOMALY_EXIT_CODE);
+        }
+    }
+
     Ok(())
 }
 
@@ -1,6 +1,7 @@
 //! Messages exchanged within the program.
 
-use etherparse::{Ethernet2Header, Ipv4Header, UdpHeader, VlanHeader};
+use crate::rtps::VendorParams;
+use etherparse::{Ethernet2Header, Ipv4Header, Ipv6Header, UdpHeader, VlanHeader};
 use rustdds::{
     discovery::{
         sedp_messages::{DiscoveredReaderData, DiscoveredTopicData, DiscoveredWriterData},
@@ -13,12 +14,14 @@ use rustdds::{
     },
     SequenceNumber, Timestamp, GUID,
 };
-use std::time::Instant;
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Instant,
+};
 
 /// The message that is sent to the updater.
 #[derive(Debug, Clone)]
 pub enum UpdateEvent {
-    RtpsMsg(RtpsMsgEvent),
     RtpsSubmsg(RtpsSubmsgEvent),
     ParticipantInfo(ParticipantInfo),
     Tick(TickEvent),
@@ -70,6 +73,22 @@ pub enum RtpsSubmsgEventKind {
     HeartbeatFrag(HeartbeatFragEvent),
 }
 
+impl RtpsSubmsgEventKind {
+    /// Gets the writer that the submessage is sent by or addressed
+    /// to.
+    pub fn writer_guid(&self) -> GUID {
+        match self {
+            Self::Data(event) => event.writer_guid,
+            Self::DataFrag(event) => event.writer_guid,
+            Self::Gap(event) => event.writer_guid,
+            Self::AckNack(event) => event.writer_guid,
+            Self::NackFrag(event) => event.writer_guid,
+            Self::Heartbeat(event) => event.writer_guid,
+            Self::HeartbeatFrag(event) => event.writer_guid,
+        }
+    }
+}
+
 impl From<NackFragEvent> for RtpsSubmsgEventKind {
     fn from(v: NackFragEvent) -> Self {
         Self::NackFrag(v)
@@ -112,23 +131,40 @@ impl From<AckNackEvent> for RtpsSubmsgEventKind {
     }
 }
 
-/// The event records the receipt of a RTPS packet.
-#[derive(Debug, Clone)]
-pub struct RtpsMsgEvent {
-    pub headers: RtpsPacketHeaders,
-}
-
 /// The dissected headers from a RTPS packet.
 #[derive(Debug, Clone)]
 pub struct RtpsPacketHeaders {
     pub pcap_header: pcap::PacketHeader,
     pub link: Option<Ethernet2Header>,
     pub vlan: Option<VlanHeader>,
-    pub ipv4: Ipv4Header,
+    pub ip: IpHeaders,
     pub udp: UdpHeader,
     pub ts: chrono::Duration,
 }
 
+/// The IPv4 or IPv6 header of a RTPS packet.
+#[derive(Debug, Clone)]
+pub enum IpHeaders {
+    V4(Ipv4Header),
+    V6(Ipv6Header),
+}
+
+impl IpHeaders {
+    pub fn source_addr(&self) -> IpAddr {
+        match self {
+            IpHeaders::V4(header) => Ipv4Addr::from(header.source).into(),
+            IpHeaders::V6(header) => Ipv6Addr::from(header.source).into(),
+        }
+    }
+
+    pub fn destination_addr(&self) -> IpAddr {
+        match self {
+            IpHeaders::V4(header) => Ipv4Addr::from(header.destination).into(),
+            IpHeaders::V6(header) => Ipv6Addr::from(header.destination).into(),
+        }
+    }
+}
+
 /// The typed data payload decoded from a RTPS submessage.
 #[derive(Debug, Clone)]
 pub enum DataPayload {
@@ -214,7 +250,42 @@ pub struct DataEvent {
     pub writer_guid: GUID,
     pub writer_sn: SequenceNumber,
     pub payload_size: usize,
+    /// The destination of the packet carrying the submessage.
+    pub destination: Locator,
     pub payload: Option<DataPayload>,
+    /// Vendor-specific parameters found in discovery payloads.
+    pub vendor_params: Option<VendorParams>,
+    pub inline_qos: Option<InlineQos>,
+}
+
+/// The decoded inline QoS parameters of a DATA submessage.
+#[derive(Debug, Clone, Default)]
+pub struct InlineQos {
+    pub status_info: Option<StatusInfo>,
+    /// The PID_KEY_HASH parameter identifying the instance.
+    pub key_hash: Option<[u8; 16]>,
+}
+
+/// The flags carried by the PID_STATUS_INFO inline QoS parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusInfo {
+    pub disposed: bool,
+    pub unregistered: bool,
+    pub filtered: bool,
+}
+
+impl StatusInfo {
+    /// Decodes the 4-byte parameter value. The flags are stored in
+    /// the last byte regardless of the endianness.
+    pub fn from_bytes(value: &[u8]) -> Option<Self> {
+        let flags = *value.get(3)?;
+
+        Some(Self {
+            disposed: flags & 0x1 != 0,
+            unregistered: flags & 0x2 != 0,
+            filtered: flags & 0x4 != 0,
+        })
+    }
 }
 
 /// The events records the receipt of a DATA-FRAG submessage.
@@ -228,6 +299,8 @@ pub struct DataFragEvent {
     pub fragment_size: u16,
     pub payload_size: usize,
     pub payload_hash: u64,
+    /// The destination of the packet carrying the submessage.
+    pub destination: Locator,
 }
 
 /// The events records the receipt of a GAP submessage.
@@ -246,6 +319,8 @@ pub struct NackFragEvent {
     pub reader_guid: GUID,
     pub writer_sn: SequenceNumber,
     pub count: i32,
+    /// The fragment numbers requested by the reader.
+    pub fragment_numbers: Vec<u32>,
 }
 
 /// Records the GUID prefix and locators of an observed participant.
@@ -255,4 +330,7 @@ pub struct ParticipantInfo {
     pub guid_prefix: GuidPrefix,
     pub unicast_locator_list: Vec<Locator>,
     pub multicast_locator_list: Option<Vec<Locator>>,
+    /// The source address of the packet. It is `None` if the packet
+    /// is relayed on behalf of another participant by INFO_SRC.
+    pub source_addr: Option<IpAddr>,
 }
@@ -1,35 +1,490 @@
 //! Command-line options.
+//!
+//! Common options can also be given by `DDSHARK_*` environment
+//! variables, which take effect when the option is absent on the
+//! command line.
 
-use clap::Parser;
-use std::path::PathBuf;
+use crate::{
+    config::TICK_INTERVAL,
+    rtps::{CaptureConfig, OverflowStrategy, PayloadFraming, TimeSource},
+    state::Severity,
+    summary::SummaryFormat,
+};
+use anyhow::{bail, Context, Result};
+use clap::{parser::ValueSource, CommandFactory, FromArgMatches, Parser};
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// A byte pattern given in hex. The alias keeps clap from treating
+/// the option as a list of bytes.
+type Signature = Vec<u8>;
 
 /// A quick DDS sniffer.
 #[derive(Debug, Clone, Parser)]
 pub struct Opts {
-    #[clap(long, default_value = "4")]
+    /// Load options from a TOML file. Options given on the command
+    /// line or by environment variables take precedence.
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+
+    #[clap(long, env = "DDSHARK_REFRESH_RATE", default_value = "4")]
     pub refresh_rate: u32,
 
-    /// The input packet dump to be inspected.
-    #[clap(short = 'f', long)]
+    /// The input packet dump to be inspected. Pass `-` to read it
+    /// from the standard input.
+    #[clap(short = 'f', long, env = "DDSHARK_FILE")]
     pub file: Option<PathBuf>,
 
     /// The network interface to be inspected.
-    #[clap(short = 'i', long)]
+    #[clap(short = 'i', long, env = "DDSHARK_INTERFACE")]
     pub interface: Option<String>,
 
+    /// Enable promiscuous mode on the capture interface.
+    #[clap(long, overrides_with = "no_promisc")]
+    pub promisc: bool,
+
+    /// Disable promiscuous mode on the capture interface. This is the
+    /// default.
+    #[clap(long, overrides_with = "promisc")]
+    pub no_promisc: bool,
+
+    /// The BPF capture filter, e.g. "udp portrange 7400-7500". Pass
+    /// an empty string to capture all packets.
+    #[clap(long, default_value = "udp")]
+    pub filter: String,
+
+    /// Only watch the DDS domain with this ID, judged by the UDP
+    /// ports of the default RTPS port mapping. The check is done after
+    /// the --filter BPF filter. For deployments with custom ports,
+    /// leave it unset and select the ports with --filter instead.
+    #[clap(long)]
+    pub domain: Option<u16>,
+
+    /// The seconds to wait for missing IPv4 fragments before dropping
+    /// the incomplete datagram.
+    #[clap(long, default_value = "5")]
+    pub reassembly_timeout: u64,
+
+    /// The time base for rates and timelines. The RTPS time depends
+    /// on the sender's clock, while the pcap time includes the
+    /// network delay.
+    #[clap(long, value_enum, default_value = "pcap")]
+    pub time_source: TimeSource,
+
     /// Enable OTLP logging.
     #[clap(short = 'o', long)]
     pub otlp: bool,
 
     /// Set the OTLP endpoint.
-    #[clap(short = 'e', long, default_value = "http://localhost:4317")]
+    #[clap(
+        short = 'e',
+        long,
+        env = "DDSHARK_OTLP_ENDPOINT",
+        default_value = "http://localhost:4317"
+    )]
     pub otlp_endpoint: Option<String>,
 
+    /// Apply up to 64 queued events under one lock of the state, which
+    /// reduces the lock contention with the TUI on busy networks.
+    #[clap(long)]
+    pub batch: bool,
+
+    /// The milliseconds to wait for the updater to accept an event.
+    /// Events are dropped after the timeout when the updater cannot
+    /// keep up with the traffic.
+    #[clap(long, default_value = "100")]
+    pub send_timeout: u64,
+
+    /// What to do with new events when the updater falls behind.
+    /// drop-oldest keeps the freshest view of live captures, while
+    /// block loses no events but may stall the capture.
+    #[clap(long, value_enum, default_value = "drop-newest")]
+    pub overflow: OverflowStrategy,
+
+    /// Set the OTLP service name. Defaults to "dds.traffic.HOSTNAME".
+    #[clap(long)]
+    pub otlp_service_name: Option<String>,
+
+    /// Add a resource attribute to the OTLP traces in the form
+    /// KEY=VALUE. Can be specified multiple times.
+    #[clap(long, value_parser = parse_key_value)]
+    pub otlp_resource: Vec<(String, String)>,
+
     /// Disable text user interface.
     #[clap(long)]
     pub no_tui: bool,
 
+    /// Print diagnostic messages in JSON when the text user interface
+    /// is disabled.
+    #[clap(long)]
+    pub log_json: bool,
+
+    /// Write the decoded events as JSON Lines to the file, or to
+    /// stdout if the path is `-`. Writing to stdout requires
+    /// --no-tui, and the summary is then printed to stderr.
+    #[clap(long)]
+    pub events_jsonl: Option<PathBuf>,
+
+    /// The format of the summary printed on exit when the text user
+    /// interface is disabled.
+    #[clap(long, value_enum, default_value = "text")]
+    pub summary_format: SummaryFormat,
+
     /// Start logging when the program starts.
     #[clap(long)]
     pub log_on_start: bool,
+
+    /// The directory where logs are written.
+    #[clap(long, default_value = "ddshark")]
+    pub log_dir: PathBuf,
+
+    /// Remove an existing log directory instead of renaming it to
+    /// `<dir>.old.N`. A directory not written by ddshark is never
+    /// removed.
+    #[clap(long)]
+    pub log_overwrite: bool,
+
+    /// Write the graph of participants, topics, writers and readers
+    /// in Graphviz DOT format to the path on exit.
+    #[clap(long)]
+    pub export_graph: Option<PathBuf>,
+
+    /// Exit after the last packet of the input file is processed.
+    #[clap(long)]
+    pub exit_on_eof: bool,
+
+    /// Exit after capturing for this many seconds. An input file also
+    /// stops at its end.
+    #[clap(long)]
+    pub duration: Option<u64>,
+
+    /// The number of bytes of the vendor-specific header to strip
+    /// before each RTPS message.
+    #[clap(long, default_value = "0")]
+    pub strip_prefix: usize,
+
+    /// The number of bytes of the trailer, e.g. an integrity check,
+    /// to strip after each RTPS message.
+    #[clap(long, default_value = "0")]
+    pub strip_suffix: usize,
+
+    /// Search for the hex-encoded signature in the UDP payload and
+    /// decode the RTPS message from there, e.g. 52545053 for "RTPS".
+    #[clap(long, value_parser = parse_hex_signature)]
+    pub rtps_signature: Option<Signature>,
+
+    /// Serve the statistics for Prometheus at http://<ADDR>/metrics,
+    /// e.g. 0.0.0.0:9100.
+    #[clap(long)]
+    pub prometheus_listen: Option<SocketAddr>,
+
+    /// Process the input file as fast as possible without the TUI and
+    /// print the decode and update throughput.
+    #[clap(long, requires = "file")]
+    pub benchmark: bool,
+
+    /// Print the network devices that can be passed to `--interface`
+    /// and exit.
+    #[clap(long)]
+    pub list_interfaces: bool,
+
+    /// The speed of the offline replay relative to the capture time.
+    /// Rates are still computed in capture time.
+    #[clap(long, default_value = "1", value_parser = parse_replay_speed)]
+    pub replay_speed: f64,
+
+    /// The step in seconds to fast-forward an offline replay.
+    #[clap(long, default_value = "10")]
+    pub seek_step: u32,
+
+    /// Exit with status 2 if any abnormality at or above the given
+    /// severity was recorded.
+    #[clap(long, value_enum)]
+    pub fail_on: Option<Severity>,
+
+    /// Report an abnormality when the written samples on a topic
+    /// exceed the acknowledged samples by this amount.
+    #[clap(long, default_value = "1000")]
+    pub max_ack_lag: i64,
+
+    /// Show a hint when packets are captured but none of them is RTPS
+    /// after this many seconds.
+    #[clap(long, default_value = "5")]
+    pub no_rtps_hint_delay: u64,
+
+    /// Save the recognized RTPS packets to a pcap file, e.g. to attach
+    /// a trimmed capture to a bug report.
+    #[clap(long)]
+    pub write_pcap: Option<PathBuf>,
+
+    /// Start on the Topics tab with the given topic selected, and
+    /// list its writers and readers as soon as it is discovered.
+    #[clap(long)]
+    pub focus_topic: Option<String>,
+
+    /// Highlight the writers whose bit rate exceeds this value in bits
+    /// per second.
+    #[clap(long)]
+    pub highlight_bitrate: Option<f64>,
+
+    /// Highlight the participants, writers and readers silent for
+    /// longer than this many seconds.
+    #[clap(long, default_value = "10")]
+    pub stale_age: f64,
+
+    /// The maximum number of rows rendered in each tab.
+    #[clap(long, env = "DDSHARK_MAX_ROWS")]
+    pub max_rows: Option<usize>,
+
+    /// Do not restore or save the hidden columns and the sort order
+    /// of the tabs in ~/.config/ddshark/ui.toml.
+    #[clap(long)]
+    pub no_ui_state: bool,
+
+    /// Report an abnormality when a writer disposes or unregisters
+    /// instances faster than this rate per second.
+    #[clap(long, default_value = "100")]
+    pub max_disposal_rate: f64,
+
+    /// Report an abnormality when the payload size of a sample
+    /// deviates from the writer's mean by more than this many
+    /// standard deviations.
+    #[clap(long, default_value = "6")]
+    pub payload_size_sigma: f64,
+
+    /// The seconds between the removals of inactive participants,
+    /// empty topics and stale fragmented messages.
+    #[clap(long, default_value = "10")]
+    pub cleanup_interval: u64,
+
+    /// Remove a participant without readers and writers after it is
+    /// silent for this many seconds.
+    #[clap(long, default_value = "60")]
+    pub cleanup_retention: u64,
+
+    /// The seconds over which the message and bit rates are averaged.
+    /// Defaults to the UI tick interval.
+    #[clap(long, value_parser = parse_rate_window)]
+    pub rate_window: Option<f64>,
+
+    /// The maximum number of abnormalities kept. The oldest ones are
+    /// dropped beyond the limit.
+    #[clap(long, default_value = "1000")]
+    pub max_abnormalities: usize,
+
+    /// The maximum number of readers and writers tracked on a
+    /// participant. Endpoints beyond the limit are ignored.
+    #[clap(long, default_value = "10000")]
+    pub max_endpoints: usize,
+
+    /// Report an abnormality when a participant sends more SPDP
+    /// announcements than this number within its lease duration.
+    #[clap(long, default_value = "20")]
+    pub max_spdp_per_lease: usize,
+
+    /// Report an abnormality when a reader keeps requesting the same
+    /// missing sample for longer than this many seconds.
+    #[clap(long, default_value = "10")]
+    pub max_missing_age: f64,
+
+    /// Report an abnormality when the ratio of likely retransmitted
+    /// bytes on a topic exceeds this value.
+    #[clap(long, default_value = "0.2")]
+    pub max_retx_ratio: f64,
+
+    /// Set the bandwidth budget of a topic in bits per second, in the
+    /// form NAME=BPS. Use `*` as the name to set the budget of the
+    /// remaining topics. Can be specified multiple times.
+    #[clap(long, value_parser = parse_topic_budget)]
+    pub topic_budget: Vec<(String, f64)>,
+
+    /// Set the total bandwidth budget of all topics in bits per
+    /// second.
+    #[clap(long)]
+    pub total_budget: Option<f64>,
+}
+
+impl Opts {
+    /// Checks if the packet dump is read from the standard input.
+    pub fn reads_stdin(&self) -> bool {
+        self.file.as_deref() == Some(Path::new("-"))
+    }
+
+    /// Checks if the decoded events are written to the standard
+    /// output.
+    pub fn writes_events_to_stdout(&self) -> bool {
+        self.events_jsonl.as_deref() == Some(Path::new("-"))
+    }
+
+    /// Checks if a packet dump file is replayed, which can be paced
+    /// and fast-forwarded unlike a pipe.
+    pub fn replays_file(&self) -> bool {
+        self.file.is_some() && !self.reads_stdin()
+    }
+
+    /// Gets the window over which the rates are averaged.
+    pub fn rate_window(&self) -> chrono::Duration {
+        let window = match self.rate_window {
+            Some(secs) => Duration::from_secs_f64(secs),
+            None => TICK_INTERVAL,
+        };
+        chrono::Duration::from_std(window).unwrap()
+    }
+
+    /// Gets the packet capture parameters.
+    pub fn capture_config(&self) -> CaptureConfig {
+        CaptureConfig {
+            promisc: self.promisc && !self.no_promisc,
+            time_source: self.time_source,
+            exit_on_eof: self.exit_on_eof || self.duration.is_some(),
+            framing: PayloadFraming {
+                strip_prefix: self.strip_prefix,
+                strip_suffix: self.strip_suffix,
+                signature: self.rtps_signature.clone(),
+            },
+            reassembly_timeout: Duration::from_secs(self.reassembly_timeout),
+            filter: self.filter.clone(),
+            domain: self.domain,
+            write_pcap: self.write_pcap.clone(),
+            send_timeout: Duration::from_millis(self.send_timeout),
+            overflow: self.overflow,
+        }
+    }
+
+    /// Parses the options from the command line and the config file
+    /// given by `--config`.
+    ///
+    /// The precedence is defaults < config file < command line and
+    /// environment variables. The file entries are fed to the same
+    /// parser as command line arguments, so the keys are option names
+    /// and the values are written as on the command line, e.g.
+    /// `topic_budget = ["rt/chatter=1000000"]`.
+    pub fn load() -> Result<Self> {
+        let args: Vec<OsString> = env::args_os().collect();
+        let command = Self::command();
+        let matches = command.clone().get_matches_from(&args);
+        let opts = Self::from_arg_matches(&matches)?;
+
+        let Some(path) = &opts.config else {
+            return Ok(opts);
+        };
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("unable to read config file {}", path.display()))?;
+        let table: toml::Table = text
+            .parse()
+            .with_context(|| format!("unable to parse config file {}", path.display()))?;
+
+        let mut file_args: Vec<OsString> = vec![];
+
+        for (key, value) in table {
+            let id = key.replace('-', "_");
+            let is_known = id != "config"
+                && command
+                    .get_arguments()
+                    .any(|arg| arg.get_id().as_str() == id);
+            if !is_known {
+                bail!("unknown option '{key}' in config file {}", path.display());
+            }
+
+            // Skip the options given explicitly.
+            let explicit = matches!(
+                matches.value_source(&id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            );
+            if explicit {
+                continue;
+            }
+
+            let flag = format!("--{}", id.replace('_', "-"));
+            let values = match value {
+                toml::Value::Boolean(true) => {
+                    file_args.push(flag.into());
+                    continue;
+                }
+                toml::Value::Boolean(false) => continue,
+                toml::Value::Array(items) => items,
+                value => vec![value],
+            };
+
+            for value in values {
+                let value = match value {
+                    toml::Value::String(text) => text,
+                    toml::Value::Integer(_) | toml::Value::Float(_) => value.to_string(),
+                    _ => bail!(
+                        "unsupported value for '{key}' in config file {}",
+                        path.display()
+                    ),
+                };
+                file_args.push(flag.clone().into());
+                file_args.push(value.into());
+            }
+        }
+
+        // Insert the file options after the program name.
+        let (program, cli_args) = args.split_at(1.min(args.len()));
+        let args: Vec<OsString> = program
+            .iter()
+            .cloned()
+            .chain(file_args)
+            .chain(cli_args.iter().cloned())
+            .collect();
+
+        let opts = Self::try_parse_from(args).unwrap_or_else(|err| err.exit());
+        Ok(opts)
+    }
+}
+
+fn parse_topic_budget(text: &str) -> Result<(String, f64), String> {
+    let (name, bps) = text
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expect NAME=BPS, but get '{text}'"))?;
+    let bps: f64 = bps
+        .parse()
+        .map_err(|_| format!("invalid bit rate '{bps}'"))?;
+    Ok((name.to_string(), bps))
+}
+
+fn parse_key_value(text: &str) -> Result<(String, String), String> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("expect KEY=VALUE, but get '{text}'"))?;
+    if key.is_empty() {
+        return Err(format!("empty key in '{text}'"));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn parse_hex_signature(text: &str) -> Result<Signature, String> {
+    let signature =
+        hex::decode(text).map_err(|err| format!("invalid hex signature '{text}': {err}"))?;
+    if signature.is_empty() {
+        return Err("the signature must not be empty".to_string());
+    }
+    Ok(signature)
+}
+
+fn parse_rate_window(text: &str) -> Result<f64, String> {
+    let secs: f64 = text
+        .parse()
+        .map_err(|_| format!("invalid rate window '{text}'"))?;
+    if !(secs > 0.0 && secs.is_finite()) {
+        return Err(format!("rate window must be positive, but get '{text}'"));
+    }
+    Ok(secs)
+}
+
+fn parse_replay_speed(text: &str) -> Result<f64, String> {
+    let speed: f64 = text
+        .parse()
+        .map_err(|_| format!("invalid replay speed '{text}'"))?;
+    if !(speed > 0.0 && speed.is_finite()) {
+        return Err(format!("replay speed must be positive, but get '{text}'"));
+    }
+    Ok(speed)
 }
@@ -1,21 +1,39 @@
-use crate::{message::RtpsSubmsgEventKind, opts::Opts};
+use crate::{
+    message::{RtpsSubmsgEvent, RtpsSubmsgEventKind},
+    opts::Opts,
+    utils::GUIDExt,
+};
 
 use gethostname::gethostname;
-use mac_address::mac_address_by_name;
-use opentelemetry_api::{global::shutdown_tracer_provider, KeyValue};
+use mac_address::{get_mac_address, mac_address_by_name};
+use opentelemetry_api::{
+    global::shutdown_tracer_provider,
+    trace::{Span, SpanBuilder, SpanKind, Tracer},
+    KeyValue,
+};
 use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::{runtime, trace as sdktrace, trace::Sampler, Resource};
 use opentelemetry_semantic_conventions as semcov;
+use rustdds::structure::guid::EntityKind;
 use std::time::{Duration, SystemTime};
 
 pub struct TraceHandle {
     tracer: sdktrace::Tracer,
+    /// The capture interface, or `None` for the default device or a
+    /// packet dump.
+    interface: Option<String>,
     mac_address: [u8; 6],
 }
 
 impl TraceHandle {
     pub fn new(opts: &Opts) -> Self {
-        let mac_address = match mac_address_by_name(opts.interface.as_deref().unwrap_or("eno2")) {
+        // Use the MAC address of the capture interface, or the first
+        // one found on the host if no interface is given.
+        let mac_address = match &opts.interface {
+            Some(interface) => mac_address_by_name(interface),
+            None => get_mac_address(),
+        };
+        let mac_address = match mac_address {
             Ok(Some(ma)) => ma.bytes(),
             Ok(None) => [0; 6],
             Err(_) => [0; 6],
@@ -36,13 +54,7 @@ impl TraceHandle {
             .with_sampler(Sampler::AlwaysOn)
             .with_max_events_per_span(64)
             .with_max_attributes_per_span(16)
-            .with_resource(Resource::new(vec![
-                KeyValue::new(semcov::resource::SERVICE_NAME, "dds.traffic"),
-                KeyValue::new(
-                    semcov::resource::HOST_NAME,
-                    gethostname().to_string_lossy().to_string(),
-                ),
-            ]));
+            .with_resource(Resource::new(resource_attributes(opts)));
 
         let batch_config = sdktrace::BatchConfig::default()
             .with_max_concurrent_exports(4)
@@ -58,79 +70,123 @@ impl TraceHandle {
             .unwrap();
 
         TraceHandle {
+            interface: opts.interface.clone(),
             mac_address,
             tracer,
         }
     }
 
-    pub fn send_trace(&self, _message: &RtpsSubmsgEventKind, _topic_name: String) {
-        todo!();
-    }
+    /// Sends a span for the submessage. The span starts at the
+    /// receipt time and lasts for the payload transmission time on a
+    /// 2.5Gbps link.
+    pub fn send_trace(&self, msg: &RtpsSubmsgEvent, topic_name: Option<&str>) {
+        let writer_guid = msg.kind.writer_guid();
+        let (submsg_type, payload_size, mut attrs) = match &msg.kind {
+            RtpsSubmsgEventKind::Data(event) => (
+                "DATA",
+                event.payload_size,
+                vec![
+                    KeyValue::new("sn", event.writer_sn.0),
+                    KeyValue::new("payload_size", event.payload_size as i64),
+                ],
+            ),
+            RtpsSubmsgEventKind::DataFrag(event) => (
+                "DATA_FRAG",
+                event.payload_size,
+                vec![
+                    KeyValue::new("sn", event.writer_sn.0),
+                    KeyValue::new("fragment_starting_num", event.fragment_starting_num as i64),
+                    KeyValue::new(
+                        "fragments_in_submessage",
+                        event.fragments_in_submessage as i64,
+                    ),
+                    KeyValue::new("payload_size", event.payload_size as i64),
+                ],
+            ),
+            RtpsSubmsgEventKind::Gap(event) => {
+                let gap_list: Vec<_> = event.gap_list.iter().map(|sn| sn.0).collect();
+                (
+                    "GAP",
+                    0,
+                    vec![
+                        KeyValue::new("reader_id", event.reader_guid.display().to_string()),
+                        KeyValue::new("gap_start", event.gap_start.0),
+                        KeyValue::new("gap_list", format!("{gap_list:?}")),
+                    ],
+                )
+            }
+            RtpsSubmsgEventKind::AckNack(event) => (
+                "ACKNACK",
+                0,
+                vec![
+                    KeyValue::new("reader_id", event.reader_guid.display().to_string()),
+                    KeyValue::new("base_sn", event.base_sn),
+                    KeyValue::new("missing_sn", format!("{:?}", event.missing_sn)),
+                    KeyValue::new("count", event.count as i64),
+                ],
+            ),
+            RtpsSubmsgEventKind::NackFrag(event) => (
+                "NACK_FRAG",
+                0,
+                vec![
+                    KeyValue::new("reader_id", event.reader_guid.display().to_string()),
+                    KeyValue::new("sn", event.writer_sn.0),
+                    KeyValue::new("fragment_numbers", format!("{:?}", event.fragment_numbers)),
+                    KeyValue::new("count", event.count as i64),
+                ],
+            ),
+            RtpsSubmsgEventKind::Heartbeat(event) => (
+                "HEARTBEAT",
+                0,
+                vec![
+                    KeyValue::new("first_sn", event.first_sn.0),
+                    KeyValue::new("last_sn", event.last_sn.0),
+                    KeyValue::new("count", event.count as i64),
+                ],
+            ),
+            RtpsSubmsgEventKind::HeartbeatFrag(event) => (
+                "HEARTBEAT_FRAG",
+                0,
+                vec![
+                    KeyValue::new("sn", event.writer_sn.0),
+                    KeyValue::new("last_fragment_num", event.last_fragment_num.0 as i64),
+                    KeyValue::new("count", event.count as i64),
+                ],
+            ),
+        };
+
+        let traffic_type = match writer_guid.entity_id.entity_kind {
+            EntityKind::WRITER_WITH_KEY_USER_DEFINED | EntityKind::WRITER_NO_KEY_USER_DEFINED => {
+                "USER_DEFINED"
+            }
+            _ => "BUILT_IN",
+        };
+        attrs.extend([
+            KeyValue::new("traffic_type", traffic_type),
+            KeyValue::new("topic_name", topic_name.unwrap_or("").to_string()),
+            KeyValue::new("writer_id", writer_guid.display().to_string()),
+            KeyValue::new("mac_address", convert_to_colon_sep_hex(self.mac_address)),
+        ]);
+        if let Some(interface) = &self.interface {
+            attrs.push(KeyValue::new("interface", interface.clone()));
+        }
 
-    // pub fn send_trace(&self, message: &RtpsEvent, topic_name: String) {
-    //     let (headers, event) = (message.headers.clone(), message.context.clone());
-    //     let capture_time = headers.pcap_header.ts;
-    //     // let ma: [u8; 6] = headers.eth_header.destination;
-
-    //     let (submsg_type, writer_id, sn, fragment_starting_num, payload_size) = match event {
-    //         RtpsEvent::Data(event) => (
-    //             "DATA",
-    //             event.writer_guid,
-    //             event.writer_sn,
-    //             0u32,
-    //             event.payload_size,
-    //         ),
-    //         RtpsEvent::DataFrag(event) => (
-    //             "DATA_FRAG",
-    //             event.writer_guid,
-    //             event.writer_sn,
-    //             event.fragment_starting_num,
-    //             event.payload_size,
-    //         ),
-    //         RtpsEvent::Gap(_) => todo!(),
-    //         RtpsEvent::Heartbeat(_) => todo!(),
-    //         RtpsEvent::AckNack(_) => todo!(),
-    //         RtpsEvent::NackFrag(_) => todo!(),
-    //         RtpsEvent::HeartbeatFrag(_) => todo!(),
-    //     };
-    //     let traffic_type = match writer_id.entity_id.entity_kind {
-    //         // TODO: add complete cases
-    //         EntityKind::WRITER_NO_KEY_USER_DEFINED => "USER_DEFINED",
-    //         _ => "BUILT_IN",
-    //     };
-
-    //     // Create attributes to be attached to the span.
-    //     let attrs = vec![
-    //         semcov::trace::EVENT_NAME.string("eno2"),
-    //         KeyValue::new("traffic_type", traffic_type.to_string()),
-    //         KeyValue::new("topic_name", topic_name),
-    //         KeyValue::new("writer_id", convert_to_colon_sep_hex(writer_id.to_bytes())),
-    //         KeyValue::new("sn", sn.0),
-    //         KeyValue::new("fragment_starting_num", fragment_starting_num as i64),
-    //         KeyValue::new("payload_size", payload_size as i64),
-    //         KeyValue::new(
-    //             "pcp",
-    //             headers
-    //                 .vlan_header
-    //                 .unwrap_or(SingleVlanHeader::default())
-    //                 .priority_code_point as i64,
-    //         ),
-    //     ];
-
-    //     // Create a span with the given attributes. The start time is set to captured time.
-    //     // The end time is set to captured time + payload size * 8 / 2.5Gbps.
-    //     let mut span = self.tracer.build(SpanBuilder {
-    //         name: submsg_type.into(),
-    //         span_kind: Some(SpanKind::Internal),
-    //         start_time: Some(convert_to_system_time(capture_time)),
-    //         attributes: Some(attrs.into_iter().collect()),
-    //         ..Default::default()
-    //     });
-    //     span.end_with_timestamp(
-    //         convert_to_system_time(capture_time)
-    //             + Duration::from_secs_f64(payload_size as f64 * 8. / (2.5 * 1e9)),
-    //     );
-    // }
+        // The receipt time is negative only if the clock is broken.
+        let Ok(since_epoch) = msg.recv_time.to_std() else {
+            return;
+        };
+        let start_time = SystemTime::UNIX_EPOCH + since_epoch;
+        let end_time = start_time + Duration::from_secs_f64(payload_size as f64 * 8. / (2.5 * 1e9));
+
+        let mut span = self.tracer.build(SpanBuilder {
+            name: submsg_type.into(),
+            span_kind: Some(SpanKind::Internal),
+            start_time: Some(start_time),
+            attributes: Some(attrs.into_iter().collect()),
+            ..Default::default()
+        });
+        span.end_with_timestamp(end_time);
+    }
 }
 
 impl Drop for TraceHandle {
@@ -139,6 +195,27 @@ impl Drop for TraceHandle {
     }
 }
 
+/// Builds the resource attributes from the service name, the host
+/// name and the `--otlp-resource` options.
+fn resource_attributes(opts: &Opts) -> Vec<KeyValue> {
+    let hostname = gethostname().to_string_lossy().to_string();
+    let service_name = opts
+        .otlp_service_name
+        .clone()
+        .unwrap_or_else(|| format!("dds.traffic.{hostname}"));
+
+    let mut attrs = vec![
+        KeyValue::new(semcov::resource::SERVICE_NAME, service_name),
+        KeyValue::new(semcov::resource::HOST_NAME, hostname),
+    ];
+    attrs.extend(
+        opts.otlp_resource
+            .iter()
+            .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+    );
+    attrs
+}
+
 pub fn convert_to_system_time(capture_time: libc::timeval) -> SystemTime {
     SystemTime::UNIX_EPOCH
         + Duration::new(
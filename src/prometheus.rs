@@ -0,0 +1,265 @@
+//! The Prometheus scrape endpoint that serves the traffic statistics
+//! in the text exposition format.
+
+use crate::{
+    state::State,
+    utils::{GUIDExt, GuidPrefixExt},
+};
+use anyhow::Result;
+use rustdds::GUID;
+use std::{
+    fmt::Write as _,
+    net::SocketAddr,
+    sync::{Arc, Mutex, PoisonError},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    select,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+/// Serves `/metrics` on the address until the cancel token is
+/// signaled.
+pub async fn serve_metrics(
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    cancel_token: CancellationToken,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+
+    loop {
+        let (stream, _) = select! {
+            _ = cancel_token.cancelled() => break,
+            result = listener.accept() => result?,
+        };
+
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle_connection(stream, &state).await {
+                warn!(error = %err, "unable to serve the metrics request");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+async fn handle_connection(mut stream: TcpStream, state: &Mutex<State>) -> Result<()> {
+    // Only the request line matters. The rest of the request is
+    // ignored.
+    let mut buf = [0u8; 1024];
+    let len = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..len]);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next();
+    let path = request_line.next();
+
+    let response = match (method, path) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = {
+                let state = state.lock().unwrap_or_else(PoisonError::into_inner);
+                render_metrics(&state)
+            };
+            format!(
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: text/plain; version=0.0.4\r\n\
+                 Content-Length: {}\r\n\
+                 Connection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Renders the state in the Prometheus text format.
+fn render_metrics(state: &State) -> String {
+    let mut out = String::new();
+    let stat = &state.stat;
+
+    metric_header(
+        &mut out,
+        "ddshark_packets_total",
+        "counter",
+        "RTPS submessages processed.",
+    );
+    writeln!(out, "ddshark_packets_total {}", stat.packet_count).unwrap();
+
+    metric_header(
+        &mut out,
+        "ddshark_submessages_total",
+        "counter",
+        "RTPS submessages by kind.",
+    );
+    for (kind, count) in [
+        ("data", stat.data_submsg_count),
+        ("datafrag", stat.datafrag_submsg_count),
+        ("acknack", stat.acknack_submsg_count),
+        ("nackfrag", stat.ackfrag_submsg_count),
+        ("heartbeat", stat.heartbeat_submsg_count),
+        ("heartbeat_frag", stat.heartbeat_frag_submsg_count),
+        ("gap", stat.gap_submsg_count),
+    ] {
+        writeln!(out, "ddshark_submessages_total{{kind=\"{kind}\"}} {count}").unwrap();
+    }
+
+    metric_header(
+        &mut out,
+        "ddshark_abnormalities_total",
+        "counter",
+        "Detected abnormalities.",
+    );
+    writeln!(
+        out,
+        "ddshark_abnormalities_total {}",
+        state.dropped_abnormality_count + state.abnormalities.len()
+    )
+    .unwrap();
+
+    // Per-topic statistics
+    let mut topics: Vec<_> = state.topics.iter().collect();
+    topics.sort_unstable_by(|(lname, _), (rname, _)| lname.cmp(rname));
+
+    metric_header(
+        &mut out,
+        "ddshark_topic_messages_total",
+        "counter",
+        "Messages per topic.",
+    );
+    for (name, topic) in &topics {
+        let name = escape(name);
+        writeln!(
+            out,
+            "ddshark_topic_messages_total{{topic=\"{name}\"}} {}",
+            topic.total_msg_count
+        )
+        .unwrap();
+    }
+
+    metric_header(
+        &mut out,
+        "ddshark_topic_bytes_total",
+        "counter",
+        "Payload bytes per topic.",
+    );
+    for (name, topic) in &topics {
+        let name = escape(name);
+        writeln!(
+            out,
+            "ddshark_topic_bytes_total{{topic=\"{name}\"}} {}",
+            topic.total_byte_count
+        )
+        .unwrap();
+    }
+
+    metric_header(
+        &mut out,
+        "ddshark_topic_msg_rate",
+        "gauge",
+        "Mean messages per second per topic.",
+    );
+    for (name, topic) in &topics {
+        let name = escape(name);
+        writeln!(
+            out,
+            "ddshark_topic_msg_rate{{topic=\"{name}\"}} {}",
+            topic.msg_rate_stat.stat().mean
+        )
+        .unwrap();
+    }
+
+    metric_header(
+        &mut out,
+        "ddshark_topic_bit_rate",
+        "gauge",
+        "Mean bits per second per topic.",
+    );
+    for (name, topic) in &topics {
+        let name = escape(name);
+        writeln!(
+            out,
+            "ddshark_topic_bit_rate{{topic=\"{name}\"}} {}",
+            topic.bit_rate_stat.stat().mean
+        )
+        .unwrap();
+    }
+
+    // Per-participant statistics
+    let mut participants: Vec<_> = state.participants.iter().collect();
+    participants.sort_unstable_by(|(lprefix, _), (rprefix, _)| lprefix.cmp(rprefix));
+
+    metric_header(
+        &mut out,
+        "ddshark_participant_messages_total",
+        "counter",
+        "Messages per participant.",
+    );
+    for (prefix, part) in &participants {
+        let prefix = prefix.display();
+        writeln!(
+            out,
+            "ddshark_participant_messages_total{{guid_prefix=\"{prefix}\"}} {}",
+            part.total_msg_count
+        )
+        .unwrap();
+    }
+
+    metric_header(
+        &mut out,
+        "ddshark_participant_bytes_total",
+        "counter",
+        "Payload bytes per participant.",
+    );
+    for (prefix, part) in &participants {
+        let prefix = prefix.display();
+        writeln!(
+            out,
+            "ddshark_participant_bytes_total{{guid_prefix=\"{prefix}\"}} {}",
+            part.total_byte_count
+        )
+        .unwrap();
+    }
+
+    // Per-writer statistics, labeled by topic and participant
+    metric_header(
+        &mut out,
+        "ddshark_writer_messages_total",
+        "counter",
+        "Messages per writer.",
+    );
+    for &(&prefix, part) in &participants {
+        for (&entity_id, writer) in &part.writers {
+            let guid = GUID::new(prefix, entity_id);
+            let topic = escape(writer.topic_name().unwrap_or(""));
+            writeln!(
+                out,
+                "ddshark_writer_messages_total{{guid=\"{}\",guid_prefix=\"{}\",topic=\"{topic}\"}} {}",
+                guid.display(),
+                prefix.display(),
+                writer.total_msg_count
+            )
+            .unwrap();
+        }
+    }
+
+    out
+}
+
+fn metric_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(out, "# HELP {name} {help}").unwrap();
+    writeln!(out, "# TYPE {name} {kind}").unwrap();
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
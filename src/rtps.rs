@@ -1,9 +1,20 @@
 //! RTPS packet data loader, decoder and others.
 
+mod capture_config;
+mod capture_stats;
+mod decompress;
 mod packet_decoder;
 mod packet_iter;
 mod packet_source;
 mod packet_stream;
+mod param_list;
+mod replay_control;
 
-pub use packet_decoder::{PacketDecoder, RtpsPacket};
+pub use capture_config::{
+    validate_filter, CaptureConfig, OverflowStrategy, PayloadFraming, TimeSource,
+};
+pub use capture_stats::CaptureStats;
+pub use packet_decoder::{PacketDecoder, PacketKind, RtpsPacket};
 pub use packet_source::PacketSource;
+pub use param_list::VendorParams;
+pub use replay_control::ReplayControl;
@@ -0,0 +1,231 @@
+use super::decompress;
+use crate::capabilities;
+use anyhow::anyhow;
+use etherparse::UdpHeader;
+use pcap::{Active, Capture, Device, Linktype, Offline, Savefile};
+use std::{
+    os::fd::IntoRawFd,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Parameters of the packet capture and the decoded events.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureConfig {
+    /// Enable promiscuous mode. It is disabled by default, which
+    /// follows the libpcap default.
+    pub promisc: bool,
+    /// The time base of the generated events.
+    pub time_source: TimeSource,
+    /// Shut down when the offline capture is fully processed instead
+    /// of waiting for the user to quit.
+    pub exit_on_eof: bool,
+    /// The encapsulation around RTPS messages in UDP payloads.
+    pub framing: PayloadFraming,
+    /// The time to wait for the remaining fragments of an IPv4
+    /// datagram before dropping it.
+    pub reassembly_timeout: Duration,
+    /// The BPF filter applied to both live and offline captures. An
+    /// empty filter accepts all packets.
+    pub filter: String,
+    /// Keep only the packets whose UDP ports map to this DDS domain.
+    pub domain: Option<u16>,
+    /// Write the captured frames of the RTPS packets to this pcap
+    /// file.
+    pub write_pcap: Option<PathBuf>,
+    /// The time to wait for the updater to accept an event before
+    /// dropping it.
+    pub send_timeout: Duration,
+    /// What to do with new events when the updater queue is full.
+    pub overflow: OverflowStrategy,
+}
+
+/// The vendor-specific encapsulation wrapped around RTPS messages.
+///
+/// The prefix and suffix are stripped from the reassembled UDP
+/// payload. If the signature is set, the RTPS message starts at the
+/// first occurrence of the signature in the remaining bytes.
+#[derive(Debug, Clone, Default)]
+pub struct PayloadFraming {
+    pub strip_prefix: usize,
+    pub strip_suffix: usize,
+    pub signature: Option<Vec<u8>>,
+}
+
+impl PayloadFraming {
+    /// Extracts the RTPS message from the payload, or returns `None`
+    /// if the payload is too short or the signature is not found.
+    pub fn extract<'a>(&self, payload: &'a [u8]) -> Option<&'a [u8]> {
+        let end = payload.len().checked_sub(self.strip_suffix)?;
+        let payload = payload.get(self.strip_prefix..end)?;
+
+        match &self.signature {
+            Some(signature) if !signature.is_empty() => {
+                let offset = payload
+                    .windows(signature.len())
+                    .position(|window| window == signature.as_slice())?;
+                Some(&payload[offset..])
+            }
+            _ => Some(payload),
+        }
+    }
+}
+
+/// The source of the timestamps used for rate windows and timelines.
+///
+/// The pcap time is taken by the capturing host, so it is consistent
+/// across all senders but includes the network delay. The RTPS time
+/// is carried by INFO_TS submessages and reflects when the sender
+/// wrote the sample, but depends on the sender's clock being in sync.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TimeSource {
+    /// Use the capture time recorded by pcap.
+    #[default]
+    Pcap,
+    /// Use the RTPS INFO_TS timestamp and fall back to the capture
+    /// time when it is absent or invalid.
+    Rtps,
+}
+
+/// The strategy to handle new events when the updater queue is full.
+///
+/// Dropping the newest events keeps the queued events in order but
+/// shows a stale view when the updater falls behind a live capture.
+/// Dropping the oldest events keeps the view fresh at the cost of
+/// gaps in the past traffic. Blocking loses no events but stalls the
+/// capture, so the kernel may drop packets on live captures instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OverflowStrategy {
+    /// Drop the new event after waiting for the send timeout.
+    #[default]
+    DropNewest,
+    /// Discard the oldest queued events to make room for the new one.
+    DropOldest,
+    /// Wait until the updater accepts the event.
+    Block,
+}
+
+impl CaptureConfig {
+    /// Checks if the source or destination port of the packet belongs
+    /// to the configured domain. All packets pass if the domain is not
+    /// set.
+    pub fn accepts_ports(&self, udp: &UdpHeader) -> bool {
+        let Some(domain) = self.domain else {
+            return true;
+        };
+        [udp.source_port, udp.destination_port]
+            .into_iter()
+            .any(|port| port_to_domain(port) == Some(domain))
+    }
+
+    /// Opens the device with the configured parameters. Permission
+    /// errors come with the guidance to grant the capture privileges.
+    pub fn open(&self, device: Device) -> anyhow::Result<Capture<Active>> {
+        let mut cap = Capture::from_device(device)?
+            .promisc(self.promisc)
+            .open()
+            .map_err(|err| match capabilities::permission_error_guidance(&err) {
+                Some(guidance) => anyhow!(guidance),
+                None => err.into(),
+            })?;
+        cap.filter(&self.filter, true)?;
+        Ok(cap)
+    }
+
+    /// Opens the packet dump file with the configured filter. A gzip
+    /// or zstd compressed file is decompressed on the fly.
+    pub fn open_file(&self, path: &Path) -> anyhow::Result<Capture<Offline>> {
+        let mut cap = match decompress::decompress_to_pipe(path)? {
+            // SAFETY: The capture takes over the pipe and is the only
+            // reader of it.
+            Some(fd) => unsafe { Capture::from_raw_fd(fd.into_raw_fd())? },
+            None => Capture::from_file(path)?,
+        };
+        cap.filter(&self.filter, true)?;
+        Ok(cap)
+    }
+
+    /// Creates the pcap file given by `--write-pcap`, or returns
+    /// `None` if it is not set.
+    pub fn create_savefile(&self) -> Result<Option<Savefile>, pcap::Error> {
+        let Some(path) = &self.write_pcap else {
+            return Ok(None);
+        };
+        // The decoder only accepts Ethernet frames.
+        let savefile = Capture::dead(Linktype::ETHERNET)?.savefile(path)?;
+        Ok(Some(savefile))
+    }
+
+    /// Opens the packet dump written to the standard input, e.g. piped
+    /// from `tcpdump -w -`, with the configured filter.
+    pub fn open_stdin(&self) -> Result<Capture<Offline>, pcap::Error> {
+        // SAFETY: The standard input stays open for the whole process
+        // and is only read by the capture.
+        let mut cap = unsafe { Capture::from_raw_fd(libc::STDIN_FILENO)? };
+        cap.filter(&self.filter, true)?;
+        Ok(cap)
+    }
+}
+
+// The default port number parameters in the RTPS specification
+// (9.6.1.1). The ports of domain d and participant p are
+//
+// - discovery multicast: PB + DG * d + D0
+// - discovery unicast:   PB + DG * d + D1 + PG * p
+// - user multicast:      PB + DG * d + D2
+// - user unicast:        PB + DG * d + D3 + PG * p
+const PORT_BASE: u16 = 7400;
+const DOMAIN_ID_GAIN: u16 = 250;
+const D0: u16 = 0;
+const D1: u16 = 10;
+const D2: u16 = 1;
+
+/// Gets the domain of a port assigned by the default port mapping,
+/// or `None` if the port is not in the mapping.
+fn port_to_domain(port: u16) -> Option<u16> {
+    let offset = port.checked_sub(PORT_BASE)?;
+    let domain = offset / DOMAIN_ID_GAIN;
+
+    // The unicast ports of participants start at D1 and D3 and
+    // interleave with each other.
+    let ok = match offset % DOMAIN_ID_GAIN {
+        D0 | D2 => true,
+        rem => rem >= D1,
+    };
+    ok.then_some(domain)
+}
+
+/// Checks the syntax of a BPF filter without opening a device.
+pub fn validate_filter(filter: &str) -> Result<(), pcap::Error> {
+    Capture::dead(Linktype::ETHERNET)?.compile(filter, true)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn port_to_domain_maps_default_ports() {
+        // Discovery and user multicast ports.
+        assert_eq!(port_to_domain(7400), Some(0));
+        assert_eq!(port_to_domain(7401), Some(0));
+        assert_eq!(port_to_domain(7650), Some(1));
+        assert_eq!(port_to_domain(7651), Some(1));
+
+        // Discovery and user unicast ports of the first participants.
+        assert_eq!(port_to_domain(7410), Some(0));
+        assert_eq!(port_to_domain(7411), Some(0));
+        assert_eq!(port_to_domain(7412), Some(0));
+        assert_eq!(port_to_domain(7660), Some(1));
+        assert_eq!(port_to_domain(7661), Some(1));
+    }
+
+    #[test]
+    fn port_to_domain_rejects_ports_outside_mapping() {
+        assert_eq!(port_to_domain(22), None);
+        assert_eq!(port_to_domain(PORT_BASE - 1), None);
+        assert_eq!(port_to_domain(7402), None);
+        assert_eq!(port_to_domain(7659), None);
+    }
+}
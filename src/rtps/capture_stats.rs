@@ -0,0 +1,102 @@
+use super::packet_decoder::PacketKind;
+use rustdds::structure::guid::GuidPrefix;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+/// Shared counters of the captured packets.
+///
+/// The counters are updated by the packet stream before non-RTPS
+/// packets are dropped, so that the UI can tell an idle network from
+/// a capture that sees traffic but no RTPS.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureStats {
+    total_packets: Arc<AtomicUsize>,
+    rtps_packets: Arc<AtomicUsize>,
+    evicted_datagrams: Arc<AtomicUsize>,
+    dropped_events: Arc<AtomicUsize>,
+    queue_depth: Arc<AtomicUsize>,
+    parse_errors: Arc<AtomicUsize>,
+    last_parse_error: Arc<Mutex<Option<ParseErrorSource>>>,
+}
+
+/// The sender of a RTPS message that failed to parse, known from the
+/// fixed-size message header.
+#[derive(Debug, Clone, Copy)]
+pub struct ParseErrorSource {
+    pub vendor_id: [u8; 2],
+    pub guid_prefix: GuidPrefix,
+}
+
+impl CaptureStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a decoded packet.
+    pub(super) fn record(&self, packet: &PacketKind) {
+        self.total_packets.fetch_add(1, Ordering::Relaxed);
+        if let PacketKind::Rtps(_) = packet {
+            self.rtps_packets.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts an incomplete IPv4 datagram dropped from reassembly.
+    pub(super) fn record_evicted_datagram(&self) {
+        self.evicted_datagrams.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a RTPS message that failed to parse.
+    pub(super) fn record_parse_error(&self, source: Option<ParseErrorSource>) {
+        self.parse_errors.fetch_add(1, Ordering::Relaxed);
+        *self.last_parse_error.lock().unwrap() = source;
+    }
+
+    /// Counts an event dropped because the updater falls behind.
+    pub(crate) fn record_dropped_event(&self) {
+        self.dropped_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the number of events waiting for the updater.
+    pub(crate) fn set_queue_depth(&self, depth: usize) {
+        self.queue_depth.store(depth, Ordering::Relaxed);
+    }
+
+    /// Gets the number of all captured packets.
+    pub fn total_packets(&self) -> usize {
+        self.total_packets.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of captured RTPS packets.
+    pub fn rtps_packets(&self) -> usize {
+        self.rtps_packets.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of IPv4 datagrams that were never fully
+    /// reassembled.
+    pub fn evicted_datagrams(&self) -> usize {
+        self.evicted_datagrams.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of events dropped on congestion.
+    pub fn dropped_events(&self) -> usize {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of RTPS messages that failed to parse.
+    pub fn parse_errors(&self) -> usize {
+        self.parse_errors.load(Ordering::Relaxed)
+    }
+
+    /// Gets the sender of the last RTPS message that failed to parse,
+    /// if its header is intact.
+    pub fn last_parse_error(&self) -> Option<ParseErrorSource> {
+        *self.last_parse_error.lock().unwrap()
+    }
+
+    /// Gets the last observed number of queued events.
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,82 @@
+//! Decompression of gzip and zstd compressed packet dump files.
+
+use anyhow::Result;
+use std::{fs::File, io::Read, os::fd::OwnedFd, path::Path};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// The compression format of a packet dump file.
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Gzip,
+    Zstd,
+}
+
+/// Decompresses the file in a background thread if it is compressed
+/// with gzip or zstd, and returns the read end of a pipe that yields
+/// the decompressed packet dump. Returns `None` if the file is not
+/// compressed.
+///
+/// The format is detected by the magic bytes rather than the file
+/// extension. Nothing is written to the disk.
+pub fn decompress_to_pipe(path: &Path) -> Result<Option<OwnedFd>> {
+    let mut magic = vec![];
+    File::open(path)?.take(4).read_to_end(&mut magic)?;
+
+    let format = if magic.starts_with(GZIP_MAGIC) {
+        Format::Gzip
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        Format::Zstd
+    } else {
+        return Ok(None);
+    };
+
+    decompress(path, format).map(Some)
+}
+
+#[cfg(feature = "compression")]
+fn decompress(path: &Path, format: Format) -> Result<OwnedFd> {
+    use std::{
+        io::{self, Error},
+        os::fd::FromRawFd,
+        thread,
+    };
+    use tracing::error;
+
+    let file = File::open(path)?;
+    let mut reader: Box<dyn Read + Send> = match format {
+        Format::Gzip => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        Format::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+    };
+
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors written by pipe2().
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(Error::last_os_error().into());
+    }
+    // SAFETY: Both descriptors are freshly created and owned here.
+    let (read_fd, write_fd) =
+        unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+    let path = path.to_owned();
+    thread::spawn(move || {
+        let mut writer = File::from(write_fd);
+        match io::copy(&mut reader, &mut writer) {
+            Ok(_) => {}
+            // The capture is closed before reaching the end of file.
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+            Err(err) => error!("unable to decompress {}: {err}", path.display()),
+        }
+    });
+
+    Ok(read_fd)
+}
+
+#[cfg(not(feature = "compression"))]
+fn decompress(path: &Path, format: Format) -> Result<OwnedFd> {
+    anyhow::bail!(
+        "{} is compressed with {format:?}, but ddshark is built without the `compression` feature",
+        path.display()
+    )
+}
@@ -1,4 +1,8 @@
-use crate::message::RtpsPacketHeaders;
+use super::{
+    capture_config::{CaptureConfig, PayloadFraming},
+    capture_stats::{CaptureStats, ParseErrorSource},
+};
+use crate::message::{IpHeaders, RtpsPacketHeaders};
 use anyhow::bail;
 use bytes::Bytes;
 use etherparse::{
@@ -6,7 +10,7 @@ use etherparse::{
 };
 use libc::timeval;
 use pcap::{PacketCodec, PacketHeader};
-use rustdds::rtps::Message;
+use rustdds::{rtps::Message, structure::guid::GuidPrefix};
 use std::{
     borrow::Cow,
     collections::{BTreeMap, HashMap},
@@ -15,21 +19,43 @@ use std::{
 use tracing::error;
 
 pub struct PacketDecoder {
-    /// Map of (source, destination, id) to (fragment offset, payload)
-    fragments: HashMap<(Ipv4Addr, Ipv4Addr, u16), BTreeMap<u16, Vec<u8>>>,
-    /// Map of (source, destination, id) to (total received length, total length)
-    assemblers: HashMap<(Ipv4Addr, Ipv4Addr, u16), (usize, usize)>,
+    /// Map of (source, destination, id) to (byte offset, payload)
+    fragments: HashMap<(Ipv4Addr, Ipv4Addr, u16), BTreeMap<usize, Vec<u8>>>,
+    /// Map of (source, destination, id) to the datagram length known
+    /// from the last fragment
+    total_lengths: HashMap<(Ipv4Addr, Ipv4Addr, u16), usize>,
+    /// Map of (source, destination, id) to the capture time of the
+    /// first received fragment
+    started: HashMap<(Ipv4Addr, Ipv4Addr, u16), chrono::Duration>,
+    /// Incomplete datagrams older than this are dropped.
+    reassembly_timeout: chrono::Duration,
+    /// The encapsulation stripped from reassembled payloads.
+    framing: PayloadFraming,
+    stats: CaptureStats,
+    /// Keep the captured frames of each RTPS packet.
+    keep_raw_frames: bool,
+    /// Map of (source, destination, id) to the captured frames of the
+    /// fragments, kept only if `keep_raw_frames` is set
+    fragment_frames: HashMap<(Ipv4Addr, Ipv4Addr, u16), Vec<RawFrame>>,
 }
 
 impl PacketDecoder {
-    pub fn new() -> Self {
+    pub fn new(config: &CaptureConfig, stats: CaptureStats) -> Self {
         PacketDecoder {
             fragments: HashMap::new(),
-            assemblers: HashMap::new(),
+            total_lengths: HashMap::new(),
+            started: HashMap::new(),
+            reassembly_timeout: chrono::Duration::from_std(config.reassembly_timeout).unwrap(),
+            framing: config.framing.clone(),
+            stats,
+            keep_raw_frames: config.write_pcap.is_some(),
+            fragment_frames: HashMap::new(),
         }
     }
 
     fn dissect_packet<'a>(&mut self, packet: &'a pcap::Packet) -> Dissection<'a> {
+        let ts = timeval_to_duration(packet.header.ts);
+
         let Ok(headers) = PacketHeaders::from_ethernet_slice(packet) else {
             return Dissection::NotSupported;
         };
@@ -41,20 +67,61 @@ impl PacketDecoder {
             payload,
         } = headers;
 
-        let Some(IpHeader::Version4(ipv4, _)) = ip else {
-            return Dissection::NotSupported;
+        let ipv4 = match ip {
+            Some(IpHeader::Version4(ipv4, _)) => ipv4,
+            Some(IpHeader::Version6(ipv6, extensions)) => {
+                // Fragmented IPv6 packets are not reassembled. Even the
+                // first fragment is skipped since it carries only a
+                // part of the datagram.
+                let is_fragment = extensions
+                    .fragment
+                    .is_some_and(|frag| frag.more_fragments || frag.fragment_offset != 0);
+                if is_fragment {
+                    return Dissection::NotSupported;
+                }
+                let Some(TransportHeader::Udp(udp)) = transport else {
+                    return Dissection::NotSupported;
+                };
+                return MaybeAssembledUdpPacket {
+                    link,
+                    vlan,
+                    ip: IpHeaders::V6(ipv6),
+                    udp,
+                    payload: Cow::Borrowed(payload),
+                    fragment_key: None,
+                }
+                .into();
+            }
+            None => return Dissection::NotSupported,
         };
 
         let is_fragment = ipv4.more_fragments || ipv4.fragments_offset != 0;
+        let fragment_key = is_fragment.then(|| {
+            (
+                ipv4.source.into(),
+                ipv4.destination.into(),
+                ipv4.identification,
+            )
+        });
+
+        if let Some(key) = fragment_key.filter(|_| self.keep_raw_frames) {
+            self.fragment_frames
+                .entry(key)
+                .or_default()
+                .push(RawFrame::new(packet));
+        }
 
         let (udp, defrag_payload) = if is_fragment {
-            let payload = match self.process_fragments(&ipv4, payload) {
+            let payload = match self.process_fragments(&ipv4, payload, ts) {
                 Some(payload) => payload,
                 None => {
                     return Dissection::Ipv4Fragment { link, vlan, ipv4 };
                 }
             };
             let Ok((udp, payload)) = UdpHeader::from_slice(&payload) else {
+                if let Some(key) = fragment_key {
+                    self.fragment_frames.remove(&key);
+                }
                 return Dissection::NotSupported;
             };
             (udp, Cow::Owned(payload.to_vec()))
@@ -68,50 +135,99 @@ impl PacketDecoder {
         MaybeAssembledUdpPacket {
             link,
             vlan,
-            ipv4,
+            ip: IpHeaders::V4(ipv4),
             udp,
             payload: defrag_payload,
+            fragment_key,
         }
         .into()
     }
 
     /// Process packet fragments and return the payload if it is complete.
     /// Returns None if not all fragments have been received
-    fn process_fragments(&mut self, ipv4: &Ipv4Header, payload: &[u8]) -> Option<Vec<u8>> {
+    fn process_fragments(
+        &mut self,
+        ipv4: &Ipv4Header,
+        payload: &[u8],
+        ts: chrono::Duration,
+    ) -> Option<Vec<u8>> {
         let src = ipv4.source.into();
         let dst = ipv4.destination.into();
         let ident = ipv4.identification;
 
-        // Store the fragment into the buffer
-        let fragment_buffer = self.fragments.entry((src, dst, ident)).or_default();
-        fragment_buffer.insert(ipv4.fragments_offset, payload.to_vec());
+        self.evict_stale_fragments(ts);
+        self.started.entry((src, dst, ident)).or_insert(ts);
 
-        // Update the assembler
-        let (received_length, total_length) =
-            self.assemblers.entry((src, dst, ident)).or_insert((0, 0));
-        let fragment_len = payload.len();
-        *received_length += fragment_len;
+        // The fragment offset is counted in 8-byte units.
+        let key = (src, dst, ident);
+        let start = ipv4.fragments_offset as usize * 8;
+        let end = start + payload.len();
 
-        // Update total_length if this is the last fragment
+        // Reject duplicated or overlapping fragments, which would
+        // otherwise corrupt the reassembled payload.
+        let fragment_buffer = self.fragments.entry(key).or_default();
+        let overlaps_prev = fragment_buffer
+            .range(..=start)
+            .next_back()
+            .is_some_and(|(&prev_start, prev)| prev_start + prev.len() > start);
+        let overlaps_next = fragment_buffer
+            .range(start..)
+            .next()
+            .is_some_and(|(&next_start, _)| next_start < end);
+        if overlaps_prev || overlaps_next {
+            return None;
+        }
+        fragment_buffer.insert(start, payload.to_vec());
+
+        // The last fragment tells the datagram length.
         if !ipv4.more_fragments {
-            let new_total_length = ipv4.fragments_offset as usize + fragment_len;
-            if new_total_length > *total_length {
-                *total_length = new_total_length;
-            }
+            self.total_lengths.insert(key, end);
         }
+        let total_length = *self.total_lengths.get(&key)?;
 
-        // If all fragments have been received, reassemble and return the packet
-        if *received_length == *total_length {
-            let reassembled_fragments = self.fragments.remove(&(src, dst, ident)).unwrap();
-            let mut reassembled = Vec::new();
-            for (_, fragment) in reassembled_fragments {
-                reassembled.extend(fragment);
+        // Check if the fragments cover the whole datagram.
+        let mut covered = 0;
+        for (&start, fragment) in fragment_buffer.iter() {
+            if start != covered {
+                return None;
             }
-            self.assemblers.remove(&(src, dst, ident));
-            return Some(reassembled);
+            covered += fragment.len();
+        }
+        if covered != total_length {
+            return None;
         }
 
-        None
+        let reassembled: Vec<u8> = self
+            .fragments
+            .remove(&key)
+            .unwrap()
+            .into_values()
+            .flatten()
+            .collect();
+        self.total_lengths.remove(&key);
+        self.started.remove(&key);
+        Some(reassembled)
+    }
+
+    /// Drops incomplete datagrams whose first fragment arrived more
+    /// than the reassembly timeout ago, which happens when fragments
+    /// are lost.
+    fn evict_stale_fragments(&mut self, ts: chrono::Duration) {
+        let timeout = self.reassembly_timeout;
+        let stale: Vec<_> = self
+            .started
+            .iter()
+            .filter(|(_, &since)| ts - since > timeout)
+            .map(|(&key, _)| key)
+            .collect();
+
+        for key in stale {
+            self.started.remove(&key);
+            self.fragments.remove(&key);
+            self.total_lengths.remove(&key);
+            self.fragment_frames.remove(&key);
+            self.stats.record_evicted_datagram();
+        }
     }
 }
 
@@ -136,34 +252,53 @@ impl PacketCodec for PacketDecoder {
         let MaybeAssembledUdpPacket {
             link,
             vlan,
-            ipv4,
+            ip,
             udp,
             payload,
+            fragment_key,
         } = packet;
 
+        // The frames of a reassembled datagram are collected with its
+        // fragments.
+        let fragment_frames = fragment_key.and_then(|key| self.fragment_frames.remove(&key));
+
+        // The framing applies to the reassembled payload rather than
+        // to each IP fragment.
+        let Some(payload) = self.framing.extract(&payload) else {
+            bail!();
+        };
+
         if !payload.starts_with(b"RTPS") {
             bail!();
         }
 
-        let bytes = Bytes::copy_from_slice(&payload);
+        let bytes = Bytes::copy_from_slice(payload);
         let message: Message = match Message::read_from_buffer(&bytes) {
             Ok(msg) => msg,
             Err(err) => {
                 error!("error: {err:?}");
+                self.stats.record_parse_error(parse_error_source(payload));
                 bail!();
             }
         };
 
+        let raw_frames = if self.keep_raw_frames {
+            fragment_frames.unwrap_or_else(|| vec![RawFrame::new(&pcap_packet)])
+        } else {
+            vec![]
+        };
+
         RtpsPacket {
             headers: RtpsPacketHeaders {
                 pcap_header: *pcap_packet.header,
                 link,
                 vlan,
-                ipv4,
+                ip,
                 udp,
                 ts: timeval_to_duration(pcap_packet.header.ts),
             },
             message,
+            raw_frames,
         }
         .into()
     }
@@ -198,6 +333,25 @@ impl From<OtherPacket> for PacketKind {
 pub struct RtpsPacket {
     pub headers: RtpsPacketHeaders,
     pub message: Message,
+    /// The captured frames carrying the message, which are more than
+    /// one for a reassembled IPv4 datagram. It is empty unless
+    /// `--write-pcap` is set.
+    pub raw_frames: Vec<RawFrame>,
+}
+
+/// A frame as captured by pcap.
+pub struct RawFrame {
+    pub header: PacketHeader,
+    pub data: Bytes,
+}
+
+impl RawFrame {
+    fn new(packet: &pcap::Packet) -> Self {
+        Self {
+            header: *packet.header,
+            data: Bytes::copy_from_slice(packet.data),
+        }
+    }
 }
 
 pub struct OtherPacket {
@@ -206,6 +360,17 @@ pub struct OtherPacket {
     pub len: u32,
 }
 
+/// Reads the sender from the RTPS header, which is laid out as
+/// "RTPS", the protocol version, the vendor id and the GUID prefix.
+fn parse_error_source(message: &[u8]) -> Option<ParseErrorSource> {
+    let header = message.get(..20)?;
+
+    Some(ParseErrorSource {
+        vendor_id: [header[6], header[7]],
+        guid_prefix: GuidPrefix::new(&header[8..20]),
+    })
+}
+
 fn timeval_to_duration(ts: timeval) -> chrono::Duration {
     let timeval { tv_sec, tv_usec } = ts;
     chrono::Duration::microseconds(tv_sec * 1_000_000 + tv_usec)
@@ -225,9 +390,11 @@ enum Dissection<'a> {
 struct MaybeAssembledUdpPacket<'a> {
     pub link: Option<Ethernet2Header>,
     pub vlan: Option<VlanHeader>,
-    pub ipv4: Ipv4Header,
+    pub ip: IpHeaders,
     pub udp: UdpHeader,
     pub payload: Cow<'a, [u8]>,
+    /// The (source, destination, id) of a reassembled IPv4 datagram.
+    pub fragment_key: Option<(Ipv4Addr, Ipv4Addr, u16)>,
 }
 
 impl<'a> From<MaybeAssembledUdpPacket<'a>> for Dissection<'a> {
@@ -235,3 +402,172 @@ impl<'a> From<MaybeAssembledUdpPacket<'a>> for Dissection<'a> {
         Self::UdpPacket(v)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use etherparse::IpNumber;
+
+    /// The 24-byte datagram split into three 8-byte fragments.
+    fn datagram() -> Vec<u8> {
+        (0..24).collect()
+    }
+
+    fn decoder() -> PacketDecoder {
+        PacketDecoder::new(&CaptureConfig::default(), CaptureStats::new())
+    }
+
+    /// Builds the header of the fragment at the byte offset.
+    fn fragment_header(offset: usize, len: usize, more_fragments: bool) -> Ipv4Header {
+        let mut header = Ipv4Header::new(
+            len as u16,
+            64,
+            IpNumber::Udp as u8,
+            [10, 0, 0, 1],
+            [10, 0, 0, 2],
+        );
+        header.identification = 42;
+        header.fragments_offset = (offset / 8) as u16;
+        header.more_fragments = more_fragments;
+        header
+    }
+
+    /// Feeds the fragment given by the byte range of the datagram.
+    fn feed(decoder: &mut PacketDecoder, range: std::ops::Range<usize>) -> Option<Vec<u8>> {
+        let payload = &datagram()[range.clone()];
+        let header = fragment_header(range.start, payload.len(), range.end < 24);
+        decoder.process_fragments(&header, payload, chrono::Duration::zero())
+    }
+
+    #[test]
+    fn reassemble_reordered_fragments() {
+        let mut decoder = decoder();
+        assert_eq!(feed(&mut decoder, 16..24), None);
+        assert_eq!(feed(&mut decoder, 0..8), None);
+        assert_eq!(feed(&mut decoder, 8..16), Some(datagram()));
+    }
+
+    #[test]
+    fn reassemble_duplicated_fragments() {
+        let mut decoder = decoder();
+        assert_eq!(feed(&mut decoder, 0..8), None);
+        assert_eq!(feed(&mut decoder, 0..8), None);
+        assert_eq!(feed(&mut decoder, 8..16), None);
+        assert_eq!(feed(&mut decoder, 8..16), None);
+        assert_eq!(feed(&mut decoder, 16..24), Some(datagram()));
+    }
+
+    #[test]
+    fn reject_overlapping_fragments() {
+        let mut decoder = decoder();
+        assert_eq!(feed(&mut decoder, 0..16), None);
+
+        // Overlaps the tail of the first fragment with different
+        // bytes, which must not make it into the datagram.
+        let overlapping = [0xff; 16];
+        let header = fragment_header(8, overlapping.len(), false);
+        let result = decoder.process_fragments(&header, &overlapping, chrono::Duration::zero());
+        assert_eq!(result, None);
+
+        assert_eq!(feed(&mut decoder, 16..24), Some(datagram()));
+    }
+
+    /// A RTPS message with an INFO_TS submessage.
+    fn rtps_message() -> Vec<u8> {
+        let mut message = b"RTPS".to_vec();
+        message.extend([2, 3, 0x01, 0x0f]);
+        message.extend(1..=12);
+        message.extend([0x09, 0x01, 8, 0]);
+        message.extend([0; 8]);
+        message
+    }
+
+    /// Builds an Ethernet frame carrying the UDP datagram over IPv6,
+    /// optionally with a fragment header of the offset in 8-byte units
+    /// and the more fragments flag.
+    fn ipv6_frame(payload: &[u8], fragment: Option<(u16, bool)>) -> Vec<u8> {
+        let mut udp = vec![];
+        udp.extend(7411u16.to_be_bytes());
+        udp.extend(7410u16.to_be_bytes());
+        udp.extend((8 + payload.len() as u16).to_be_bytes());
+        udp.extend([0, 0]);
+        udp.extend(payload);
+
+        let mut ip_payload = vec![];
+        let next_header = match fragment {
+            Some((offset, more_fragments)) => {
+                ip_payload.extend([IpNumber::Udp as u8, 0]);
+                ip_payload.extend((offset << 3 | more_fragments as u16).to_be_bytes());
+                ip_payload.extend(7u32.to_be_bytes());
+                IpNumber::IPv6FragmentationHeader as u8
+            }
+            None => IpNumber::Udp as u8,
+        };
+        ip_payload.extend(udp);
+
+        let mut frame = vec![];
+        frame.extend([0x02, 0, 0, 0, 0, 2, 0x02, 0, 0, 0, 0, 1]);
+        frame.extend([0x86, 0xdd]);
+        frame.extend([0x60, 0, 0, 0]);
+        frame.extend((ip_payload.len() as u16).to_be_bytes());
+        frame.extend([next_header, 64]);
+        frame.extend([0xfe, 0x80].into_iter().chain([0; 13]).chain([1]));
+        frame.extend([0xfe, 0x80].into_iter().chain([0; 13]).chain([2]));
+        frame.extend(ip_payload);
+        frame
+    }
+
+    fn decode_frame(decoder: &mut PacketDecoder, frame: &[u8]) -> PacketKind {
+        let header = PacketHeader {
+            ts: timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            caplen: frame.len() as u32,
+            len: frame.len() as u32,
+        };
+        decoder.decode(pcap::Packet::new(&header, frame))
+    }
+
+    #[test]
+    fn decode_rtps_over_ipv6() {
+        let frame = ipv6_frame(&rtps_message(), None);
+        let PacketKind::Rtps(packet) = decode_frame(&mut decoder(), &frame) else {
+            panic!("the packet is not decoded as RTPS");
+        };
+
+        let IpHeaders::V6(ipv6) = &packet.headers.ip else {
+            panic!("the packet is not decoded as IPv6");
+        };
+        assert_eq!(ipv6.destination[15], 2);
+        assert_eq!(packet.headers.udp.destination_port, 7410);
+        assert_eq!(
+            packet.message.header.guid_prefix,
+            GuidPrefix::new(&rtps_message()[8..20])
+        );
+    }
+
+    #[test]
+    fn skip_ipv6_fragments() {
+        let mut decoder = decoder();
+
+        // Both the first and the following fragments are skipped.
+        let first = ipv6_frame(&rtps_message(), Some((0, true)));
+        assert!(matches!(
+            decode_frame(&mut decoder, &first),
+            PacketKind::Other(_)
+        ));
+        let last = ipv6_frame(&rtps_message(), Some((4, false)));
+        assert!(matches!(
+            decode_frame(&mut decoder, &last),
+            PacketKind::Other(_)
+        ));
+
+        // An atomic fragment carries the whole datagram.
+        let atomic = ipv6_frame(&rtps_message(), Some((0, false)));
+        assert!(matches!(
+            decode_frame(&mut decoder, &atomic),
+            PacketKind::Rtps(_)
+        ));
+    }
+}
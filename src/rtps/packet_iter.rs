@@ -9,13 +9,13 @@ pub enum MessageIter {
 }
 
 impl MessageIter {
-    pub fn new_active(capture: Capture<Active>) -> Self {
-        MessageIter::from(capture.iter(PacketDecoder::new()))
+    pub fn new_active(capture: Capture<Active>, decoder: PacketDecoder) -> Self {
+        MessageIter::from(capture.iter(decoder))
     }
 
-    pub fn new_offline(capture: Capture<Offline>) -> Self {
+    pub fn new_offline(capture: Capture<Offline>, decoder: PacketDecoder) -> Self {
         OfflineMessageIter {
-            packet_iter: capture.iter(PacketDecoder::new()),
+            packet_iter: capture.iter(decoder),
             since: None,
         }
         .into()
@@ -1,46 +1,63 @@
 use super::{
+    capture_config::CaptureConfig,
+    capture_stats::CaptureStats,
     packet_decoder::PacketDecoder,
     packet_iter::MessageIter,
     packet_stream::{build_packet_stream, RtpsPacketStream},
+    replay_control::ReplayControl,
 };
 use anyhow::{anyhow, Result};
-use pcap::{Capture, Device};
+use pcap::Device;
 use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum PacketSource {
     Default,
-    File { path: PathBuf },
+    File {
+        path: PathBuf,
+    },
+    /// A packet dump piped to the standard input.
+    Stdin,
     Interface(String),
 }
 
 impl PacketSource {
-    pub fn into_iter(self) -> Result<MessageIter> {
+    pub fn into_iter(self, config: &CaptureConfig) -> Result<MessageIter> {
+        let decoder = PacketDecoder::new(config, CaptureStats::new());
         let iter = match self {
             PacketSource::Default => {
-                let cap = Device::lookup()?
-                    .ok_or_else(|| anyhow!("no available network device"))?
-                    .open()?;
-                MessageIter::new_active(cap)
+                let dev =
+                    Device::lookup()?.ok_or_else(|| anyhow!("no available network device"))?;
+                let cap = config.open(dev)?;
+                MessageIter::new_active(cap, decoder)
             }
             PacketSource::File { path } => {
-                let cap = Capture::from_file(path)?;
-                MessageIter::new_offline(cap)
+                let cap = config.open_file(&path)?;
+                MessageIter::new_offline(cap, decoder)
+            }
+            PacketSource::Stdin => {
+                let cap = config.open_stdin()?;
+                MessageIter::new_offline(cap, decoder)
             }
             PacketSource::Interface(interface) => {
-                let cap = Device::list()?
+                let dev = Device::list()?
                     .into_iter()
                     .find(|dev| dev.name == interface)
-                    .ok_or_else(|| anyhow!("unable to find network device {interface}"))?
-                    .open()?;
-                MessageIter::from(cap.iter(PacketDecoder::new()))
+                    .ok_or_else(|| anyhow!("unable to find network device {interface}"))?;
+                let cap = config.open(dev)?;
+                MessageIter::new_active(cap, decoder)
             }
         };
 
         Ok(iter)
     }
 
-    pub fn into_stream(self) -> Result<RtpsPacketStream> {
-        build_packet_stream(self)
+    pub fn into_stream(
+        self,
+        config: &CaptureConfig,
+        replay: ReplayControl,
+        stats: CaptureStats,
+    ) -> Result<RtpsPacketStream> {
+        build_packet_stream(self, config, replay, stats)
     }
 }
@@ -1,36 +1,49 @@
 use super::{
+    capture_config::CaptureConfig,
+    capture_stats::CaptureStats,
     packet_decoder::{PacketDecoder, PacketKind, RtpsPacket},
+    replay_control::ReplayControl,
     PacketSource,
 };
+use crate::config::PIPED_PACKET_QUEUE_LEN;
 use anyhow::{anyhow, Result};
 use futures::{
     stream::{self, BoxStream},
     FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt,
 };
 use pcap::{Active, Capture, Device, Offline};
-use std::time::Instant;
+use std::{thread, time::Instant};
 
 pub type RtpsPacketStream = BoxStream<'static, Result<RtpsPacket, pcap::Error>>;
 
-pub fn build_packet_stream(src: PacketSource) -> Result<RtpsPacketStream> {
+pub fn build_packet_stream(
+    src: PacketSource,
+    config: &CaptureConfig,
+    replay: ReplayControl,
+    stats: CaptureStats,
+) -> Result<RtpsPacketStream> {
+    let decoder = PacketDecoder::new(config, stats.clone());
     let stream = match src {
         PacketSource::Default => {
-            let cap = Device::lookup()?
-                .ok_or_else(|| anyhow!("no available network device"))?
-                .open()?;
-            build_active_packet_stream(cap)?.boxed()
+            let dev = Device::lookup()?.ok_or_else(|| anyhow!("no available network device"))?;
+            let cap = config.open(dev)?;
+            build_active_packet_stream(cap, decoder, stats)?.boxed()
         }
         PacketSource::File { path } => {
-            let cap = Capture::from_file(path)?;
-            build_offline_packet_stream(cap)?.boxed()
+            let cap = config.open_file(&path)?;
+            build_offline_packet_stream(cap, decoder, replay, stats)?.boxed()
+        }
+        PacketSource::Stdin => {
+            let cap = config.open_stdin()?;
+            build_piped_packet_stream(cap, decoder, stats).boxed()
         }
         PacketSource::Interface(interface) => {
-            let cap = Device::list()?
+            let dev = Device::list()?
                 .into_iter()
                 .find(|dev| dev.name == interface)
-                .ok_or_else(|| anyhow!("unable to find network device {interface}"))?
-                .open()?;
-            build_active_packet_stream(cap)?.boxed()
+                .ok_or_else(|| anyhow!("unable to find network device {interface}"))?;
+            let cap = config.open(dev)?;
+            build_active_packet_stream(cap, decoder, stats)?.boxed()
         }
     };
 
@@ -39,24 +52,71 @@ pub fn build_packet_stream(src: PacketSource) -> Result<RtpsPacketStream> {
 
 fn build_active_packet_stream(
     cap: Capture<Active>,
+    decoder: PacketDecoder,
+    stats: CaptureStats,
 ) -> Result<impl Stream<Item = Result<RtpsPacket, pcap::Error>> + Send + 'static> {
     let stream = cap
         .setnonblock()?
-        .stream(PacketDecoder::new())?
-        .try_filter_map(|pkt| async move {
-            let PacketKind::Rtps(pkt) = pkt else {
-                return Ok(None);
-            };
+        .stream(decoder)?
+        .try_filter_map(move |pkt| {
+            stats.record(&pkt);
+
+            async move {
+                let PacketKind::Rtps(pkt) = pkt else {
+                    return Ok(None);
+                };
 
-            Ok(Some(pkt))
+                Ok(Some(pkt))
+            }
         });
     Ok(stream)
 }
 
+/// Builds the stream on a packet dump from a pipe. The packets are
+/// passed on as soon as they arrive since the writer of the pipe
+/// usually captures in real time.
+///
+/// Reading the pipe blocks until the next packet arrives, so it is
+/// done on a dedicated thread. Otherwise a quiet pipe would stall the
+/// runtime and the cancellation would wait for the next packet.
+fn build_piped_packet_stream(
+    cap: Capture<Offline>,
+    decoder: PacketDecoder,
+    stats: CaptureStats,
+) -> impl Stream<Item = Result<RtpsPacket, pcap::Error>> + Send + 'static {
+    let (tx, rx) = flume::bounded(PIPED_PACKET_QUEUE_LEN);
+
+    // The thread is detached. It ends at the end of the dump or when
+    // the stream is dropped and the next packet fails to be sent.
+    thread::spawn(move || {
+        for packet in cap.iter(decoder) {
+            let is_err = packet.is_err();
+            if tx.send(packet).is_err() || is_err {
+                break;
+            }
+        }
+    });
+
+    rx.into_stream().try_filter_map(move |packet| {
+        stats.record(&packet);
+
+        async move {
+            let PacketKind::Rtps(packet) = packet else {
+                return Ok(None);
+            };
+
+            Ok(Some(packet))
+        }
+    })
+}
+
 fn build_offline_packet_stream(
     cap: Capture<Offline>,
+    decoder: PacketDecoder,
+    replay: ReplayControl,
+    stats: CaptureStats,
 ) -> Result<impl Stream<Item = Result<RtpsPacket, pcap::Error>> + Send + 'static> {
-    let iter = cap.iter(PacketDecoder::new());
+    let iter = cap.iter(decoder);
     let mut stream = stream::iter(iter);
 
     let stream = async move {
@@ -67,19 +127,22 @@ fn build_offline_packet_stream(
         let since_instant = Instant::now();
         let since_ts = first_packet.ts();
 
-        let rest = stream.and_then(move |packet| async move {
-            // Simulate the receipt rate
-            let now = Instant::now();
-            let ts = packet.ts();
+        let rest = stream.and_then(move |packet| {
+            // Packets before the seek target are processed without
+            // waiting.
+            let diff = replay.wall_elapsed(packet.ts() - since_ts);
 
-            let diff = (ts - since_ts).to_std().unwrap();
-            let until = since_instant + diff;
+            async move {
+                // Simulate the receipt rate
+                let now = Instant::now();
+                let until = since_instant + diff;
 
-            if let Some(wait) = until.checked_duration_since(now) {
-                tokio::time::sleep(wait).await;
-            }
+                if let Some(wait) = until.checked_duration_since(now) {
+                    tokio::time::sleep(wait).await;
+                }
 
-            Ok(packet)
+                Ok(packet)
+            }
         });
 
         let stream = stream::iter([Ok(first_packet)]).chain(rest);
@@ -89,13 +152,17 @@ fn build_offline_packet_stream(
     .map_ok(|stream| stream::iter(stream).flatten())
     .into_stream()
     .try_flatten()
-    .try_filter_map(|packet| async move {
-        // Get the RTPS packet
-        let PacketKind::Rtps(packet) = packet else {
-            return Ok(None);
-        };
+    .try_filter_map(move |packet| {
+        stats.record(&packet);
+
+        async move {
+            // Get the RTPS packet
+            let PacketKind::Rtps(packet) = packet else {
+                return Ok(None);
+            };
 
-        Ok(Some(packet))
+            Ok(Some(packet))
+        }
     });
 
     Ok(stream)
@@ -0,0 +1,153 @@
+//! A lightweight scanner over raw PL_CDR parameter lists.
+//!
+//! `rustdds` drops parameters it does not recognize, including the
+//! vendor-specific ones sent by RTI Connext. The scanner walks the
+//! serialized payload directly so that these parameters can be
+//! recovered.
+
+/// The standard PID_ENTITY_NAME parameter.
+pub const PID_ENTITY_NAME: u16 = 0x0062;
+
+/// The PID_SENTINEL parameter that terminates a parameter list.
+pub const PID_SENTINEL: u16 = 0x0001;
+
+/// The PID_PAD parameter.
+pub const PID_PAD: u16 = 0x0000;
+
+/// The RTI Connext PID_PRODUCT_VERSION parameter.
+pub const PID_RTI_PRODUCT_VERSION: u16 = 0x8000;
+
+/// The RTI Connext PID_ROLE_NAME parameter.
+pub const PID_RTI_ROLE_NAME: u16 = 0x800a;
+
+/// The vendor ID of RTI Connext.
+pub const VENDOR_ID_RTI: [u8; 2] = [0x01, 0x01];
+
+/// Vendor-specific discovery parameters that `rustdds` does not
+/// surface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VendorParams {
+    pub entity_name: Option<String>,
+    pub role_name: Option<String>,
+    pub product_version: Option<[u8; 4]>,
+}
+
+impl VendorParams {
+    /// Scans a serialized discovery payload, including the 4-byte
+    /// encapsulation header, for the known vendor parameters.
+    /// Vendor-specific PIDs are only interpreted when `vendor_id`
+    /// matches the vendor that defines them.
+    pub fn parse(payload: &[u8], vendor_id: [u8; 2]) -> Option<Self> {
+        let is_rti = vendor_id == VENDOR_ID_RTI;
+        let mut params = Self::default();
+
+        for (pid, value, little_endian) in ParamListIter::new(payload)? {
+            match pid {
+                PID_ENTITY_NAME => {
+                    params.entity_name = parse_string(value, little_endian);
+                }
+                PID_RTI_ROLE_NAME if is_rti => {
+                    params.role_name = parse_string(value, little_endian);
+                }
+                PID_RTI_PRODUCT_VERSION if is_rti => {
+                    if let Some(version) = value.get(0..4) {
+                        params.product_version = Some(version.try_into().unwrap());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let found = params != Self::default();
+        found.then_some(params)
+    }
+
+    /// Returns the entity name, falling back to the role name.
+    pub fn display_name(&self) -> Option<&str> {
+        self.entity_name.as_deref().or(self.role_name.as_deref())
+    }
+}
+
+/// Iterates over `(pid, value, little_endian)` tuples of a PL_CDR
+/// parameter list.
+pub struct ParamListIter<'a> {
+    buf: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> ParamListIter<'a> {
+    /// Creates an iterator from a serialized payload starting with
+    /// the encapsulation header. Returns `None` if the payload is not
+    /// encoded in PL_CDR.
+    pub fn new(payload: &'a [u8]) -> Option<Self> {
+        let header = payload.get(0..4)?;
+        let little_endian = match [header[0], header[1]] {
+            [0x00, 0x02] => false,
+            [0x00, 0x03] => true,
+            _ => return None,
+        };
+
+        Some(Self {
+            buf: &payload[4..],
+            little_endian,
+        })
+    }
+}
+
+impl<'a> Iterator for ParamListIter<'a> {
+    type Item = (u16, &'a [u8], bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let header = self.buf.get(0..4)?;
+            let (pid, len) = if self.little_endian {
+                (
+                    u16::from_le_bytes([header[0], header[1]]),
+                    u16::from_le_bytes([header[2], header[3]]),
+                )
+            } else {
+                (
+                    u16::from_be_bytes([header[0], header[1]]),
+                    u16::from_be_bytes([header[2], header[3]]),
+                )
+            };
+
+            // Strip the must-understand flag. The vendor-specific bit
+            // is kept since it is part of the ID space.
+            let pid = pid & !0x4000;
+
+            if pid == PID_SENTINEL {
+                self.buf = &[];
+                return None;
+            }
+
+            let Some(value) = self.buf.get(4..4 + len as usize) else {
+                self.buf = &[];
+                return None;
+            };
+            self.buf = &self.buf[4 + len as usize..];
+
+            if pid == PID_PAD {
+                continue;
+            }
+
+            return Some((pid, value, self.little_endian));
+        }
+    }
+}
+
+/// Parses a CDR string, which is a 4-byte length followed by a
+/// NUL-terminated byte sequence.
+fn parse_string(value: &[u8], little_endian: bool) -> Option<String> {
+    let len_bytes: [u8; 4] = value.get(0..4)?.try_into().unwrap();
+    let len = if little_endian {
+        u32::from_le_bytes(len_bytes)
+    } else {
+        u32::from_be_bytes(len_bytes)
+    } as usize;
+
+    let bytes = value.get(4..4 + len)?;
+    let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+    let text = String::from_utf8_lossy(bytes).into_owned();
+    Some(text)
+}
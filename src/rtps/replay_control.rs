@@ -0,0 +1,72 @@
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// A shared handle controlling the replay of an offline capture.
+///
+/// The handle keeps the replay speed and the accumulated seek offset.
+/// Both the offline packet stream and the updater clock convert
+/// between the wall-clock time and the capture time through this
+/// handle, so that statistics windows follow the capture time
+/// regardless of the replay speed.
+#[derive(Debug, Clone)]
+pub struct ReplayControl {
+    speed: f64,
+    seek_offset_us: Arc<AtomicI64>,
+}
+
+impl ReplayControl {
+    pub fn new() -> Self {
+        Self::with_speed(1.0)
+    }
+
+    /// Creates a handle that replays the capture at the given speed.
+    pub fn with_speed(speed: f64) -> Self {
+        assert!(speed > 0.0);
+
+        Self {
+            speed,
+            seek_offset_us: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    /// Fast-forwards the replay by the given step.
+    pub fn seek_forward(&self, step: chrono::Duration) {
+        let Some(step_us) = step.num_microseconds() else {
+            return;
+        };
+        self.seek_offset_us.fetch_add(step_us, Ordering::SeqCst);
+    }
+
+    /// Gets the accumulated seek offset.
+    pub fn offset(&self) -> chrono::Duration {
+        let offset_us = self.seek_offset_us.load(Ordering::SeqCst);
+        chrono::Duration::microseconds(offset_us)
+    }
+
+    /// Converts the wall-clock time elapsed since the replay starts
+    /// to the elapsed capture time.
+    pub fn capture_elapsed(&self, wall_elapsed: Duration) -> chrono::Duration {
+        let elapsed = chrono::Duration::from_std(wall_elapsed.mul_f64(self.speed)).unwrap();
+        elapsed + self.offset()
+    }
+
+    /// Converts the capture time elapsed since the first packet to
+    /// the wall-clock time when the packet should be replayed.
+    pub fn wall_elapsed(&self, capture_elapsed: chrono::Duration) -> Duration {
+        (capture_elapsed - self.offset())
+            .to_std()
+            .unwrap_or(Duration::ZERO)
+            .div_f64(self.speed)
+    }
+}
+
+impl Default for ReplayControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
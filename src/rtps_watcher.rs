@@ -5,15 +5,18 @@ use super::PacketSource;
 use crate::{
     message::{
         AckNackEvent, DataEvent, DataFragEvent, GapEvent, HeartbeatEvent, HeartbeatFragEvent,
-        NackFragEvent, ParticipantInfo, RtpsPacketHeaders, RtpsSubmsgEvent, RtpsSubmsgEventKind,
-        UpdateEvent,
+        InlineQos, NackFragEvent, ParticipantInfo, RtpsPacketHeaders, RtpsSubmsgEvent,
+        RtpsSubmsgEventKind, StatusInfo, UpdateEvent,
+    },
+    rtps::{
+        CaptureConfig, CaptureStats, OverflowStrategy, ReplayControl, RtpsPacket, TimeSource,
+        VendorParams,
     },
-    rtps::RtpsPacket,
     utils::EntityIdExt,
 };
 use anyhow::Result;
 use bytes::Bytes;
-use etherparse::{Ipv4Header, UdpHeader};
+use etherparse::UdpHeader;
 use futures::{stream, StreamExt, TryStreamExt};
 use itertools::chain;
 use rustdds::{
@@ -25,7 +28,7 @@ use rustdds::{
         header::Header,
         protocol_version::ProtocolVersion,
         submessages::{
-            elements::serialized_payload::SerializedPayload,
+            elements::{parameter_list::ParameterList, serialized_payload::SerializedPayload},
             info_source::InfoSource,
             submessages::{
                 AckNack, Data, DataFrag, Gap, Heartbeat, HeartbeatFrag, InfoDestination,
@@ -40,6 +43,7 @@ use rustdds::{
     structure::{
         guid::{EntityId, GuidPrefix},
         locator::Locator,
+        parameter_id::ParameterId,
         sequence_number::FragmentNumber,
     },
     RepresentationIdentifier, SequenceNumber, Timestamp, GUID,
@@ -48,11 +52,11 @@ use serde::Deserialize;
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
-    net::SocketAddrV4,
+    net::{IpAddr, SocketAddrV4, SocketAddrV6},
     time::Duration,
 };
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, warn};
+use tracing::{debug, error};
 
 struct Interpreter {
     src_version: ProtocolVersion,
@@ -63,50 +67,158 @@ struct Interpreter {
     multicast_locator_list: Option<Vec<Locator>>,
     timestamp: Timestamp,
     recv_time: chrono::Duration,
+    time_source: TimeSource,
+    /// The packet source address, which is cleared by INFO_SRC.
+    source_addr: Option<IpAddr>,
+    /// The packet destination address and port.
+    destination: Locator,
 }
 
-const SEND_TIMEOUT: Duration = Duration::from_millis(100);
+impl Interpreter {
+    /// Gets the event time according to the selected time source.
+    fn event_time(&self) -> chrono::Duration {
+        match self.time_source {
+            TimeSource::Pcap => self.recv_time,
+            TimeSource::Rtps => timestamp_to_duration(self.timestamp).unwrap_or(self.recv_time),
+        }
+    }
+}
+
+const EOF_DRAIN_INTERVAL: Duration = Duration::from_millis(10);
 
 /// The RTPS watcher function.
+///
+/// The receiver is used to discard the oldest queued events. It must
+/// be given with [OverflowStrategy::DropOldest] and only then.
 pub async fn rtps_watcher(
     source: PacketSource,
     tx: flume::Sender<UpdateEvent>,
+    rx: Option<flume::Receiver<UpdateEvent>>,
     cancel_token: CancellationToken,
+    config: CaptureConfig,
+    replay: ReplayControl,
+    stats: CaptureStats,
 ) -> Result<()> {
-    let stream = source.into_stream()?;
+    assert_eq!(
+        rx.is_some(),
+        config.overflow == OverflowStrategy::DropOldest,
+        "the receiver must be given with the drop-oldest overflow strategy only"
+    );
+
+    let stream = source.into_stream(&config, replay, stats.clone())?;
+    let mut savefile = config.create_savefile()?;
 
     // Keep waiting when the packet stream is depleted. This prevents
     // immediate exit when the stream reaches to the end of .pcap
     // file.
-    let stream = stream.chain(stream::pending());
+    let stream = if config.exit_on_eof {
+        stream
+    } else {
+        stream.chain(stream::pending()).boxed()
+    };
 
     // The stream runs until the cancel_token is signaled.
     let mut stream = stream.take_until(cancel_token.cancelled()).boxed();
 
     while let Some(msg) = stream.try_next().await? {
-        let events = handle_msg(&msg);
+        if !config.accepts_ports(&msg.headers.udp) {
+            continue;
+        }
+
+        if let Some(savefile) = &mut savefile {
+            for frame in &msg.raw_frames {
+                savefile.write(&pcap::Packet::new(&frame.header, &frame.data));
+            }
+        }
+
+        let events = handle_msg(&msg, config.time_source);
 
         // Send events to the updater
         for event in events {
-            let send = tokio::time::timeout(SEND_TIMEOUT, tx.send_async(event));
+            if !send_event(&tx, rx.as_ref(), event, &config, &stats).await {
+                return Ok(());
+            }
+        }
+        stats.set_queue_depth(tx.len());
+    }
+
+    if let Some(savefile) = &mut savefile {
+        savefile.flush()?;
+    }
+
+    // Shut down after the updater consumes the remaining events.
+    if config.exit_on_eof && !cancel_token.is_cancelled() {
+        while !tx.is_empty() {
+            tokio::time::sleep(EOF_DRAIN_INTERVAL).await;
+        }
+        cancel_token.cancel();
+    }
+
+    Ok(())
+}
+
+/// Sends an event to the updater following the overflow strategy.
+/// Returns false if the updater is gone.
+async fn send_event(
+    tx: &flume::Sender<UpdateEvent>,
+    rx: Option<&flume::Receiver<UpdateEvent>>,
+    event: UpdateEvent,
+    config: &CaptureConfig,
+    stats: &CaptureStats,
+) -> bool {
+    // The updater reports the drops in abnormalities.
+    match config.overflow {
+        OverflowStrategy::DropOldest => {
+            let rx = rx.expect("the receiver is required to drop the oldest events");
+            let mut event = event;
+            loop {
+                // The updater is gone if the watcher holds the only
+                // receiver.
+                if tx.receiver_count() <= 1 {
+                    return false;
+                }
 
+                match tx.try_send(event) {
+                    Ok(()) => return true,
+                    Err(flume::TrySendError::Disconnected(_)) => return false,
+                    Err(flume::TrySendError::Full(returned)) => {
+                        event = returned;
+                        match rx.try_recv() {
+                            // Control events from the UI are not
+                            // traffic. Queue them again instead of
+                            // dropping them.
+                            Ok(UpdateEvent::ToggleLogging) => {
+                                if tx.send_async(UpdateEvent::ToggleLogging).await.is_err() {
+                                    return false;
+                                }
+                            }
+                            Ok(_) => stats.record_dropped_event(),
+                            Err(_) => {}
+                        }
+                    }
+                }
+            }
+        }
+        OverflowStrategy::Block => tx.send_async(event).await.is_ok(),
+        OverflowStrategy::DropNewest => {
+            let send = tokio::time::timeout(config.send_timeout, tx.send_async(event));
             match send.await {
-                Ok(Ok(())) => {}
-                Ok(Err(flume::SendError(_))) => return Ok(()),
+                Ok(Ok(())) => true,
+                Ok(Err(flume::SendError(_))) => false,
                 Err(_) => {
-                    warn!("congestion occurs");
-                    continue;
+                    stats.record_dropped_event();
+                    true
                 }
             }
         }
     }
-
-    Ok(())
 }
 
 /// Handles a RTPS packet.
-fn handle_msg(msg: &RtpsPacket) -> Vec<UpdateEvent> {
-    let RtpsPacket { headers, message } = msg;
+pub(crate) fn handle_msg(msg: &RtpsPacket, time_source: TimeSource) -> Vec<UpdateEvent> {
+    let RtpsPacket {
+        headers, message, ..
+    } = msg;
 
     let mut interpreter = {
         let Header {
@@ -116,14 +228,21 @@ fn handle_msg(msg: &RtpsPacket) -> Vec<UpdateEvent> {
             ..
         } = message.header;
         let RtpsPacketHeaders {
-            ipv4: Ipv4Header { source, .. },
-            udp: UdpHeader { source_port, .. },
+            ref ip,
+            udp:
+                UdpHeader {
+                    source_port,
+                    destination_port,
+                    ..
+                },
             ts: recv_time,
             ..
         } = *headers;
         assert_ne!(guid_prefix, GuidPrefix::UNKNOWN);
 
-        let unicast_locator = Locator::UdpV4(SocketAddrV4::new(source.into(), source_port));
+        let source = ip.source_addr();
+        let unicast_locator = udp_locator(source, source_port);
+        let destination = udp_locator(ip.destination_addr(), destination_port);
 
         Interpreter {
             src_version: protocol_version,
@@ -134,6 +253,9 @@ fn handle_msg(msg: &RtpsPacket) -> Vec<UpdateEvent> {
             unicast_locator_list: Some(vec![unicast_locator]),
             multicast_locator_list: None,
             recv_time,
+            time_source,
+            source_addr: Some(source),
+            destination,
         }
     };
 
@@ -143,6 +265,7 @@ fn handle_msg(msg: &RtpsPacket) -> Vec<UpdateEvent> {
         guid_prefix: interpreter.src_guid_prefix,
         unicast_locator_list: interpreter.unicast_locator_list.as_ref().unwrap().clone(),
         multicast_locator_list: None,
+        source_addr: interpreter.source_addr,
     }
     .into();
 
@@ -172,7 +295,7 @@ fn handle_submsg(interpreter: &mut Interpreter, submsg: &Submessage) -> Vec<Upda
                 }
             };
             let event = RtpsSubmsgEvent {
-                recv_time: interpreter.recv_time,
+                recv_time: interpreter.event_time(),
                 rtps_time: interpreter.timestamp,
                 kind,
             }
@@ -185,7 +308,7 @@ fn handle_submsg(interpreter: &mut Interpreter, submsg: &Submessage) -> Vec<Upda
                 ReaderSubmessage::NackFrag(data, _) => handle_submsg_nackfrag(interpreter, data),
             };
             let event = RtpsSubmsgEvent {
-                recv_time: interpreter.recv_time,
+                recv_time: interpreter.event_time(),
                 rtps_time: interpreter.timestamp,
                 kind,
             }
@@ -211,6 +334,9 @@ fn handle_submsg(interpreter: &mut Interpreter, submsg: &Submessage) -> Vec<Upda
                     unicast_locator_list: None,
                     multicast_locator_list: None,
                     recv_time: interpreter.recv_time,
+                    time_source: interpreter.time_source,
+                    source_addr: None,
+                    destination: interpreter.destination,
                 };
 
                 vec![]
@@ -231,6 +357,7 @@ fn handle_submsg(interpreter: &mut Interpreter, submsg: &Submessage) -> Vec<Upda
                     unicast_locator_list: info.unicast_locator_list.clone(),
                     multicast_locator_list: info.multicast_locator_list.clone(),
                     recv_time: interpreter.recv_time,
+                    source_addr: interpreter.source_addr,
                 }
                 .into();
 
@@ -253,11 +380,12 @@ fn handle_submsg_data(interpreter: &Interpreter, data: &Data) -> RtpsSubmsgEvent
     let Data {
         writer_id,
         writer_sn,
-        inline_qos: _,
+        ref inline_qos,
         ref serialized_payload,
         ..
     } = *data;
     let writer_guid = GUID::new(interpreter.src_guid_prefix, writer_id);
+    let inline_qos = inline_qos.as_ref().map(decode_inline_qos);
 
     let payload_size = match serialized_payload {
         Some(payload) => payload.len(),
@@ -268,8 +396,8 @@ fn handle_submsg_data(interpreter: &Interpreter, data: &Data) -> RtpsSubmsgEvent
         macro_rules! bail {
             () => {
                 debug!(
-                    "payload deserialization is not implemented for {}",
-                    writer_id.display()
+                    entity_id = %writer_id.display(),
+                    "payload deserialization is not implemented"
                 );
                 return None;
             };
@@ -327,15 +455,44 @@ fn handle_submsg_data(interpreter: &Interpreter, data: &Data) -> RtpsSubmsgEvent
         Some(payload)
     })();
 
+    // Recover vendor-specific parameters that rustdds does not
+    // decode, such as entity names sent by RTI Connext.
+    let vendor_params = match (&payload, serialized_payload) {
+        (Some(_), Some(bytes)) => VendorParams::parse(bytes, interpreter.src_vendor_id.vendor_id),
+        _ => None,
+    };
+
     DataEvent {
         writer_guid,
         writer_sn,
         payload_size,
+        destination: interpreter.destination,
         payload,
+        vendor_params,
+        inline_qos,
     }
     .into()
 }
 
+/// Extracts the inline QoS parameters of interest.
+fn decode_inline_qos(params: &ParameterList) -> InlineQos {
+    let mut inline_qos = InlineQos::default();
+
+    for param in &params.parameters {
+        match param.parameter_id {
+            ParameterId::PID_STATUS_INFO => {
+                inline_qos.status_info = StatusInfo::from_bytes(&param.value);
+            }
+            ParameterId::PID_KEY_HASH => {
+                inline_qos.key_hash = param.value.as_slice().try_into().ok();
+            }
+            _ => {}
+        }
+    }
+
+    inline_qos
+}
+
 fn handle_submsg_datafrag(interpreter: &Interpreter, data: &DataFrag) -> RtpsSubmsgEventKind {
     let DataFrag {
         writer_id,
@@ -377,6 +534,7 @@ fn handle_submsg_datafrag(interpreter: &Interpreter, data: &DataFrag) -> RtpsSub
         fragment_size,
         payload_size,
         payload_hash,
+        destination: interpreter.destination,
     }
     .into()
 }
@@ -389,7 +547,10 @@ fn handle_submsg_gap(interpreter: &Interpreter, data: &Gap) -> RtpsSubmsgEventKi
         ref gap_list,
     } = *data;
     let writer_guid = GUID::new(interpreter.src_guid_prefix, writer_id);
-    let reader_guid = GUID::new(interpreter.dst_guid_prefix.unwrap(), reader_id); // TODO: warn if dst_guid_prefix is not set
+    let reader_guid = GUID::new(
+        interpreter.dst_guid_prefix.unwrap_or(GuidPrefix::UNKNOWN),
+        reader_id,
+    );
 
     // println!("gap {}", writer_id.display());
 
@@ -407,26 +568,27 @@ fn handle_submsg_nackfrag(interpreter: &Interpreter, data: &NackFrag) -> RtpsSub
         reader_id,
         writer_id,
         writer_sn,
-        // ref fragment_number_state,
+        ref fragment_number_state,
         count,
         ..
     } = *data;
-    let writer_guid = GUID::new(interpreter.dst_guid_prefix.unwrap(), writer_id); // TODO: warn if dst_guid_prefix is not set
+    // The updater resolves the writer if INFO_DST is absent.
+    let writer_guid = GUID::new(
+        interpreter.dst_guid_prefix.unwrap_or(GuidPrefix::UNKNOWN),
+        writer_id,
+    );
     let reader_guid = GUID::new(interpreter.src_guid_prefix, reader_id);
-
-    // println!("nack {}\t{fragment_number_state:?}", writer_id.display());
-
-    // let nums: Vec<_> = fragment_number_state
-    //     .iter()
-    //     .map(|FragmentNumber(n)| n)
-    //     .collect();
-    // println!("nack_frag {} {:?}", writer_id.display(), nums);
+    let fragment_numbers: Vec<_> = fragment_number_state
+        .iter()
+        .map(|FragmentNumber(num)| num)
+        .collect();
 
     NackFragEvent {
         writer_guid,
         reader_guid,
         writer_sn,
         count,
+        fragment_numbers,
     }
     .into()
 }
@@ -488,7 +650,11 @@ fn handle_submsg_acknack(interpreter: &Interpreter, data: &AckNack) -> RtpsSubms
         ..
     } = *data;
 
-    let writer_guid = GUID::new(interpreter.dst_guid_prefix.unwrap(), writer_id); // TODO: warn if dst_guid_prefix is not set
+    // The updater resolves the writer if INFO_DST is absent.
+    let writer_guid = GUID::new(
+        interpreter.dst_guid_prefix.unwrap_or(GuidPrefix::UNKNOWN),
+        writer_id,
+    );
     let reader_guid = GUID::new(interpreter.src_guid_prefix, reader_id);
     let base_sn = reader_sn_state.base().0;
     let missing_sn: Vec<_> = reader_sn_state
@@ -513,7 +679,7 @@ where
     T: PlCdrDeserialize,
 {
     let Some(payload) = payload else {
-        error!("no payload found for entity {}", entity_id.display());
+        error!(entity_id = %entity_id.display(), "no payload found");
         return None;
     };
     let result = PlCdrDeserializerAdapter::from_bytes(payload, RepresentationIdentifier::PL_CDR_LE);
@@ -521,11 +687,32 @@ where
         Ok(data) => data,
         Err(err) => {
             error!(
-                "fail to parse payload for entity {}: {err}",
-                entity_id.display()
+                entity_id = %entity_id.display(),
+                error = %err,
+                "fail to parse payload"
             );
             return None;
         }
     };
     Some(data)
 }
+
+/// Converts an RTPS timestamp to the duration since the Unix epoch.
+/// Returns `None` if the timestamp is invalid.
+fn timestamp_to_duration(timestamp: Timestamp) -> Option<chrono::Duration> {
+    if timestamp == Timestamp::INVALID {
+        return None;
+    }
+
+    // The timestamp is a 32.32 fixed-point number of seconds.
+    let ticks = timestamp.to_ticks();
+    let nanos = ((ticks as u128 * 1_000_000_000) >> 32) as i64;
+    Some(chrono::Duration::nanoseconds(nanos))
+}
+
+fn udp_locator(addr: IpAddr, port: u16) -> Locator {
+    match addr {
+        IpAddr::V4(addr) => Locator::UdpV4(SocketAddrV4::new(addr, port)),
+        IpAddr::V6(addr) => Locator::UdpV6(SocketAddrV6::new(addr, port, 0, 0)),
+    }
+}
@@ -1,11 +1,18 @@
 //! The singleton state that keeps track of all participant and entity
 //! status.
 
-use crate::{config::TICK_INTERVAL, logger::Logger, utils::TimedStat};
+use crate::{
+    config::{ACK_LATENCY_WINDOW, RATE_HISTORY_LEN},
+    logger::Logger,
+    message::StatusInfo,
+    utils::{RateHistory, RunningStat, SnRanges, TimedStat},
+};
 use chrono::{DateTime, Local};
+use clap::ValueEnum;
 use rbtree_defrag_buffer::DefragBuf;
 use rustdds::{
-    discovery::{DiscoveredReaderData, DiscoveredWriterData},
+    discovery::{DiscoveredReaderData, DiscoveredTopicData, DiscoveredWriterData},
+    policy::Reliability,
     structure::{
         guid::{EntityId, GuidPrefix},
         locator::Locator,
@@ -13,7 +20,7 @@ use rustdds::{
     SequenceNumber, GUID,
 };
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     ops::Range,
     time::Instant,
 };
@@ -22,71 +29,199 @@ use std::{
 #[derive(Debug)]
 pub struct State {
     pub tick_since: Instant,
+    /// The window over which the message, bit and acknack rates are
+    /// averaged.
+    pub rate_window: chrono::Duration,
     pub participants: HashMap<GuidPrefix, ParticipantState>,
     pub topics: HashMap<String, TopicState>,
+    /// The recent abnormalities in time order. The oldest ones are
+    /// dropped beyond `--max-abnormalities`.
     pub abnormalities: Vec<Abnormality>,
+    /// The number of abnormalities dropped from the front of
+    /// `abnormalities`.
+    pub dropped_abnormality_count: usize,
+    /// The highest severity among the dropped abnormalities.
+    pub dropped_max_severity: Option<Severity>,
     pub stat: Statistics,
     pub logger: Option<Logger>,
 }
 
-impl Default for State {
-    fn default() -> Self {
+impl Clone for State {
+    /// Copies the state for display. The logger owns the open log
+    /// files, so it stays with the original state.
+    fn clone(&self) -> Self {
+        Self {
+            tick_since: self.tick_since,
+            rate_window: self.rate_window,
+            participants: self.participants.clone(),
+            topics: self.topics.clone(),
+            abnormalities: self.abnormalities.clone(),
+            dropped_abnormality_count: self.dropped_abnormality_count,
+            dropped_max_severity: self.dropped_max_severity,
+            stat: self.stat.clone(),
+            logger: None,
+        }
+    }
+}
+
+impl State {
+    /// Creates an empty state whose rates are averaged over
+    /// `rate_window`.
+    pub fn new(rate_window: chrono::Duration) -> Self {
         Self {
             tick_since: Instant::now(),
+            rate_window,
             participants: HashMap::new(),
             topics: HashMap::new(),
             abnormalities: vec![],
+            dropped_abnormality_count: 0,
+            dropped_max_severity: None,
             stat: Statistics::default(),
             logger: None,
         }
     }
+
+    /// Removes the NACK_FRAG requests on the sample of the writer once
+    /// the sample is completed or dropped.
+    pub fn clear_nackfrag(&mut self, writer_guid: GUID, writer_sn: SequenceNumber) {
+        for participant in self.participants.values_mut() {
+            for reader in participant.readers.values_mut() {
+                reader.nackfrag.retain(|&guid, nackfrag| {
+                    guid != writer_guid || nackfrag.writer_sn != writer_sn.0
+                });
+            }
+        }
+    }
 }
 
 /// The state for a participant.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ParticipantState {
     pub writers: HashMap<EntityId, WriterState>,
     pub readers: HashMap<EntityId, ReaderState>,
     pub unicast_locator_list: Option<Vec<Locator>>,
     pub multicast_locator_list: Option<Vec<Locator>>,
+    pub entity_name: Option<String>,
+    pub product_version: Option<[u8; 4]>,
+    /// The vendor ID advertised in SPDP data.
+    pub vendor_id: Option<[u8; 2]>,
+    /// The RTPS protocol version (major, minor) advertised in SPDP
+    /// data.
+    pub protocol_version: Option<(u8, u8)>,
     pub total_msg_count: usize,
     pub total_byte_count: usize,
     pub total_acknack_count: usize,
     pub msg_rate_stat: TimedStat,
     pub bit_rate_stat: TimedStat,
     pub acknack_rate_stat: TimedStat,
+    /// The total number of SPDP announcements.
+    pub spdp_count: usize,
+    /// The lease duration advertised in SPDP data.
+    pub lease_duration: Option<chrono::Duration>,
+    /// The receipt time when the current lease period begins.
+    pub spdp_period_start: Option<chrono::Duration>,
+    /// The number of SPDP announcements in the current lease period.
+    pub spdp_period_count: usize,
+    pub spdp_flood_reported: bool,
+    /// The number of endpoints dropped after the participant reaches
+    /// the endpoint limit.
+    pub dropped_endpoint_count: usize,
+    pub locator_mismatch_reported: bool,
+    /// The receipt time of the last packet from the participant.
+    pub last_recv_time: Option<chrono::Duration>,
+    /// The time since `last_recv_time`, updated on each tick.
+    pub age: Option<chrono::Duration>,
 }
 
-impl Default for ParticipantState {
-    fn default() -> Self {
-        let window = chrono::Duration::from_std(TICK_INTERVAL).unwrap();
-
+impl ParticipantState {
+    /// Creates the state whose rates are averaged over `rate_window`.
+    pub fn new(rate_window: chrono::Duration) -> Self {
         Self {
             writers: HashMap::new(),
             readers: HashMap::new(),
             unicast_locator_list: None,
             multicast_locator_list: None,
+            entity_name: None,
+            product_version: None,
+            vendor_id: None,
+            protocol_version: None,
             total_msg_count: 0,
             total_byte_count: 0,
             total_acknack_count: 0,
-            msg_rate_stat: TimedStat::new(window),
-            bit_rate_stat: TimedStat::new(window),
-            acknack_rate_stat: TimedStat::new(window),
+            msg_rate_stat: TimedStat::new(rate_window),
+            bit_rate_stat: TimedStat::new(rate_window),
+            acknack_rate_stat: TimedStat::new(rate_window),
+            spdp_count: 0,
+            lease_duration: None,
+            spdp_period_start: None,
+            spdp_period_count: 0,
+            spdp_flood_reported: false,
+            dropped_endpoint_count: 0,
+            locator_mismatch_reported: false,
+            last_recv_time: None,
+            age: None,
         }
     }
 }
 
 /// The state for a writer entity.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct WriterState {
     pub last_sn: Option<SequenceNumber>,
     pub frag_messages: HashMap<SequenceNumber, FragmentedMessage>,
+    /// The number of fragmented messages dropped because the missing
+    /// fragments did not arrive in time.
+    pub frag_timeout_count: usize,
     pub total_msg_count: usize,
     pub total_byte_count: usize,
     pub msg_rate_stat: TimedStat,
     pub bit_rate_stat: TimedStat,
     pub heartbeat: Option<HeartbeatState>,
     pub data: Option<DiscoveredWriterData>,
+    pub entity_name: Option<String>,
+    /// The number of DATA submessages without payload, which are
+    /// typically disposals or unregistrations on keyed topics.
+    pub empty_msg_count: usize,
+    pub disposal_count: usize,
+    pub unregister_count: usize,
+    pub disposal_rate_stat: TimedStat,
+    pub disposal_storm_reported: bool,
+    /// The status info of the last sample carrying one in its inline
+    /// QoS.
+    pub last_status_info: Option<StatusInfo>,
+    /// The key hash of the last sample carrying one in its inline QoS.
+    pub last_key_hash: Option<[u8; 16]>,
+    /// The recently seen key hashes and when each was last seen. The
+    /// least recently seen ones are evicted beyond
+    /// [KEY_HASH_HISTORY_LEN](crate::config::KEY_HASH_HISTORY_LEN).
+    pub key_hashes: HashMap<[u8; 16], chrono::Duration>,
+    /// The number of key hashes evicted from `key_hashes`.
+    pub evicted_key_hash_count: usize,
+    /// The statistics of serialized payload sizes of non-empty
+    /// samples.
+    pub payload_size_stat: RunningStat,
+    pub payload_size_reported: bool,
+    /// Set when user data arrives before the writer is discovered.
+    /// It is cleared once the discovery data arrives.
+    pub data_before_discovery: bool,
+    /// The highest sequence number sent to each destination locator.
+    pub highest_sn: HashMap<Locator, SequenceNumber>,
+    /// The bytes of samples that repeat a seen sequence number, which
+    /// are likely retransmissions.
+    pub retx_byte_count: usize,
+    /// The sequence numbers announced irrelevant by GAP submessages,
+    /// which will never be delivered.
+    pub lost_sn: SnRanges,
+    /// The bit rate sampled on each tick.
+    pub bit_rate_history: RateHistory,
+    /// The time each recent sequence number is first sent.
+    pub sent_times: BTreeMap<i64, chrono::Duration>,
+    /// The seconds from sending a sample to its acknowledgment.
+    pub ack_latency_stat: TimedStat,
+    /// The receipt time of the last submessage from the writer.
+    pub last_recv_time: Option<chrono::Duration>,
+    /// The time since `last_recv_time`, updated on each tick.
+    pub age: Option<chrono::Duration>,
 }
 
 impl WriterState {
@@ -99,33 +234,84 @@ impl WriterState {
         let type_name = &self.data.as_ref()?.publication_topic_data.type_name;
         Some(type_name)
     }
-}
 
-impl Default for WriterState {
-    fn default() -> Self {
-        let window = chrono::Duration::from_std(TICK_INTERVAL).unwrap();
+    /// Checks if the writer is discovered to be best-effort.
+    pub fn is_best_effort(&self) -> bool {
+        let Some(data) = &self.data else {
+            return false;
+        };
+        matches!(
+            data.publication_topic_data.reliability,
+            Some(Reliability::BestEffort)
+        )
+    }
+}
 
+impl WriterState {
+    /// Creates the state whose rates are averaged over `rate_window`.
+    pub fn new(rate_window: chrono::Duration) -> Self {
         Self {
             frag_messages: HashMap::new(),
+            frag_timeout_count: 0,
             last_sn: None,
             heartbeat: None,
             total_msg_count: 0,
             total_byte_count: 0,
-            msg_rate_stat: TimedStat::new(window),
-            bit_rate_stat: TimedStat::new(window),
+            msg_rate_stat: TimedStat::new(rate_window),
+            bit_rate_stat: TimedStat::new(rate_window),
             data: None,
+            entity_name: None,
+            empty_msg_count: 0,
+            disposal_count: 0,
+            unregister_count: 0,
+            disposal_rate_stat: TimedStat::new(rate_window),
+            disposal_storm_reported: false,
+            last_status_info: None,
+            last_key_hash: None,
+            key_hashes: HashMap::new(),
+            evicted_key_hash_count: 0,
+            payload_size_stat: RunningStat::new(),
+            payload_size_reported: false,
+            data_before_discovery: false,
+            highest_sn: HashMap::new(),
+            retx_byte_count: 0,
+            lost_sn: SnRanges::new(),
+            bit_rate_history: RateHistory::new(RATE_HISTORY_LEN),
+            sent_times: BTreeMap::new(),
+            ack_latency_stat: TimedStat::new(
+                chrono::Duration::from_std(ACK_LATENCY_WINDOW).unwrap(),
+            ),
+            last_recv_time: None,
+            age: None,
         }
     }
 }
 
 /// The state for a reader entity.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ReaderState {
     pub data: Option<DiscoveredReaderData>,
-    pub acknack: Option<AckNackState>,
+    pub entity_name: Option<String>,
+    /// The last ACKNACK sent to each writer.
+    pub acknack: HashMap<GUID, AckNackState>,
     pub last_sn: Option<i64>,
+    /// The last acknowledged sequence number per writer.
+    pub acked_sn: HashMap<GUID, i64>,
     pub total_acknack_count: usize,
     pub acknack_rate_stat: TimedStat,
+    /// Set when the reader requests samples that the writer declared
+    /// lost by GAP. It is cleared once the requests stop.
+    pub unrecoverable_reported: bool,
+    /// How long the oldest missing sequence number has been
+    /// requested from any writer, updated on each tick.
+    pub missing_age: Option<chrono::Duration>,
+    pub stuck_reported: bool,
+    /// The last NACK_FRAG sent to each writer.
+    pub nackfrag: HashMap<GUID, NackFragState>,
+    /// The receipt time of the last submessage from the reader.
+    pub last_recv_time: Option<chrono::Duration>,
+    /// The time since `last_recv_time`, updated on each tick.
+    pub age: Option<chrono::Duration>,
 }
 
 impl ReaderState {
@@ -138,25 +324,47 @@ impl ReaderState {
         let type_name = self.data.as_ref()?.subscription_topic_data.type_name();
         Some(type_name)
     }
-}
 
-impl Default for ReaderState {
-    fn default() -> Self {
-        let window = chrono::Duration::from_std(TICK_INTERVAL).unwrap();
+    /// Gets the ACKNACK state of the writer from which the reader has
+    /// been requesting missing samples for the longest time.
+    pub fn longest_missing(&self) -> Option<(GUID, &AckNackState)> {
+        self.acknack
+            .iter()
+            .filter_map(|(&writer_guid, acknack)| {
+                let since = acknack.missing_since?;
+                Some((since, writer_guid, acknack))
+            })
+            .min_by_key(|&(since, _, _)| since)
+            .map(|(_, writer_guid, acknack)| (writer_guid, acknack))
+    }
+}
 
+impl ReaderState {
+    /// Creates the state whose rates are averaged over `rate_window`.
+    pub fn new(rate_window: chrono::Duration) -> Self {
         Self {
             last_sn: None,
             data: None,
-            acknack: None,
+            entity_name: None,
+            acknack: HashMap::new(),
+            acked_sn: HashMap::new(),
             total_acknack_count: 0,
-            acknack_rate_stat: TimedStat::new(window),
+            acknack_rate_stat: TimedStat::new(rate_window),
+            unrecoverable_reported: false,
+            missing_age: None,
+            stuck_reported: false,
+            nackfrag: HashMap::new(),
+            last_recv_time: None,
+            age: None,
         }
     }
 }
 
 /// The state for a topic.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TopicState {
+    /// The topic type and QoS announced by the builtin topic writer.
+    pub data: Option<DiscoveredTopicData>,
     pub total_msg_count: usize,
     pub total_byte_count: usize,
     pub msg_rate_stat: TimedStat,
@@ -165,21 +373,64 @@ pub struct TopicState {
     pub acknack_rate_stat: TimedStat,
     pub readers: HashSet<GUID>,
     pub writers: HashSet<GUID>,
+    /// The sum of writer sequence numbers over matched reader-writer
+    /// pairs.
+    pub written_sn_sum: i64,
+    /// The sum of acknowledged sequence numbers over matched
+    /// reader-writer pairs.
+    pub acked_sn_sum: i64,
+    pub ack_lag_reported: bool,
+    /// The ratio of the bit rate to the bandwidth budget.
+    pub budget_usage: Option<f64>,
+    pub budget_reported: bool,
+    /// The bytes of likely retransmitted samples.
+    pub retx_byte_count: usize,
+    pub retx_reported: bool,
+    /// The bit rate sampled on each tick.
+    pub bit_rate_history: RateHistory,
+    /// The (writer, reader) pairs reported for incompatible QoS.
+    pub qos_mismatch_reported: HashSet<(GUID, GUID)>,
+    /// The type name first advertised by a writer or reader on the
+    /// topic.
+    pub advertised_type_name: Option<String>,
+    pub type_conflict_reported: bool,
 }
 
-impl Default for TopicState {
-    fn default() -> Self {
-        let window = chrono::Duration::from_std(TICK_INTERVAL).unwrap();
+impl TopicState {
+    /// Gets the type name in the discovered topic data, or the one
+    /// first advertised by the endpoints if the topic data is absent.
+    pub fn type_name(&self) -> Option<&str> {
+        match &self.data {
+            Some(data) => Some(&data.topic_data.type_name),
+            None => self.advertised_type_name.as_deref(),
+        }
+    }
+}
 
+impl TopicState {
+    /// Creates the state whose rates are averaged over `rate_window`.
+    pub fn new(rate_window: chrono::Duration) -> Self {
         Self {
+            data: None,
             total_msg_count: 0,
             total_byte_count: 0,
-            msg_rate_stat: TimedStat::new(window),
-            bit_rate_stat: TimedStat::new(window),
+            msg_rate_stat: TimedStat::new(rate_window),
+            bit_rate_stat: TimedStat::new(rate_window),
             total_acknack_count: 0,
-            acknack_rate_stat: TimedStat::new(window),
+            acknack_rate_stat: TimedStat::new(rate_window),
             readers: HashSet::new(),
             writers: HashSet::new(),
+            written_sn_sum: 0,
+            acked_sn_sum: 0,
+            ack_lag_reported: false,
+            budget_usage: None,
+            budget_reported: false,
+            retx_byte_count: 0,
+            retx_reported: false,
+            bit_rate_history: RateHistory::new(RATE_HISTORY_LEN),
+            qos_mismatch_reported: HashSet::new(),
+            advertised_type_name: None,
+            type_conflict_reported: false,
         }
     }
 }
@@ -193,10 +444,32 @@ pub struct FragmentedMessage {
     /// A range -> payload hash mapping
     pub intervals: HashMap<Range<usize>, u64>,
     pub defrag_buf: DefragBuf,
+    /// The receipt time of the last fragment.
+    pub last_update: chrono::Duration,
+}
+
+impl Clone for FragmentedMessage {
+    /// Rebuilds the defragmentation buffer from the received
+    /// intervals.
+    fn clone(&self) -> Self {
+        let mut defrag_buf = DefragBuf::new(self.num_fragments);
+        for range in self.intervals.keys() {
+            let _ = defrag_buf.insert(range.clone());
+        }
+
+        Self {
+            data_size: self.data_size,
+            num_fragments: self.num_fragments,
+            recvd_fragments: self.recvd_fragments,
+            intervals: self.intervals.clone(),
+            defrag_buf,
+            last_update: self.last_update,
+        }
+    }
 }
 
 impl FragmentedMessage {
-    pub fn new(data_size: usize, fragment_size: usize) -> Self {
+    pub fn new(data_size: usize, fragment_size: usize, recv_time: chrono::Duration) -> Self {
         let num_fragments = (data_size + fragment_size - 1) / fragment_size;
         Self {
             data_size,
@@ -204,6 +477,7 @@ impl FragmentedMessage {
             recvd_fragments: 0,
             defrag_buf: DefragBuf::new(num_fragments),
             intervals: HashMap::new(),
+            last_update: recv_time,
         }
     }
 }
@@ -216,7 +490,7 @@ pub struct FragmentInterval {
 }
 
 /// The state that keeps the counts and time of heartbeat messages.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HeartbeatState {
     pub first_sn: i64,
     pub last_sn: i64,
@@ -225,25 +499,56 @@ pub struct HeartbeatState {
 }
 
 /// An abnormal event report.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Abnormality {
     pub when: DateTime<Local>,
     pub writer_guid: Option<GUID>,
     pub reader_guid: Option<GUID>,
     pub topic_name: Option<String>,
+    pub severity: Severity,
     pub desc: String,
 }
 
+/// The severity level of an abnormality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
 /// The state that keeping track of ACK-NACK message counts and time.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AckNackState {
     pub missing_sn: Vec<i64>,
     pub count: i32,
     pub since: Instant,
+    /// The receipt time of the first ACKNACK that requests the oldest
+    /// missing sequence number, or `None` if nothing is missing.
+    pub missing_since: Option<chrono::Duration>,
+}
+
+/// The fragments of a sample that a reader requests by NACK_FRAG.
+#[derive(Debug, Clone)]
+pub struct NackFragState {
+    pub writer_sn: i64,
+    /// The requested fragment numbers, which start from 1.
+    pub missing_frags: SnRanges,
+    pub count: i32,
 }
 
 /// General traffic statistics.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Statistics {
     pub packet_count: usize,
     pub data_submsg_count: usize,
@@ -252,6 +557,7 @@ pub struct Statistics {
     pub ackfrag_submsg_count: usize,
     pub heartbeat_submsg_count: usize,
     pub heartbeat_frag_submsg_count: usize,
+    pub gap_submsg_count: usize,
 }
 
 impl Default for Statistics {
@@ -264,6 +570,7 @@ impl Default for Statistics {
             ackfrag_submsg_count: 0,
             heartbeat_submsg_count: 0,
             heartbeat_frag_submsg_count: 0,
+            gap_submsg_count: 0,
         }
     }
 }
@@ -0,0 +1,177 @@
+//! Periodic removal of state entries that are no longer active, which
+//! keeps the state from growing forever in long captures.
+
+use crate::{
+    config::FRAG_MESSAGE_TIMEOUT,
+    state::{Abnormality, Severity, State},
+};
+use chrono::Local;
+use rustdds::{SequenceNumber, GUID};
+use std::time::Duration;
+
+pub struct StateCleanup {
+    interval: chrono::Duration,
+    retention: chrono::Duration,
+    /// The capture time of the last cleanup.
+    last_cleanup: Option<chrono::Duration>,
+}
+
+impl StateCleanup {
+    /// Creates a cleanup that runs every `interval` and removes
+    /// participants without endpoints that have been silent for
+    /// `retention`.
+    pub fn new(interval: Duration, retention: Duration) -> Self {
+        Self {
+            interval: chrono::Duration::from_std(interval).unwrap(),
+            retention: chrono::Duration::from_std(retention).unwrap(),
+            last_cleanup: None,
+        }
+    }
+
+    /// Checks if the cleanup interval has passed at the capture time.
+    pub fn should_cleanup(&self, now: chrono::Duration) -> bool {
+        match self.last_cleanup {
+            Some(last_cleanup) => now - last_cleanup >= self.interval,
+            None => true,
+        }
+    }
+
+    /// Removes the participants without readers and writers that
+    /// are not heard from within the retention, the topics without
+    /// readers, writers and discovered topic data, and the stale
+    /// fragmented messages.
+    pub fn cleanup(&mut self, state: &mut State, now: chrono::Duration) {
+        self.last_cleanup = Some(now);
+
+        drop_stale_frag_messages(state, now);
+
+        let retention = self.retention;
+        state.participants.retain(|_, part| {
+            let is_empty = part.writers.is_empty() && part.readers.is_empty();
+            let is_silent = match part.last_recv_time {
+                Some(last_recv_time) => now - last_recv_time > retention,
+                None => true,
+            };
+            !(is_empty && is_silent)
+        });
+
+        // Topics announced by DiscoveredTopicData stay listed even
+        // without any endpoint.
+        state.topics.retain(|_, topic| {
+            topic.data.is_some() || !topic.readers.is_empty() || !topic.writers.is_empty()
+        });
+    }
+}
+
+/// Drops the fragmented messages whose last fragment arrived more
+/// than [FRAG_MESSAGE_TIMEOUT] before `now`, which bounds the memory
+/// held by writers that lose fragments.
+fn drop_stale_frag_messages(state: &mut State, now: chrono::Duration) {
+    let timeout = chrono::Duration::from_std(FRAG_MESSAGE_TIMEOUT).unwrap();
+
+    let mut dropped = vec![];
+
+    for (&guid_prefix, participant) in &mut state.participants {
+        for (&entity_id, writer) in &mut participant.writers {
+            let stale: Vec<SequenceNumber> = writer
+                .frag_messages
+                .iter()
+                .filter(|(_, frag_msg)| now - frag_msg.last_update > timeout)
+                .map(|(&sn, _)| sn)
+                .collect();
+            if stale.is_empty() {
+                continue;
+            }
+
+            let writer_guid = GUID::new(guid_prefix, entity_id);
+            for &sn in &stale {
+                writer.frag_messages.remove(&sn);
+                dropped.push((writer_guid, sn));
+            }
+            writer.frag_timeout_count += stale.len();
+
+            let mut stale_sn: Vec<i64> = stale.iter().map(|sn| sn.0).collect();
+            stale_sn.sort_unstable();
+
+            state.abnormalities.push(Abnormality {
+                when: Local::now(),
+                writer_guid: Some(writer_guid),
+                reader_guid: None,
+                topic_name: writer.topic_name().map(|t| t.to_string()),
+                severity: Severity::Warning,
+                desc: format!(
+                    "dropped incomplete fragmented messages with sn {stale_sn:?} after {}s \
+                     without new fragments",
+                    timeout.num_seconds()
+                ),
+            });
+        }
+    }
+
+    for (writer_guid, sn) in dropped {
+        state.clear_nackfrag(writer_guid, sn);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::{FragmentedMessage, NackFragState, ParticipantState, ReaderState, WriterState},
+        utils::SnRanges,
+    };
+    use rustdds::structure::guid::{EntityId, EntityKind, GuidPrefix};
+
+    #[test]
+    fn cleanup_removes_silent_participants_and_stale_fragments() {
+        let rate_window = chrono::Duration::seconds(1);
+        let mut state = State::new(rate_window);
+
+        // An empty participant last heard at the capture start.
+        let silent_prefix = GuidPrefix::new(&[1; 12]);
+        let mut silent = ParticipantState::new(rate_window);
+        silent.last_recv_time = Some(chrono::Duration::zero());
+        state.participants.insert(silent_prefix, silent);
+
+        // A writer with a fragmented message that stops receiving
+        // fragments at the capture start.
+        let writer_prefix = GuidPrefix::new(&[2; 12]);
+        let writer_id = EntityId::new([0, 0, 1], EntityKind::WRITER_NO_KEY_USER_DEFINED);
+        let mut writer = WriterState::new(rate_window);
+        writer.frag_messages.insert(
+            SequenceNumber::from(1),
+            FragmentedMessage::new(4096, 1024, chrono::Duration::zero()),
+        );
+        let mut participant = ParticipantState::new(rate_window);
+        participant.writers.insert(writer_id, writer);
+        state.participants.insert(writer_prefix, participant);
+
+        // A reader waiting for the fragments of the message.
+        let reader_prefix = GuidPrefix::new(&[3; 12]);
+        let reader_id = EntityId::new([0, 0, 1], EntityKind::READER_NO_KEY_USER_DEFINED);
+        let mut reader = ReaderState::new(rate_window);
+        reader.nackfrag.insert(
+            GUID::new(writer_prefix, writer_id),
+            NackFragState {
+                writer_sn: 1,
+                missing_frags: SnRanges::new(),
+                count: 1,
+            },
+        );
+        let mut participant = ParticipantState::new(rate_window);
+        participant.readers.insert(reader_id, reader);
+        state.participants.insert(reader_prefix, participant);
+
+        let mut cleanup = StateCleanup::new(Duration::from_secs(10), Duration::from_secs(60));
+        let now = chrono::Duration::seconds(120);
+        assert!(cleanup.should_cleanup(now));
+        cleanup.cleanup(&mut state, now);
+
+        assert!(!state.participants.contains_key(&silent_prefix));
+        let writer = &state.participants[&writer_prefix].writers[&writer_id];
+        assert!(writer.frag_messages.is_empty());
+        assert_eq!(writer.frag_timeout_count, 1);
+        let reader = &state.participants[&reader_prefix].readers[&reader_id];
+        assert!(reader.nackfrag.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+//! The summary printed on exit when the text user interface is
+//! disabled.
+
+use crate::{state::State, utils::GUIDExt};
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// The number of the busiest topics in the summary.
+const TOP_TOPICS: usize = 10;
+
+/// The output format of the summary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SummaryFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    participants: usize,
+    topics: usize,
+    packets: usize,
+    submessages: SubmessageCounts,
+    top_topics: Vec<TopicSummary>,
+    abnormalities: Vec<AbnormalitySummary>,
+}
+
+#[derive(Debug, Serialize)]
+struct SubmessageCounts {
+    data: usize,
+    datafrag: usize,
+    acknack: usize,
+    nackfrag: usize,
+    heartbeat: usize,
+    heartbeat_frag: usize,
+    gap: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct TopicSummary {
+    name: String,
+    bytes: usize,
+    msgs: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct AbnormalitySummary {
+    when: DateTime<Local>,
+    severity: &'static str,
+    writer_guid: Option<String>,
+    reader_guid: Option<String>,
+    topic_name: Option<String>,
+    desc: String,
+}
+
+/// Prints the traffic statistics, the topics with the most bytes and
+/// the abnormalities.
+pub fn print_summary(out: &mut dyn Write, state: &State, format: SummaryFormat) -> Result<()> {
+    let summary = Summary::new(state);
+
+    match format {
+        SummaryFormat::Text => summary.print_text(out)?,
+        SummaryFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(&summary)?)?,
+    }
+
+    Ok(())
+}
+
+impl Summary {
+    fn new(state: &State) -> Self {
+        let stat = &state.stat;
+
+        let mut topics: Vec<_> = state.topics.iter().collect();
+        topics.sort_unstable_by(|(lname, ltopic), (rname, rtopic)| {
+            rtopic
+                .total_byte_count
+                .cmp(&ltopic.total_byte_count)
+                .then_with(|| lname.cmp(rname))
+        });
+        let top_topics = topics
+            .into_iter()
+            .take(TOP_TOPICS)
+            .map(|(name, topic)| TopicSummary {
+                name: name.clone(),
+                bytes: topic.total_byte_count,
+                msgs: topic.total_msg_count,
+            })
+            .collect();
+
+        let abnormalities = state
+            .abnormalities
+            .iter()
+            .map(|report| AbnormalitySummary {
+                when: report.when,
+                severity: report.severity.as_str(),
+                writer_guid: report.writer_guid.map(|guid| guid.display().to_string()),
+                reader_guid: report.reader_guid.map(|guid| guid.display().to_string()),
+                topic_name: report.topic_name.clone(),
+                desc: report.desc.clone(),
+            })
+            .collect();
+
+        Self {
+            participants: state.participants.len(),
+            topics: state.topics.len(),
+            packets: stat.packet_count,
+            submessages: SubmessageCounts {
+                data: stat.data_submsg_count,
+                datafrag: stat.datafrag_submsg_count,
+                acknack: stat.acknack_submsg_count,
+                nackfrag: stat.ackfrag_submsg_count,
+                heartbeat: stat.heartbeat_submsg_count,
+                heartbeat_frag: stat.heartbeat_frag_submsg_count,
+                gap: stat.gap_submsg_count,
+            },
+            top_topics,
+            abnormalities,
+        }
+    }
+
+    fn print_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        let counts = &self.submessages;
+
+        writeln!(out, "participants:   {}", self.participants)?;
+        writeln!(out, "topics:         {}", self.topics)?;
+        writeln!(out, "packets:        {}", self.packets)?;
+        writeln!(out, "data:           {}", counts.data)?;
+        writeln!(out, "datafrag:       {}", counts.datafrag)?;
+        writeln!(out, "acknack:        {}", counts.acknack)?;
+        writeln!(out, "nackfrag:       {}", counts.nackfrag)?;
+        writeln!(out, "heartbeat:      {}", counts.heartbeat)?;
+        writeln!(out, "heartbeat frag: {}", counts.heartbeat_frag)?;
+        writeln!(out, "gap:            {}", counts.gap)?;
+
+        writeln!(out)?;
+        writeln!(out, "top topics by bytes:")?;
+        for topic in &self.top_topics {
+            writeln!(
+                out,
+                "  {:>12} bytes  {:>8} msgs  {}",
+                topic.bytes, topic.msgs, topic.name
+            )?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "abnormalities: {}", self.abnormalities.len())?;
+        for report in &self.abnormalities {
+            let topic_name = report.topic_name.as_deref().unwrap_or("-");
+            writeln!(
+                out,
+                "  {} {:<7} {topic_name}: {}",
+                report.when.format("%H:%M:%S%.3f"),
+                report.severity,
+                report.desc
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,41 +1,61 @@
 //! The text-user-interface.
 
+mod detail;
 mod tab_abnormality;
+mod tab_fragment;
+mod tab_locator;
 mod tab_participant;
 mod tab_reader;
 mod tab_stat;
 mod tab_topic;
 mod tab_writer;
+mod topic_entities;
+mod topic_filter;
+mod ui_state;
 mod value;
 mod xtable;
 
 use self::{
     tab_abnormality::{AbnormalityTable, AbnormalityTableState},
+    tab_fragment::{FragmentTable, FragmentTableState},
+    tab_locator::{LocatorTable, LocatorTableState},
     tab_participant::{ParticipantTable, ParticipantTableState},
     tab_reader::{ReaderTable, ReaderTableState},
     tab_stat::{StatTable, StatTableState},
     tab_topic::{TopicTable, TopicTableState},
     tab_writer::{WriterTable, WriterTableState},
+    topic_entities::TopicEntityTable,
+    topic_filter::TopicFilter,
+    ui_state::UiState,
+    value::{StatSummary, TimeFormat, Value},
+    xtable::XTableState,
+};
+use crate::{
+    message::UpdateEvent,
+    opts::Opts,
+    rtps::{CaptureStats, ReplayControl},
+    state::State,
 };
-use crate::{message::UpdateEvent, state::State};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use flume::SendTimeoutError;
+use itertools::izip;
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout},
     prelude::*,
     style::{Color, Style},
     symbols::DOT,
-    widgets::{Block, Borders, Clear, Paragraph, Tabs},
+    widgets::{Block, Borders, Clear, Paragraph, Sparkline, Tabs},
     Frame, Terminal,
 };
 use std::{
     io,
     ops::ControlFlow,
+    path::PathBuf,
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
@@ -49,6 +69,8 @@ const TAB_TITLES: &[&str] = &[
     "Topics",
     "Statistics",
     "Abnormalities",
+    "Fragments",
+    "Locators",
 ];
 const TAB_IDX_PARTICIPANT: usize = 0;
 const TAB_IDX_WRITER: usize = 1;
@@ -56,6 +78,11 @@ const TAB_IDX_READER: usize = 2;
 const TAB_IDX_TOPIC: usize = 3;
 const TAB_IDX_STATISTICS: usize = 4;
 const TAB_IDX_ABNORMALITIES: usize = 5;
+const TAB_IDX_FRAGMENT: usize = 6;
+const TAB_IDX_LOCATOR: usize = 7;
+
+/// How long a notice stays on the tray.
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
 
 pub(crate) struct Tui {
     tab_participant: ParticipantTableState,
@@ -64,35 +91,129 @@ pub(crate) struct Tui {
     tab_topic: TopicTableState,
     tab_stat: StatTableState,
     tab_abnormality: AbnormalityTableState,
+    tab_fragment: FragmentTableState,
+    tab_locator: LocatorTableState,
     tick_dur: Duration,
     tab_index: usize,
     focus: Focus,
+    /// The scroll offset of the detail popup.
+    detail_scroll: u16,
+    /// The topic whose writers and readers are listed in the
+    /// drill-down view.
+    drill_topic: Option<String>,
+    /// The topic given by --focus-topic, which is drilled down into
+    /// once it is discovered.
+    pending_drill_topic: Option<String>,
+    topic_entities: XTableState,
+    /// Whether the detail popup describes the drill-down topic itself
+    /// instead of the selected writer or reader.
+    detail_on_topic: bool,
+    /// The topic name filter applied to the Topics and Writers tabs.
+    topic_filter: Option<TopicFilter>,
+    /// The text typed on the filter input line.
+    filter_input: String,
+    /// The error of the last typed filter, e.g. a malformed regex.
+    filter_error: Option<String>,
     cancel_token: CancellationToken,
     tx: flume::Sender<UpdateEvent>,
     state: Arc<Mutex<State>>,
+    /// The replay control, which is only available for offline
+    /// captures.
+    replay: Option<ReplayControl>,
+    seek_step: chrono::Duration,
+    capture_stats: CaptureStats,
+    /// The time when the TUI starts.
+    since: Instant,
+    no_rtps_hint_delay: Duration,
+    /// The time to wait for the updater to accept a command.
+    send_timeout: Duration,
+    /// The file that keeps the table layouts across runs, or `None`
+    /// if disabled by `--no-ui-state`.
+    ui_state_path: Option<PathBuf>,
+    /// A message shown on the tray for a while, e.g. the path of the
+    /// exported file.
+    notice: Option<(String, Instant)>,
+    /// The state snapshot displayed while the display is paused.
+    paused: Option<State>,
+    /// Whether the time columns show relative or absolute times.
+    time_format: TimeFormat,
 }
 
 impl Tui {
     pub fn new(
-        tick_dur: Duration,
+        opts: &Opts,
         tx: flume::Sender<UpdateEvent>,
         cancel_token: CancellationToken,
         state: Arc<Mutex<State>>,
+        replay: ReplayControl,
+        capture_stats: CaptureStats,
     ) -> Self {
-        Self {
+        let tick_dur = Duration::from_secs(1) / opts.refresh_rate;
+        let replay = opts.replays_file().then_some(replay);
+        let seek_step = chrono::Duration::seconds(opts.seek_step as i64);
+        let ui_state_path = if opts.no_ui_state {
+            None
+        } else {
+            UiState::default_path()
+        };
+
+        let mut tui = Self {
             tx,
             tick_dur,
             state,
             cancel_token,
+            replay,
+            seek_step,
+            capture_stats,
+            since: Instant::now(),
+            no_rtps_hint_delay: Duration::from_secs(opts.no_rtps_hint_delay),
+            send_timeout: Duration::from_millis(opts.send_timeout),
             tab_index: 0,
             tab_participant: ParticipantTableState::new(),
             tab_writer: WriterTableState::new(),
             tab_topic: TopicTableState::new(),
             tab_abnormality: AbnormalityTableState::new(),
+            tab_fragment: FragmentTableState::new(),
+            tab_locator: LocatorTableState::new(),
             tab_reader: ReaderTableState::new(),
             tab_stat: StatTableState::new(),
             focus: Focus::Dashboard,
+            detail_scroll: 0,
+            drill_topic: None,
+            pending_drill_topic: None,
+            detail_on_topic: false,
+            topic_entities: XTableState::new(),
+            topic_filter: None,
+            filter_input: String::new(),
+            filter_error: None,
+            ui_state_path,
+            notice: None,
+            paused: None,
+            time_format: TimeFormat::default(),
+        };
+
+        tui.restore_ui_state();
+
+        tui.tab_participant.set_max_rows(opts.max_rows);
+        tui.tab_writer.set_max_rows(opts.max_rows);
+        tui.tab_reader.set_max_rows(opts.max_rows);
+        tui.tab_topic.set_max_rows(opts.max_rows);
+        tui.tab_stat.set_max_rows(opts.max_rows);
+        tui.tab_abnormality.set_max_rows(opts.max_rows);
+        tui.tab_fragment.set_max_rows(opts.max_rows);
+        tui.tab_locator.set_max_rows(opts.max_rows);
+        tui.tab_writer.set_highlight_bitrate(opts.highlight_bitrate);
+        tui.tab_participant.set_stale_age(opts.stale_age);
+        tui.tab_writer.set_stale_age(opts.stale_age);
+        tui.tab_reader.set_stale_age(opts.stale_age);
+
+        if let Some(topic_name) = &opts.focus_topic {
+            tui.tab_index = TAB_IDX_TOPIC;
+            tui.tab_topic.select_topic(topic_name.clone());
+            tui.pending_drill_topic = Some(topic_name.clone());
         }
+
+        tui
     }
 
     pub fn run(mut self) -> io::Result<()> {
@@ -116,9 +237,59 @@ impl Tui {
         )?;
         terminal.show_cursor()?;
 
+        self.save_ui_state();
+
         Ok(())
     }
 
+    /// Restores the hidden columns and the sort orders saved by the
+    /// last run.
+    fn restore_ui_state(&mut self) {
+        let Some(path) = &self.ui_state_path else {
+            return;
+        };
+        let mut ui_state = match UiState::load(path) {
+            Ok(ui_state) => ui_state,
+            Err(err) => {
+                warn!("unable to load the UI state: {err:#}");
+                return;
+            }
+        };
+
+        for (title, table_state) in izip!(TAB_TITLES, self.table_states_mut()) {
+            if let Some(layout) = ui_state.tabs.remove(*title) {
+                table_state.set_layout(layout);
+            }
+        }
+    }
+
+    fn save_ui_state(&mut self) {
+        let Some(path) = self.ui_state_path.clone() else {
+            return;
+        };
+        let tabs = izip!(TAB_TITLES, self.table_states_mut())
+            .map(|(title, table_state)| (title.to_string(), table_state.layout()))
+            .collect();
+
+        if let Err(err) = (UiState { tabs }).save(&path) {
+            warn!("unable to save the UI state: {err:#}");
+        }
+    }
+
+    /// Gets the table states in the order of [TAB_TITLES].
+    fn table_states_mut(&mut self) -> [&mut XTableState; 8] {
+        [
+            self.tab_participant.table_state_mut(),
+            self.tab_writer.table_state_mut(),
+            self.tab_reader.table_state_mut(),
+            self.tab_topic.table_state_mut(),
+            self.tab_stat.table_state_mut(),
+            self.tab_abnormality.table_state_mut(),
+            self.tab_fragment.table_state_mut(),
+            self.tab_locator.table_state_mut(),
+        ]
+    }
+
     fn run_loop<B>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()>
     where
         B: Backend,
@@ -144,6 +315,7 @@ impl Tui {
             if elapsed_time >= self.tick_dur {
                 // Draw UI
                 terminal.draw(|frame| self.render(frame))?;
+                self.report_export();
 
                 // Clean up state
                 last_tick = Instant::now();
@@ -161,16 +333,136 @@ impl Tui {
                 use KeyCode as C;
 
                 let n_tabs = TAB_TITLES.len();
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+                // The detail popup takes the keys until it is closed.
+                if self.focus == Focus::Detail {
+                    match key.code {
+                        C::Char('q') | C::Esc => {
+                            // Go back to the drill-down view if the
+                            // detail is opened from it.
+                            self.focus = if self.drill_topic.is_some() {
+                                Focus::TopicEntities
+                            } else {
+                                Focus::Dashboard
+                            };
+                        }
+                        C::Char('u' | 'b') if ctrl => {
+                            self.detail_scroll = self.detail_scroll.saturating_sub(10)
+                        }
+                        C::Char('d' | 'f') if ctrl => {
+                            self.detail_scroll = self.detail_scroll.saturating_add(10)
+                        }
+                        C::Up | C::Char('k') => {
+                            self.detail_scroll = self.detail_scroll.saturating_sub(1)
+                        }
+                        C::Down | C::Char('j') => {
+                            self.detail_scroll = self.detail_scroll.saturating_add(1)
+                        }
+                        C::PageUp => self.detail_scroll = self.detail_scroll.saturating_sub(10),
+                        C::PageDown => self.detail_scroll = self.detail_scroll.saturating_add(10),
+                        C::Home | C::Char('g') => self.detail_scroll = 0,
+                        _ => {}
+                    }
+                    return Ok(ControlFlow::Continue(()));
+                }
+
+                // The topic drill-down view takes the navigation keys
+                // until it is closed.
+                if self.focus == Focus::TopicEntities {
+                    let table_state = &mut self.topic_entities;
+                    match key.code {
+                        C::Char('q') | C::Esc => {
+                            self.drill_topic = None;
+                            self.detail_on_topic = false;
+                            self.focus = Focus::Dashboard;
+                        }
+                        C::Enter => {
+                            if table_state.selected_key().is_some() {
+                                self.detail_on_topic = false;
+                                self.focus = Focus::Detail;
+                                self.detail_scroll = 0;
+                            }
+                        }
+                        C::Char('i') => {
+                            self.detail_on_topic = true;
+                            self.focus = Focus::Detail;
+                            self.detail_scroll = 0;
+                        }
+                        C::Char('u') if ctrl => table_state.previous_half_page(),
+                        C::Char('d') if ctrl => table_state.next_half_page(),
+                        C::Char('b') if ctrl => table_state.previous_page(),
+                        C::Char('f') if ctrl => table_state.next_page(),
+                        C::Up | C::Char('k') => table_state.previous_item(),
+                        C::Down | C::Char('j') => table_state.next_item(),
+                        C::Left | C::Char('h') => table_state.previous_column(),
+                        C::Right | C::Char('l') => table_state.next_column(),
+                        C::PageUp => table_state.previous_page(),
+                        C::PageDown => table_state.next_page(),
+                        C::Home | C::Char('g') => table_state.first_item(),
+                        C::End | C::Char('G') => table_state.last_item(),
+                        C::Char('s') => table_state.toggle_sort(),
+                        C::Char('v') => table_state.toggle_show(),
+                        _ => {}
+                    }
+                    return Ok(ControlFlow::Continue(()));
+                }
+
+                // The filter input line takes the keys until it is
+                // applied or canceled.
+                if self.focus == Focus::Filter {
+                    match key.code {
+                        C::Enter => self.apply_topic_filter(),
+                        C::Esc => {
+                            self.filter_error = None;
+                            self.focus = Focus::Dashboard;
+                        }
+                        C::Backspace => {
+                            self.filter_input.pop();
+                        }
+                        C::Char(ch) => self.filter_input.push(ch),
+                        _ => {}
+                    }
+                    return Ok(ControlFlow::Continue(()));
+                }
 
                 match key.code {
+                    // The Ctrl bindings go first so that they do not
+                    // trigger the plain letter bindings.
+                    C::Char('u') if ctrl => {
+                        self.active_table_state_mut().previous_half_page();
+                    }
+                    C::Char('d') if ctrl => {
+                        self.active_table_state_mut().next_half_page();
+                    }
+                    C::Char('b') if ctrl => {
+                        self.key_page_up();
+                    }
+                    C::Char('f') if ctrl => {
+                        self.key_page_down();
+                    }
                     C::Char('q') => match self.focus {
                         Focus::Dashboard => {
                             self.cancel_token.cancel();
                             return Ok(ControlFlow::Break(()));
                         }
-                        Focus::Help => self.focus = Focus::Dashboard,
+                        Focus::Help | Focus::Detail | Focus::Filter | Focus::TopicEntities => {
+                            self.focus = Focus::Dashboard
+                        }
                     },
-                    C::Char('h') => self.focus = Focus::Help,
+                    C::Enter => {
+                        if let Some(key) = self.active_table_state().selected_key() {
+                            if self.tab_index == TAB_IDX_TOPIC {
+                                self.drill_topic = Some(key.to_string());
+                                self.topic_entities = XTableState::new();
+                                self.focus = Focus::TopicEntities;
+                            } else {
+                                self.focus = Focus::Detail;
+                                self.detail_scroll = 0;
+                            }
+                        }
+                    }
+                    C::Char('?') => self.focus = Focus::Help,
                     C::Char('s') => {
                         self.toggle_sort();
                     }
@@ -182,16 +474,59 @@ impl Tui {
                             return Ok(ControlFlow::Break(()));
                         }
                     }
-                    C::Up => {
+                    C::Char(']') => {
+                        self.seek_forward();
+                    }
+                    C::Char('+') => {
+                        self.active_table_state_mut().grow_column();
+                    }
+                    C::Char('-') => {
+                        self.active_table_state_mut().shrink_column();
+                    }
+                    C::Char('c') => {
+                        self.active_table_state_mut().toggle_compact();
+                    }
+                    C::Char('d') => {
+                        self.active_table_state_mut().toggle_deltas();
+                    }
+                    C::Char('e') => {
+                        self.export_csv();
+                    }
+                    C::Char(' ') => {
+                        self.toggle_pause();
+                    }
+                    C::Char('t') => {
+                        self.time_format = self.time_format.toggle();
+                    }
+                    C::Char('w') => {
+                        self.show_participant_endpoints(TAB_IDX_WRITER);
+                    }
+                    C::Char('R') => {
+                        self.show_participant_endpoints(TAB_IDX_READER);
+                    }
+                    C::Char('/') => {
+                        self.filter_input = self
+                            .topic_filter
+                            .as_ref()
+                            .map(|filter| filter.pattern())
+                            .unwrap_or_default();
+                        self.filter_error = None;
+                        self.focus = Focus::Filter;
+                    }
+                    C::Esc => {
+                        self.active_table_state_mut().set_filter(None);
+                        self.topic_filter = None;
+                    }
+                    C::Up | C::Char('k') => {
                         self.key_up();
                     }
-                    C::Down => {
+                    C::Down | C::Char('j') => {
                         self.key_down();
                     }
-                    C::Left => {
+                    C::Left | C::Char('h') => {
                         self.key_left();
                     }
-                    C::Right => {
+                    C::Right | C::Char('l') => {
                         self.key_right();
                     }
                     C::PageUp => {
@@ -200,10 +535,10 @@ impl Tui {
                     C::PageDown => {
                         self.key_page_down();
                     }
-                    C::Home => {
+                    C::Home | C::Char('g') => {
                         self.key_home();
                     }
-                    C::End => {
+                    C::End | C::Char('G') => {
                         self.key_end();
                     }
                     C::Tab => {
@@ -226,14 +561,34 @@ impl Tui {
     where
         B: Backend,
     {
-        // Unlock the state
-        let Ok(state) = self.state.lock() else {
-            // TODO: show error
-            error!("State lock is poisoned");
-            return;
+        // Unlock the state, or use the snapshot while paused.
+        let guard;
+        let state: &State = match &self.paused {
+            Some(snapshot) => snapshot,
+            None => {
+                let Ok(locked) = self.state.lock() else {
+                    // TODO: show error
+                    error!("State lock is poisoned");
+                    return;
+                };
+                guard = locked;
+                &guard
+            }
         };
         // dbg!(state.participants.len());
 
+        // Drill down into the focused topic once it is discovered,
+        // unless the user has moved on.
+        if let Some(topic_name) = &self.pending_drill_topic {
+            if self.focus != Focus::Dashboard || self.tab_index != TAB_IDX_TOPIC {
+                self.pending_drill_topic = None;
+            } else if state.topics.contains_key(topic_name) {
+                self.drill_topic = self.pending_drill_topic.take();
+                self.topic_entities = XTableState::new();
+                self.focus = Focus::TopicEntities;
+            }
+        }
+
         // Split the screen vertically into two chunks.
         let content_height = frame.size().height.saturating_sub(2);
 
@@ -252,7 +607,7 @@ impl Tui {
 
         // Build the container for tabs
         let tabs_block = Block::default();
-        let tabs = Tabs::new(TAB_TITLES.to_vec())
+        let tabs = Tabs::new(tab_titles(state))
             .block(tabs_block)
             .style(Style::default().fg(Color::White))
             .highlight_style(Style::default().fg(Color::Yellow))
@@ -263,50 +618,291 @@ impl Tui {
         // Render the tab content according to the current tab index.
         match self.tab_index {
             TAB_IDX_PARTICIPANT => frame.render_stateful_widget(
-                ParticipantTable::new(&state),
+                ParticipantTable::new(state, self.time_format),
                 chunks[1],
                 &mut self.tab_participant,
             ),
             TAB_IDX_WRITER => frame.render_stateful_widget(
-                WriterTable::new(&state),
+                WriterTable::new(state, self.topic_filter.as_ref(), self.time_format),
                 chunks[1],
                 &mut self.tab_writer,
             ),
             TAB_IDX_READER => frame.render_stateful_widget(
-                ReaderTable::new(&state),
+                ReaderTable::new(state, self.time_format),
                 chunks[1],
                 &mut self.tab_reader,
             ),
             TAB_IDX_TOPIC => frame.render_stateful_widget(
-                TopicTable::new(&state),
+                TopicTable::new(state, self.topic_filter.as_ref()),
                 chunks[1],
                 &mut self.tab_topic,
             ),
             TAB_IDX_STATISTICS => {
-                frame.render_stateful_widget(StatTable::new(&state), chunks[1], &mut self.tab_stat);
+                frame.render_stateful_widget(
+                    StatTable::new(state, &self.capture_stats),
+                    chunks[1],
+                    &mut self.tab_stat,
+                );
             }
             TAB_IDX_ABNORMALITIES => frame.render_stateful_widget(
-                AbnormalityTable::new(&state),
+                AbnormalityTable::new(state, self.time_format),
                 chunks[1],
                 &mut self.tab_abnormality,
             ),
+            TAB_IDX_FRAGMENT => frame.render_stateful_widget(
+                FragmentTable::new(state),
+                chunks[1],
+                &mut self.tab_fragment,
+            ),
+            TAB_IDX_LOCATOR => frame.render_stateful_widget(
+                LocatorTable::new(state),
+                chunks[1],
+                &mut self.tab_locator,
+            ),
             _ => unreachable!(),
         }
 
         // Render the bottom tray
         let tray_block = Block::default();
-        let tray = Paragraph::new("Q: Exit  H: Help  TAB: Next tab").block(tray_block);
+        let tray = Paragraph::new(self.tray_text()).block(tray_block);
         frame.render_widget(tray, chunks[2]);
 
         // Render dialogs
         match self.focus {
-            Focus::Dashboard => {}
+            Focus::Dashboard => {
+                if self.should_hint_no_rtps() {
+                    Self::render_no_rtps_hint(frame);
+                }
+            }
             Focus::Help => {
                 Self::render_help_dialog(frame);
             }
+            Focus::Detail => {
+                if let Some(topic_name) = &self.drill_topic {
+                    Self::render_topic_entities(frame, state, topic_name, &mut self.topic_entities);
+                }
+                self.render_detail_dialog(frame, state);
+            }
+            Focus::Filter => {}
+            Focus::TopicEntities => {
+                if let Some(topic_name) = &self.drill_topic {
+                    Self::render_topic_entities(frame, state, topic_name, &mut self.topic_entities);
+                }
+            }
         }
     }
 
+    /// Builds the tray text showing the current position in the
+    /// active table.
+    fn tray_text(&self) -> String {
+        // Show the input line while typing a filter.
+        if self.focus == Focus::Filter {
+            let mut text = format!("/{}", self.filter_input);
+            if let Some(error) = &self.filter_error {
+                text.push_str(&format!("  ({error})"));
+            }
+            return text;
+        }
+
+        let table_state = self.active_table_state();
+        let mut items = vec![TAB_TITLES[self.tab_index].to_string()];
+
+        if self.paused.is_some() {
+            items.push("PAUSED".to_string());
+        }
+
+        let num_entries = table_state.num_entries();
+        let position = match table_state.selected() {
+            Some(index) => format!("row {}/{num_entries}", index + 1),
+            None => format!("row -/{num_entries}"),
+        };
+        items.push(position);
+
+        if let Some((title, ascending)) = table_state.sort_column() {
+            let symbol = if ascending { "↑" } else { "↓" };
+            items.push(format!("sort: {title}{symbol}"));
+        }
+
+        if let Some(filter) = table_state.filter() {
+            items.push(format!("filter: {filter} (Esc to clear)"));
+        }
+
+        if let (Some(filter), TAB_IDX_WRITER | TAB_IDX_TOPIC) = (&self.topic_filter, self.tab_index)
+        {
+            items.push(format!("topic: {} (Esc to clear)", filter.pattern()));
+        }
+
+        if let Some(Value::Stat(summary)) = table_state.selected_cell() {
+            let StatSummary {
+                mean,
+                stdev,
+                num_samples,
+            } = *summary;
+            items.push(format!(
+                "mean={} stdev={} samples={num_samples}",
+                Value::Float(mean).to_string().trim(),
+                Value::Float(stdev).to_string().trim()
+            ));
+        }
+
+        if let Some((message, since)) = &self.notice {
+            if since.elapsed() < NOTICE_DURATION {
+                items.push(message.clone());
+            }
+        }
+
+        items.push("Q: Exit  ?: Help  TAB: Next tab".to_string());
+        items.join(" | ")
+    }
+
+    fn active_table_state(&self) -> &XTableState {
+        match self.tab_index {
+            TAB_IDX_PARTICIPANT => self.tab_participant.table_state(),
+            TAB_IDX_WRITER => self.tab_writer.table_state(),
+            TAB_IDX_READER => self.tab_reader.table_state(),
+            TAB_IDX_TOPIC => self.tab_topic.table_state(),
+            TAB_IDX_STATISTICS => self.tab_stat.table_state(),
+            TAB_IDX_ABNORMALITIES => self.tab_abnormality.table_state(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.table_state(),
+            TAB_IDX_LOCATOR => self.tab_locator.table_state(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn active_table_state_mut(&mut self) -> &mut XTableState {
+        match self.tab_index {
+            TAB_IDX_PARTICIPANT => self.tab_participant.table_state_mut(),
+            TAB_IDX_WRITER => self.tab_writer.table_state_mut(),
+            TAB_IDX_READER => self.tab_reader.table_state_mut(),
+            TAB_IDX_TOPIC => self.tab_topic.table_state_mut(),
+            TAB_IDX_STATISTICS => self.tab_stat.table_state_mut(),
+            TAB_IDX_ABNORMALITIES => self.tab_abnormality.table_state_mut(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.table_state_mut(),
+            TAB_IDX_LOCATOR => self.tab_locator.table_state_mut(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Checks if packets are captured for a while but none of them is
+    /// RTPS.
+    fn should_hint_no_rtps(&self) -> bool {
+        self.since.elapsed() >= self.no_rtps_hint_delay
+            && self.capture_stats.total_packets() > 0
+            && self.capture_stats.rtps_packets() == 0
+    }
+
+    fn render_no_rtps_hint<B>(frame: &mut Frame<B>)
+    where
+        B: Backend,
+    {
+        let text = "\
+Packets are captured but none of them is RTPS.
+
+Check that the interface carries the DDS traffic, that the
+participants are on this network, and that the traffic is not
+protected by DDS Security.
+
+If a vendor header precedes the RTPS message, skip it with
+--strip-prefix or locate the message with --rtps-signature.
+Also check that --domain matches the domain in use, since the
+packets of other domains are dropped.";
+
+        let area = centered_rect(60, 30, frame.size());
+        let block = Block::default()
+            .title("No RTPS traffic")
+            .borders(Borders::ALL)
+            .on_red();
+        let dialog = Paragraph::new(text).block(block);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(dialog, area);
+    }
+
+    /// Renders the full detail of the item selected on the active
+    /// tab.
+    fn render_detail_dialog<B>(&self, frame: &mut Frame<B>, state: &State)
+    where
+        B: Backend,
+    {
+        let key = if self.drill_topic.is_some() {
+            self.topic_entities.selected_key()
+        } else {
+            self.active_table_state().selected_key()
+        };
+        let text = match (&self.drill_topic, self.detail_on_topic) {
+            (Some(topic_name), true) => detail::topic_detail(state, topic_name),
+            // The drill-down view lists both writers and readers.
+            (Some(_), false) => key.and_then(|key| {
+                detail::writer_detail(state, key)
+                    .or_else(|| detail::reader_detail(state, key, self.time_format))
+            }),
+            (None, _) => key.and_then(|key| match self.tab_index {
+                TAB_IDX_PARTICIPANT => detail::participant_detail(state, key),
+                TAB_IDX_WRITER => detail::writer_detail(state, key),
+                TAB_IDX_READER => detail::reader_detail(state, key, self.time_format),
+                _ => None,
+            }),
+        };
+        let text = text.unwrap_or_else(|| "No detail for the selected item.".to_string());
+        let history = key
+            .filter(|_| !self.detail_on_topic)
+            .and_then(|key| detail::writer_bit_rate_history(state, key));
+
+        let area = centered_rect(80, 80, frame.size());
+        frame.render_widget(Clear, area);
+
+        // Show the bit rate trend of writers below the text.
+        let text_area = match &history {
+            Some(history) => {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(1), Constraint::Length(6)])
+                    .split(area);
+                render_bit_rate_sparkline(frame, chunks[1], history);
+                chunks[0]
+            }
+            None => area,
+        };
+
+        let block = Block::default()
+            .title("Detail (↑/↓ to scroll, Esc to close)")
+            .borders(Borders::ALL)
+            .on_blue();
+        let dialog = Paragraph::new(text)
+            .block(block)
+            .scroll((self.detail_scroll, 0));
+        frame.render_widget(dialog, text_area);
+    }
+
+    /// Renders the writers and readers of the drill-down topic.
+    fn render_topic_entities<B>(
+        frame: &mut Frame<B>,
+        state: &State,
+        topic_name: &str,
+        table_state: &mut XTableState,
+    ) where
+        B: Backend,
+    {
+        let area = centered_rect(80, 80, frame.size());
+        frame.render_widget(Clear, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(6), Constraint::Min(1)])
+            .split(area);
+        let history = state
+            .topics
+            .get(topic_name)
+            .map(|topic| topic.bit_rate_history.samples())
+            .unwrap_or_default();
+        render_bit_rate_sparkline(frame, chunks[0], &history);
+        frame.render_stateful_widget(
+            TopicEntityTable::new(state, topic_name),
+            chunks[1],
+            table_state,
+        );
+    }
+
     fn render_help_dialog<B>(frame: &mut Frame<B>)
     where
         B: Backend,
@@ -319,16 +915,34 @@ impl Tui {
 
 TAB       Next tab
 Shift+TAB Previous tab
-↑         Previous item
-↓         Next item
-←         Previous column
-→         Next column
-PageUp    Previous page
-PageDown  Next page
-h         Show help
+↑/k       Previous item
+↓/j       Next item
+←/h       Previous column
+→/l       Next column
+PageUp    Previous page (also Ctrl+b)
+PageDown  Next page (also Ctrl+f)
+Ctrl+u    Previous half page
+Ctrl+d    Next half page
+Home/g    First item
+End/G     Last item
+?         Show help
+Enter     Show the detail of the selected item, or the writers
+          and readers of the selected topic
+i         Show the detail of the topic in the writer/reader list
 s         Sort by selected column
 v         Hide/Show column
++/-       Widen/Narrow selected column
+c         Truncate long values (compact mode)
+d         Highlight values changed since last refresh
+e         Export the displayed rows to a CSV file
+Space     Pause/Resume the display
+t         Show relative/absolute times
+w         Show writers of the selected participant
+R         Show readers of the selected participant
+/         Filter Topics and Writers by topic name (re: for regex)
+Esc       Clear the filters
 r         Enable/Disable data logging
+]         Fast-forward the offline replay
 q         Close dialog or exit
 ",
             env!("CARGO_PKG_VERSION")
@@ -353,6 +967,8 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.previous_item(),
             TAB_IDX_STATISTICS => self.tab_stat.previous_item(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.previous_item(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.previous_item(),
+            TAB_IDX_LOCATOR => self.tab_locator.previous_item(),
             _ => unreachable!(),
         }
     }
@@ -365,6 +981,8 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.next_item(),
             TAB_IDX_STATISTICS => self.tab_stat.next_item(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.next_item(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.next_item(),
+            TAB_IDX_LOCATOR => self.tab_locator.next_item(),
             _ => unreachable!(),
         }
     }
@@ -377,6 +995,8 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.previous_page(),
             TAB_IDX_STATISTICS => self.tab_stat.previous_page(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.previous_page(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.previous_page(),
+            TAB_IDX_LOCATOR => self.tab_locator.previous_page(),
             _ => unreachable!(),
         }
     }
@@ -389,6 +1009,8 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.next_page(),
             TAB_IDX_STATISTICS => self.tab_stat.next_page(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.next_page(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.next_page(),
+            TAB_IDX_LOCATOR => self.tab_locator.next_page(),
             _ => unreachable!(),
         }
     }
@@ -401,6 +1023,8 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.first_item(),
             TAB_IDX_STATISTICS => self.tab_stat.first_item(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.first_item(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.first_item(),
+            TAB_IDX_LOCATOR => self.tab_locator.first_item(),
             _ => unreachable!(),
         }
     }
@@ -413,6 +1037,8 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.last_item(),
             TAB_IDX_STATISTICS => self.tab_stat.last_item(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.last_item(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.last_item(),
+            TAB_IDX_LOCATOR => self.tab_locator.last_item(),
             _ => unreachable!(),
         }
     }
@@ -425,6 +1051,8 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.previous_column(),
             TAB_IDX_STATISTICS => self.tab_stat.previous_column(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.previous_column(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.previous_column(),
+            TAB_IDX_LOCATOR => self.tab_locator.previous_column(),
             _ => unreachable!(),
         }
     }
@@ -437,6 +1065,8 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.next_column(),
             TAB_IDX_STATISTICS => self.tab_stat.next_column(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.next_column(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.next_column(),
+            TAB_IDX_LOCATOR => self.tab_locator.next_column(),
             _ => unreachable!(),
         }
     }
@@ -449,6 +1079,8 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.toggle_show(),
             TAB_IDX_STATISTICS => self.tab_stat.toggle_show(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.toggle_show(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.toggle_show(),
+            TAB_IDX_LOCATOR => self.tab_locator.toggle_show(),
             _ => unreachable!(),
         }
     }
@@ -461,13 +1093,95 @@ q         Close dialog or exit
             TAB_IDX_TOPIC => self.tab_topic.toggle_sort(),
             TAB_IDX_STATISTICS => self.tab_stat.toggle_sort(),
             TAB_IDX_ABNORMALITIES => self.tab_abnormality.toggle_sort(),
+            TAB_IDX_FRAGMENT => self.tab_fragment.toggle_sort(),
+            TAB_IDX_LOCATOR => self.tab_locator.toggle_sort(),
             _ => unreachable!(),
         }
     }
 
+    /// Switches to the Writers or Readers tab showing only the
+    /// endpoints of the participant selected on the Participants tab.
+    fn show_participant_endpoints(&mut self, tab_index: usize) {
+        if self.tab_index != TAB_IDX_PARTICIPANT {
+            return;
+        }
+        let Some(guid_prefix) = self.tab_participant.table_state().selected_key() else {
+            return;
+        };
+        let filter = Some(guid_prefix.to_string());
+
+        self.tab_index = tab_index;
+        self.active_table_state_mut().set_filter(filter);
+    }
+
+    /// Applies the typed topic filter. An empty input removes the
+    /// filter.
+    fn apply_topic_filter(&mut self) {
+        if self.filter_input.is_empty() {
+            self.topic_filter = None;
+        } else {
+            match TopicFilter::parse(&self.filter_input) {
+                Ok(filter) => self.topic_filter = Some(filter),
+                Err(err) => {
+                    self.filter_error = Some(err.to_string());
+                    return;
+                }
+            }
+        }
+
+        self.filter_error = None;
+        self.focus = Focus::Dashboard;
+    }
+
+    /// Requests to write the displayed rows of the active tab to a
+    /// timestamped CSV file in the working directory. The rows are
+    /// written on the next render.
+    fn export_csv(&mut self) {
+        let tab_name = TAB_TITLES[self.tab_index].to_lowercase();
+        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let path = PathBuf::from(format!("ddshark-{tab_name}-{timestamp}.csv"));
+        self.active_table_state_mut().export_csv(path);
+    }
+
+    /// Shows the outcome of the export done by the last render.
+    fn report_export(&mut self) {
+        let Some((path, result)) = self.active_table_state_mut().take_export_result() else {
+            return;
+        };
+
+        let message = match result {
+            Ok(()) => format!("exported to {}", path.display()),
+            Err(err) => {
+                warn!("unable to export {}: {err}", path.display());
+                format!("export failed: {err}")
+            }
+        };
+        self.notice = Some((message, Instant::now()));
+    }
+
+    /// Freezes the display on a snapshot of the state, or resumes
+    /// the live state. The capture keeps going in either case.
+    fn toggle_pause(&mut self) {
+        if self.paused.take().is_some() {
+            return;
+        }
+
+        match self.state.lock() {
+            Ok(state) => self.paused = Some(state.clone()),
+            Err(_) => error!("State lock is poisoned"),
+        }
+    }
+
+    fn seek_forward(&self) {
+        if let Some(replay) = &self.replay {
+            replay.seek_forward(self.seek_step);
+        }
+    }
+
     fn toggle_logging(&self) -> ControlFlow<()> {
-        let timeout = Duration::from_millis(100);
-        let result = self.tx.send_timeout(UpdateEvent::ToggleLogging, timeout);
+        let result = self
+            .tx
+            .send_timeout(UpdateEvent::ToggleLogging, self.send_timeout);
 
         type E<T> = SendTimeoutError<T>;
 
@@ -475,7 +1189,7 @@ q         Close dialog or exit
             Ok(()) => ControlFlow::Continue(()),
             Err(E::Disconnected(_)) => ControlFlow::Break(()),
             Err(E::Timeout(_)) => {
-                warn!("congestion occurs");
+                warn!("unable to toggle logging: the updater is congested");
                 ControlFlow::Continue(())
             }
         }
@@ -486,6 +1200,63 @@ q         Close dialog or exit
 enum Focus {
     Dashboard,
     Help,
+    Detail,
+    Filter,
+    TopicEntities,
+}
+
+/// Renders the recent bit rates, with the latest one in the title.
+fn render_bit_rate_sparkline<B>(frame: &mut Frame<B>, area: Rect, history: &[u64])
+where
+    B: Backend,
+{
+    let latest = history.last().copied().unwrap_or(0);
+    let title = format!(
+        "bitrate {} (last {} ticks)",
+        Value::Float(latest as f64).to_string().trim(),
+        history.len()
+    );
+    let sparkline = Sparkline::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .data(history)
+        .style(Style::default().fg(Color::Cyan));
+    frame.render_widget(sparkline, area);
+}
+
+/// Builds the tab titles with the live number of entries, e.g.
+/// "Writers (340)".
+fn tab_titles(state: &State) -> Vec<Line<'static>> {
+    let n_writers: usize = state
+        .participants
+        .values()
+        .map(|part| part.writers.len())
+        .sum();
+    let n_readers: usize = state
+        .participants
+        .values()
+        .map(|part| part.readers.len())
+        .sum();
+    let n_abnormalities = state.abnormalities.len();
+
+    izip!(0.., TAB_TITLES)
+        .map(|(index, &title)| {
+            let count = match index {
+                TAB_IDX_PARTICIPANT => state.participants.len(),
+                TAB_IDX_WRITER => n_writers,
+                TAB_IDX_READER => n_readers,
+                TAB_IDX_TOPIC => state.topics.len(),
+                TAB_IDX_ABNORMALITIES => n_abnormalities,
+                _ => return Line::from(title),
+            };
+            let count = if index == TAB_IDX_ABNORMALITIES && n_abnormalities > 0 {
+                Span::styled(format!("({count})"), Style::default().fg(Color::Red))
+            } else {
+                Span::raw(format!("({count})"))
+            };
+
+            Line::from(vec![Span::raw(format!("{title} ")), count])
+        })
+        .collect()
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
@@ -0,0 +1,291 @@
+//! The full detail of a selected participant, endpoint or topic.
+
+use super::value::{TimeFormat, TimeValue};
+use crate::{
+    message::StatusInfo,
+    state::{State, WriterState},
+    utils::{GUIDExt, GuidPrefixExt, LocatorExt},
+};
+use rustdds::{structure::locator::Locator, GUID};
+use std::fmt::Write as _;
+
+/// Describes the participant with the displayed GUID prefix.
+pub fn participant_detail(state: &State, key: &str) -> Option<String> {
+    let (guid_prefix, part) = state
+        .participants
+        .iter()
+        .find(|(guid_prefix, _)| guid_prefix.display().to_string() == key)?;
+
+    let mut text = String::new();
+    writeln!(text, "GUID prefix:      {}", guid_prefix.display()).unwrap();
+    writeln!(
+        text,
+        "name:             {}",
+        part.entity_name.as_deref().unwrap_or("-")
+    )
+    .unwrap();
+    if let Some([major, minor, release, revision]) = part.product_version {
+        writeln!(
+            text,
+            "product:          {major}.{minor}.{release}.{revision}"
+        )
+        .unwrap();
+    }
+    if let Some([major, minor]) = part.vendor_id {
+        writeln!(text, "vendor:           {major:02x}.{minor:02x}").unwrap();
+    }
+    if let Some((major, minor)) = part.protocol_version {
+        writeln!(text, "RTPS version:     {major}.{minor}").unwrap();
+    }
+    if let Some(lease) = part.lease_duration {
+        writeln!(text, "lease duration:   {}s", lease.num_seconds()).unwrap();
+    }
+    writeln!(text, "SPDP messages:    {}", part.spdp_count).unwrap();
+    writeln!(text, "writers:          {}", part.writers.len()).unwrap();
+    writeln!(text, "readers:          {}", part.readers.len()).unwrap();
+    writeln!(text, "dropped:          {}", part.dropped_endpoint_count).unwrap();
+    write_locators(
+        &mut text,
+        "unicast locators",
+        part.unicast_locator_list.as_deref(),
+    );
+    write_locators(
+        &mut text,
+        "multicast locators",
+        part.multicast_locator_list.as_deref(),
+    );
+
+    Some(text)
+}
+
+/// Describes the writer with the displayed GUID.
+pub fn writer_detail(state: &State, key: &str) -> Option<String> {
+    let (guid, writer) = find_writer(state, key)?;
+
+    let mut text = String::new();
+    writeln!(text, "GUID:             {}", guid.display()).unwrap();
+    writeln!(
+        text,
+        "name:             {}",
+        writer.entity_name.as_deref().unwrap_or("-")
+    )
+    .unwrap();
+    writeln!(
+        text,
+        "topic:            {}",
+        writer.topic_name().unwrap_or("-")
+    )
+    .unwrap();
+    writeln!(
+        text,
+        "type:             {}",
+        writer.type_name().unwrap_or("-")
+    )
+    .unwrap();
+    if let Some(sn) = writer.last_sn {
+        writeln!(text, "last sn:          {}", sn.0).unwrap();
+    }
+    if let Some(heartbeat) = &writer.heartbeat {
+        writeln!(
+            text,
+            "cached sn:        {}..{}",
+            heartbeat.first_sn, heartbeat.last_sn
+        )
+        .unwrap();
+    }
+    writeln!(text, "messages:         {}", writer.total_msg_count).unwrap();
+    writeln!(text, "bytes:            {}", writer.total_byte_count).unwrap();
+    writeln!(text, "retransmitted:    {} bytes", writer.retx_byte_count).unwrap();
+    writeln!(text, "frag timeouts:    {}", writer.frag_timeout_count).unwrap();
+    if !writer.lost_sn.is_empty() {
+        writeln!(text, "lost sn:          {}", writer.lost_sn).unwrap();
+    }
+    writeln!(text, "disposed:         {}", writer.disposal_count).unwrap();
+    writeln!(text, "unregistered:     {}", writer.unregister_count).unwrap();
+    if let Some(status_info) = writer.last_status_info {
+        writeln!(text, "last status:      {}", status_text(status_info)).unwrap();
+    }
+    if let Some(key_hash) = writer.last_key_hash {
+        writeln!(text, "last key hash:    {}", key_hash_hex(&key_hash)).unwrap();
+    }
+    if !writer.key_hashes.is_empty() {
+        writeln!(
+            text,
+            "key hashes:       {} recent, {} evicted",
+            writer.key_hashes.len(),
+            writer.evicted_key_hash_count
+        )
+        .unwrap();
+
+        let mut key_hashes: Vec<_> = writer.key_hashes.keys().map(key_hash_hex).collect();
+        key_hashes.sort_unstable();
+        for hex in key_hashes {
+            writeln!(text, "  {hex}").unwrap();
+        }
+    }
+    if let Some(data) = &writer.data {
+        writeln!(text, "\ndiscovery data:\n{data:#?}").unwrap();
+    }
+
+    Some(text)
+}
+
+/// Formats the key hash in hexadecimal.
+fn key_hash_hex(key_hash: &[u8; 16]) -> String {
+    key_hash.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Describes the reader with the displayed GUID. The times are shown
+/// in `time_format` like the tables.
+pub fn reader_detail(state: &State, key: &str, time_format: TimeFormat) -> Option<String> {
+    let (guid, reader) = state.participants.iter().find_map(|(&guid_prefix, part)| {
+        part.readers.iter().find_map(|(&entity_id, reader)| {
+            let guid = GUID::new(guid_prefix, entity_id);
+            (guid.display().to_string() == key).then_some((guid, reader))
+        })
+    })?;
+
+    let mut text = String::new();
+    writeln!(text, "GUID:             {}", guid.display()).unwrap();
+    writeln!(
+        text,
+        "name:             {}",
+        reader.entity_name.as_deref().unwrap_or("-")
+    )
+    .unwrap();
+    writeln!(
+        text,
+        "topic:            {}",
+        reader.topic_name().unwrap_or("-")
+    )
+    .unwrap();
+    writeln!(
+        text,
+        "type:             {}",
+        reader.type_name().unwrap_or("-")
+    )
+    .unwrap();
+    if let Some(sn) = reader.last_sn {
+        writeln!(text, "last sn:          {sn}").unwrap();
+    }
+    writeln!(text, "acknacks:         {}", reader.total_acknack_count).unwrap();
+    let missing_since = reader
+        .longest_missing()
+        .and_then(|(_, acknack)| acknack.missing_since)
+        .zip(reader.missing_age)
+        .and_then(|(since, age)| TimeValue::from_capture_time(since, age, time_format));
+    if let Some(since) = missing_since {
+        writeln!(text, "missing since:    {since}").unwrap();
+    }
+    if !reader.acknack.is_empty() {
+        writeln!(text, "missing sn:").unwrap();
+        for (writer_guid, acknack) in &reader.acknack {
+            writeln!(
+                text,
+                "  {}  {:?}",
+                writer_guid.display(),
+                acknack.missing_sn
+            )
+            .unwrap();
+        }
+    }
+    if !reader.acked_sn.is_empty() {
+        writeln!(text, "acknowledged sn:").unwrap();
+        for (writer_guid, sn) in &reader.acked_sn {
+            writeln!(text, "  {}  {sn}", writer_guid.display()).unwrap();
+        }
+    }
+    if !reader.nackfrag.is_empty() {
+        writeln!(text, "nacked fragments:").unwrap();
+        for (writer_guid, nackfrag) in &reader.nackfrag {
+            writeln!(
+                text,
+                "  {}  sn {}: {}",
+                writer_guid.display(),
+                nackfrag.writer_sn,
+                nackfrag.missing_frags
+            )
+            .unwrap();
+        }
+    }
+    if let Some(data) = &reader.data {
+        writeln!(text, "\ndiscovery data:\n{data:#?}").unwrap();
+    }
+
+    Some(text)
+}
+
+/// Describes the topic with the name.
+pub fn topic_detail(state: &State, key: &str) -> Option<String> {
+    let topic = state.topics.get(key)?;
+
+    let mut text = String::new();
+    writeln!(text, "name:             {key}").unwrap();
+    writeln!(
+        text,
+        "type:             {}",
+        topic.type_name().unwrap_or("-")
+    )
+    .unwrap();
+    writeln!(text, "messages:         {}", topic.total_msg_count).unwrap();
+    writeln!(text, "bytes:            {}", topic.total_byte_count).unwrap();
+    writeln!(text, "writers:").unwrap();
+    for guid in &topic.writers {
+        writeln!(text, "  {}", guid.display()).unwrap();
+    }
+    writeln!(text, "readers:").unwrap();
+    for guid in &topic.readers {
+        writeln!(text, "  {}", guid.display()).unwrap();
+    }
+    if let Some(data) = &topic.data {
+        writeln!(text, "\ndiscovery data:\n{data:#?}").unwrap();
+    }
+
+    Some(text)
+}
+
+/// Gets the recent bit rates of the writer with the displayed GUID.
+pub fn writer_bit_rate_history(state: &State, key: &str) -> Option<Vec<u64>> {
+    let (_, writer) = find_writer(state, key)?;
+    Some(writer.bit_rate_history.samples())
+}
+
+fn find_writer<'a>(state: &'a State, key: &str) -> Option<(GUID, &'a WriterState)> {
+    state.participants.iter().find_map(|(&guid_prefix, part)| {
+        part.writers.iter().find_map(|(&entity_id, writer)| {
+            let guid = GUID::new(guid_prefix, entity_id);
+            (guid.display().to_string() == key).then_some((guid, writer))
+        })
+    })
+}
+
+/// Lists the flags set in the status info, or "alive" if none is
+/// set.
+fn status_text(status_info: StatusInfo) -> String {
+    let StatusInfo {
+        disposed,
+        unregistered,
+        filtered,
+    } = status_info;
+    let flags: Vec<_> = [
+        (disposed, "disposed"),
+        (unregistered, "unregistered"),
+        (filtered, "filtered"),
+    ]
+    .into_iter()
+    .filter_map(|(set, name)| set.then_some(name))
+    .collect();
+
+    if flags.is_empty() {
+        "alive".to_string()
+    } else {
+        flags.join(", ")
+    }
+}
+
+fn write_locators(text: &mut String, title: &str, locators: Option<&[Locator]>) {
+    writeln!(text, "{title}:").unwrap();
+    for locator in locators.unwrap_or(&[]) {
+        writeln!(text, "  {}", locator.display()).unwrap();
+    }
+}
@@ -1,10 +1,17 @@
-use super::{value::Value, xtable::XTableState};
+use super::{
+    value::{TimeFormat, TimeValue, Value},
+    xtable::XTableState,
+};
 use crate::{
-    state::{Abnormality, State},
+    state::{Abnormality, Severity, State},
     ui::xtable::XTable,
     utils::GUIDExt,
 };
-use ratatui::{prelude::*, widgets::StatefulWidget};
+use ratatui::{
+    prelude::*,
+    style::{Color, Style},
+    widgets::StatefulWidget,
+};
 use rustdds::GUID;
 
 /// The table that keeps a list of abnormal events.
@@ -13,7 +20,7 @@ pub struct AbnormalityTable {
 }
 
 impl AbnormalityTable {
-    pub fn new(state: &State) -> Self {
+    pub fn new(state: &State, time_format: TimeFormat) -> Self {
         let mut abnormalities: Vec<_> = state.abnormalities.iter().collect();
         abnormalities.sort_unstable_by(|lhs, rhs| lhs.when.cmp(&rhs.when).reverse());
 
@@ -25,6 +32,7 @@ impl AbnormalityTable {
                     writer_guid,
                     reader_guid,
                     ref topic_name,
+                    severity,
                     ref desc,
                 } = *report;
                 let guid_to_string = |guid: Option<GUID>| match guid {
@@ -32,16 +40,17 @@ impl AbnormalityTable {
                     None => "-".to_string(),
                 };
 
-                let when = when.to_rfc3339().into();
+                let when = TimeValue::new(when, time_format).into();
                 let reader_id = guid_to_string(reader_guid).into();
                 let writer_id = guid_to_string(writer_guid).into();
                 let topic_name = topic_name
                     .to_owned()
                     .unwrap_or_else(|| "-".to_string())
                     .into();
+                let severity = severity.as_str().into();
                 let desc = desc.clone().into();
 
-                vec![when, writer_id, reader_id, topic_name, desc]
+                vec![when, severity, writer_id, reader_id, topic_name, desc]
             })
             .collect();
 
@@ -54,6 +63,7 @@ impl StatefulWidget for AbnormalityTable {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         const TITLE_WHEN: &str = "when";
+        const TITLE_SEVERITY: &str = "severity";
         const TITLE_WRITER_ID: &str = "writer";
         const TITLE_READER_ID: &str = "reader";
         const TITLE_TOPIC_NAME: &str = "topic";
@@ -61,13 +71,30 @@ impl StatefulWidget for AbnormalityTable {
 
         let header = vec![
             TITLE_WHEN,
+            TITLE_SEVERITY,
             TITLE_WRITER_ID,
             TITLE_READER_ID,
             TITLE_TOPIC_NAME,
             TITLE_DESC,
         ];
 
-        let table = XTable::new("Abnormalities", &header, &self.rows);
+        let severity_index = header
+            .iter()
+            .position(|&title| title == TITLE_SEVERITY)
+            .unwrap();
+
+        // Color the rows by severity.
+        let row_style = |row: &[Value]| match &row[severity_index] {
+            Value::Text(text) if text == Severity::Error.as_str() => {
+                Style::default().fg(Color::Red)
+            }
+            Value::Text(text) if text == Severity::Warning.as_str() => {
+                Style::default().fg(Color::Yellow)
+            }
+            _ => Style::default(),
+        };
+
+        let table = XTable::new("Abnormalities", &header, &self.rows).row_style(&row_style);
         table.render(area, buf, &mut state.table_state);
     }
 }
@@ -130,4 +157,16 @@ impl AbnormalityTableState {
     pub fn toggle_sort(&mut self) {
         self.table_state.toggle_sort();
     }
+
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.table_state.set_max_rows(max_rows);
+    }
+
+    pub fn table_state(&self) -> &XTableState {
+        &self.table_state
+    }
+
+    pub fn table_state_mut(&mut self) -> &mut XTableState {
+        &mut self.table_state
+    }
 }
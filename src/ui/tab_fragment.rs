@@ -0,0 +1,204 @@
+use super::{value::Value, xtable::XTableState};
+use crate::{
+    state::{FragmentedMessage, State},
+    ui::xtable::XTable,
+    utils::GUIDExt,
+};
+use ratatui::{prelude::*, widgets::StatefulWidget};
+use rustdds::GUID;
+use std::collections::HashMap;
+
+/// The table that lists the fragmented messages being reassembled.
+pub struct FragmentTable {
+    rows: Vec<Vec<Value>>,
+}
+
+impl FragmentTable {
+    pub fn new(state: &State) -> Self {
+        // Collect the fragments requested by readers for each
+        // (writer, sn).
+        let mut nacked: HashMap<(GUID, i64), Vec<String>> = HashMap::new();
+        for (&guid_prefix, part) in &state.participants {
+            for (&entity_id, reader) in &part.readers {
+                let reader_guid = GUID::new(guid_prefix, entity_id);
+                for (&writer_guid, nackfrag) in &reader.nackfrag {
+                    nacked
+                        .entry((writer_guid, nackfrag.writer_sn))
+                        .or_default()
+                        .push(format!(
+                            "{}: {}",
+                            reader_guid.display(),
+                            nackfrag.missing_frags
+                        ));
+                }
+            }
+        }
+
+        let mut rows: Vec<_> = state
+            .participants
+            .iter()
+            .flat_map(|(&guid_prefix, part)| {
+                part.writers.iter().flat_map(move |(&entity_id, writer)| {
+                    let guid = GUID::new(guid_prefix, entity_id);
+                    writer
+                        .frag_messages
+                        .iter()
+                        .map(move |(sn, frag_msg)| (guid, sn.0, writer.topic_name(), frag_msg))
+                })
+            })
+            .map(|(guid, sn, topic_name, frag_msg)| {
+                let FragmentedMessage {
+                    data_size,
+                    num_fragments,
+                    recvd_fragments,
+                    ref defrag_buf,
+                    ..
+                } = *frag_msg;
+
+                let percent = if num_fragments > 0 {
+                    (recvd_fragments as f64 / num_fragments as f64 * 100.0).into()
+                } else {
+                    Value::None
+                };
+
+                // Fragment numbers start from 1 in RTPS.
+                let missing: Vec<String> = defrag_buf
+                    .free_intervals()
+                    .map(|range| {
+                        if range.end - range.start == 1 {
+                            format!("{}", range.start + 1)
+                        } else {
+                            format!("{}-{}", range.start + 1, range.end)
+                        }
+                    })
+                    .collect();
+
+                vec![
+                    format!("{}", guid.display()).into(),
+                    sn.into(),
+                    topic_name.unwrap_or("").to_string().into(),
+                    recvd_fragments.try_into().unwrap(),
+                    num_fragments.try_into().unwrap(),
+                    percent,
+                    data_size.try_into().unwrap(),
+                    missing.join(", ").into(),
+                    nacked
+                        .get(&(guid, sn))
+                        .map(|list| list.join("; "))
+                        .unwrap_or_default()
+                        .into(),
+                ]
+            })
+            .collect();
+        rows.sort_unstable_by(|lrow, rrow| {
+            let lkey = (&lrow[0], &lrow[1]);
+            let rkey = (&rrow[0], &rrow[1]);
+            lkey.partial_cmp(&rkey).unwrap()
+        });
+
+        Self { rows }
+    }
+}
+
+impl StatefulWidget for FragmentTable {
+    type State = FragmentTableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        const TITLE_GUID: &str = "writer";
+        const TITLE_SN: &str = "sn";
+        const TITLE_TOPIC: &str = "topic";
+        const TITLE_RECVD_FRAGMENTS: &str = "recvd";
+        const TITLE_NUM_FRAGMENTS: &str = "frags";
+        const TITLE_PERCENT: &str = "done%";
+        const TITLE_DATA_SIZE: &str = "data_size";
+        const TITLE_MISSING: &str = "missing";
+        const TITLE_NACKED: &str = "nacked_by_readers";
+
+        let header = vec![
+            TITLE_GUID,
+            TITLE_SN,
+            TITLE_TOPIC,
+            TITLE_RECVD_FRAGMENTS,
+            TITLE_NUM_FRAGMENTS,
+            TITLE_PERCENT,
+            TITLE_DATA_SIZE,
+            TITLE_MISSING,
+            TITLE_NACKED,
+        ];
+
+        let table = XTable::new("Fragmented Messages", &header, &self.rows);
+        table.render(area, buf, &mut state.table_state);
+    }
+}
+
+pub struct FragmentTableState {
+    table_state: XTableState,
+}
+
+impl FragmentTableState {
+    pub fn new() -> Self {
+        let table_state = XTableState::new();
+
+        Self { table_state }
+    }
+
+    pub fn previous_item(&mut self) {
+        self.table_state.previous_item();
+    }
+
+    pub fn next_item(&mut self) {
+        self.table_state.next_item();
+    }
+
+    pub fn previous_page(&mut self) {
+        self.table_state.previous_page();
+    }
+
+    pub fn next_page(&mut self) {
+        self.table_state.next_page();
+    }
+
+    pub fn first_item(&mut self) {
+        self.table_state.first_item();
+    }
+
+    pub fn last_item(&mut self) {
+        self.table_state.last_item();
+    }
+
+    pub fn previous_column(&mut self) {
+        self.table_state.previous_column();
+    }
+
+    pub fn next_column(&mut self) {
+        self.table_state.next_column();
+    }
+
+    pub fn first_column(&mut self) {
+        self.table_state.first_column();
+    }
+
+    pub fn last_column(&mut self) {
+        self.table_state.last_column();
+    }
+
+    pub fn toggle_show(&mut self) {
+        self.table_state.toggle_show();
+    }
+
+    pub fn toggle_sort(&mut self) {
+        self.table_state.toggle_sort();
+    }
+
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.table_state.set_max_rows(max_rows);
+    }
+
+    pub fn table_state(&self) -> &XTableState {
+        &self.table_state
+    }
+
+    pub fn table_state_mut(&mut self) -> &mut XTableState {
+        &mut self.table_state
+    }
+}
@@ -0,0 +1,159 @@
+use super::{value::Value, xtable::XTableState};
+use crate::{
+    state::State,
+    ui::xtable::XTable,
+    utils::{GuidPrefixExt, LocatorExt},
+};
+use ratatui::{prelude::*, widgets::StatefulWidget};
+use rustdds::structure::locator::Locator;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The table that lists the distinct locators advertised by the
+/// participants.
+pub struct LocatorTable {
+    rows: Vec<Vec<Value>>,
+}
+
+impl LocatorTable {
+    pub fn new(state: &State) -> Self {
+        // Map each displayed locator to its kind and the participants
+        // reachable there.
+        let mut locators: BTreeMap<String, (&str, BTreeSet<String>)> = BTreeMap::new();
+
+        for (guid_prefix, part) in &state.participants {
+            let locator_lists = [
+                part.unicast_locator_list.as_deref(),
+                part.multicast_locator_list.as_deref(),
+            ];
+
+            for locator in locator_lists.into_iter().flatten().flatten() {
+                let (_, guid_prefixes) = locators
+                    .entry(locator.display().to_string())
+                    .or_insert_with(|| (locator_kind(locator), BTreeSet::new()));
+                guid_prefixes.insert(guid_prefix.display().to_string());
+            }
+        }
+
+        let rows: Vec<_> = locators
+            .into_iter()
+            .map(|(locator, (kind, guid_prefixes))| {
+                let n_participants = guid_prefixes.len();
+                let guid_prefixes: Vec<_> = guid_prefixes.into_iter().collect();
+
+                vec![
+                    locator.into(),
+                    kind.to_string().into(),
+                    n_participants.try_into().unwrap(),
+                    guid_prefixes.join(", ").into(),
+                ]
+            })
+            .collect();
+
+        Self { rows }
+    }
+}
+
+impl StatefulWidget for LocatorTable {
+    type State = LocatorTableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        const TITLE_LOCATOR: &str = "locator";
+        const TITLE_KIND: &str = "kind";
+        const TITLE_NUM_PARTICIPANTS: &str = "participants";
+        const TITLE_GUID_PREFIXES: &str = "GUID prefixes";
+
+        let header = vec![
+            TITLE_LOCATOR,
+            TITLE_KIND,
+            TITLE_NUM_PARTICIPANTS,
+            TITLE_GUID_PREFIXES,
+        ];
+
+        let table = XTable::new("Locators", &header, &self.rows);
+        table.render(area, buf, &mut state.table_state);
+    }
+}
+
+pub struct LocatorTableState {
+    table_state: XTableState,
+}
+
+impl LocatorTableState {
+    pub fn new() -> Self {
+        let table_state = XTableState::new();
+
+        Self { table_state }
+    }
+
+    pub fn previous_item(&mut self) {
+        self.table_state.previous_item();
+    }
+
+    pub fn next_item(&mut self) {
+        self.table_state.next_item();
+    }
+
+    pub fn previous_page(&mut self) {
+        self.table_state.previous_page();
+    }
+
+    pub fn next_page(&mut self) {
+        self.table_state.next_page();
+    }
+
+    pub fn first_item(&mut self) {
+        self.table_state.first_item();
+    }
+
+    pub fn last_item(&mut self) {
+        self.table_state.last_item();
+    }
+
+    pub fn previous_column(&mut self) {
+        self.table_state.previous_column();
+    }
+
+    pub fn next_column(&mut self) {
+        self.table_state.next_column();
+    }
+
+    pub fn first_column(&mut self) {
+        self.table_state.first_column();
+    }
+
+    pub fn last_column(&mut self) {
+        self.table_state.last_column();
+    }
+
+    pub fn toggle_show(&mut self) {
+        self.table_state.toggle_show();
+    }
+
+    pub fn toggle_sort(&mut self) {
+        self.table_state.toggle_sort();
+    }
+
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.table_state.set_max_rows(max_rows);
+    }
+
+    pub fn table_state(&self) -> &XTableState {
+        &self.table_state
+    }
+
+    pub fn table_state_mut(&mut self) -> &mut XTableState {
+        &mut self.table_state
+    }
+}
+
+fn locator_kind(locator: &Locator) -> &'static str {
+    match locator {
+        Locator::UdpV4(addr) if addr.ip().is_multicast() => "multicast",
+        Locator::UdpV6(addr) if addr.ip().is_multicast() => "multicast",
+        Locator::UdpV4(addr) if addr.ip().is_loopback() => "UDPv4 loopback",
+        Locator::UdpV6(addr) if addr.ip().is_loopback() => "UDPv6 loopback",
+        Locator::UdpV4(_) => "UDPv4",
+        Locator::UdpV6(_) => "UDPv6",
+        Locator::Invalid | Locator::Reserved | Locator::Other { .. } => "other",
+    }
+}
@@ -1,10 +1,17 @@
-use super::{value::Value, xtable::XTableState};
+use super::{
+    value::{TimeFormat, TimeValue, Value},
+    xtable::XTableState,
+};
 use crate::{
     state::{ParticipantState, State},
     ui::xtable::XTable,
     utils::{GuidPrefixExt, LocatorExt},
 };
-use ratatui::{prelude::*, widgets::StatefulWidget};
+use ratatui::{
+    prelude::*,
+    style::{Color, Style},
+    widgets::StatefulWidget,
+};
 use rustdds::structure::locator::Locator;
 
 /// The table that keeps a list of observed participants.
@@ -13,7 +20,7 @@ pub struct ParticipantTable {
 }
 
 impl ParticipantTable {
-    pub fn new(state: &State) -> Self {
+    pub fn new(state: &State, time_format: TimeFormat) -> Self {
         let mut participants: Vec<_> = state.participants.iter().collect();
         participants.sort_unstable_by(|(lprefix, _), (rprefix, _)| lprefix.cmp(rprefix));
 
@@ -41,15 +48,45 @@ impl ParticipantTable {
                     ref writers,
                     ref unicast_locator_list,
                     ref multicast_locator_list,
+                    ref entity_name,
+                    product_version,
+                    vendor_id,
+                    protocol_version,
                     total_msg_count,
                     total_byte_count,
                     total_acknack_count,
                     ref msg_rate_stat,
                     ref bit_rate_stat,
                     ref acknack_rate_stat,
+                    dropped_endpoint_count,
+                    last_recv_time,
+                    age,
+                    ..
                 } = *part;
 
                 let guid_prefix = format!("{}", guid_prefix.display()).into();
+                let entity_name = match entity_name {
+                    Some(name) => name.into(),
+                    None => Value::None,
+                };
+                let product_version = match product_version {
+                    Some([major, minor, release, revision]) => {
+                        format!("{major}.{minor}.{release}.{revision}").into()
+                    }
+                    None => Value::None,
+                };
+                let vendor_id = match vendor_id {
+                    Some([major, minor]) => format!("{major:02x}.{minor:02x}").into(),
+                    None => Value::None,
+                };
+                let protocol_version = match protocol_version {
+                    Some((major, minor)) => format!("{major}.{minor}").into(),
+                    None => Value::None,
+                };
+                let age = last_recv_time
+                    .zip(age)
+                    .and_then(|(last, age)| TimeValue::from_capture_time(last, age, time_format))
+                    .into();
                 let unicast_locator_list =
                     format_locator_list(unicast_locator_list.as_deref()).into();
                 let multicast_locator_list =
@@ -57,16 +94,26 @@ impl ParticipantTable {
 
                 vec![
                     guid_prefix,
+                    entity_name,
+                    age,
+                    product_version,
+                    vendor_id,
+                    protocol_version,
                     unicast_locator_list,
                     multicast_locator_list,
                     readers.len().try_into().unwrap(),
                     writers.len().try_into().unwrap(),
+                    dropped_endpoint_count.try_into().unwrap(),
                     total_msg_count.try_into().unwrap(),
                     total_byte_count.try_into().unwrap(),
                     total_acknack_count.try_into().unwrap(),
-                    msg_rate_stat.stat().mean.into(),
-                    bit_rate_stat.stat().mean.into(),
-                    acknack_rate_stat.stat().mean.into(),
+                    Value::from(msg_rate_stat),
+                    msg_rate_stat.stat().stdev.into(),
+                    msg_rate_stat.peak_mean().into(),
+                    Value::from(bit_rate_stat),
+                    bit_rate_stat.stat().stdev.into(),
+                    bit_rate_stat.peak_mean().into(),
+                    Value::from(acknack_rate_stat),
                 ]
             })
             .collect();
@@ -80,45 +127,89 @@ impl StatefulWidget for ParticipantTable {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         const TITLE_GUID_PREFIX: &str = "GUID_prefix";
+        const TITLE_NAME: &str = "name";
+        const TITLE_LAST_SEEN: &str = "last_seen";
+        const TITLE_PRODUCT_VERSION: &str = "product";
+        const TITLE_VENDOR_ID: &str = "vendor";
+        const TITLE_PROTOCOL_VERSION: &str = "rtps_ver";
         const TITLE_UNICAST_ADDRS: &str = "unicast_addrs";
         const TITLE_MULTICAST_ADDRS: &str = "multicast_addrs";
         const TITLE_READER_COUNT: &str = "readers";
         const TITLE_WRITER_COUNT: &str = "writers";
+        const TITLE_DROPPED_COUNT: &str = "dropped";
         const TITLE_MESSAGE_COUNT: &str = "msgs";
         const TITLE_BYTE_COUNT: &str = "bytes";
         const TITLE_ACKNACK_COUNT: &str = "acknacks";
         const TITLE_MSGRATE: &str = "msg rate";
         const TITLE_BITRATE: &str = "bit rate";
+        const TITLE_MSGRATE_STDEV: &str = "msg rate stdev";
+        const TITLE_MSGRATE_PEAK: &str = "msg rate peak";
+        const TITLE_BITRATE_STDEV: &str = "bit rate stdev";
+        const TITLE_BITRATE_PEAK: &str = "bit rate peak";
         const TITLE_ACKNACK_RATE: &str = "acknack rate";
 
         let header = vec![
             TITLE_GUID_PREFIX,
+            TITLE_NAME,
+            TITLE_LAST_SEEN,
+            TITLE_PRODUCT_VERSION,
+            TITLE_VENDOR_ID,
+            TITLE_PROTOCOL_VERSION,
             TITLE_UNICAST_ADDRS,
             TITLE_MULTICAST_ADDRS,
             TITLE_READER_COUNT,
             TITLE_WRITER_COUNT,
+            TITLE_DROPPED_COUNT,
             TITLE_MESSAGE_COUNT,
             TITLE_BYTE_COUNT,
             TITLE_ACKNACK_COUNT,
             TITLE_MSGRATE,
+            TITLE_MSGRATE_STDEV,
+            TITLE_MSGRATE_PEAK,
             TITLE_BITRATE,
+            TITLE_BITRATE_STDEV,
+            TITLE_BITRATE_PEAK,
             TITLE_ACKNACK_RATE,
         ];
 
-        let table = XTable::new("Participants", &header, &self.rows);
+        // Highlight the silent participants.
+        let last_seen_index = header
+            .iter()
+            .position(|&title| title == TITLE_LAST_SEEN)
+            .unwrap();
+        let stale_age = state.stale_age;
+        let row_style = |row: &[Value]| match &row[last_seen_index] {
+            Value::Time(time) if time.age_secs() > stale_age => Style::default().fg(Color::Red),
+            _ => Style::default(),
+        };
+
+        let table = XTable::new("Participants", &header, &self.rows)
+            .row_style(&row_style)
+            .hidden_by_default(&[
+                TITLE_MSGRATE_STDEV,
+                TITLE_MSGRATE_PEAK,
+                TITLE_BITRATE_STDEV,
+                TITLE_BITRATE_PEAK,
+            ]);
         table.render(area, buf, &mut state.table_state);
     }
 }
 
 pub struct ParticipantTableState {
     table_state: XTableState,
+    /// Participants silent for longer than these seconds are
+    /// highlighted.
+    stale_age: f64,
 }
 
 impl ParticipantTableState {
     pub fn new() -> Self {
         let table_state = XTableState::new();
 
-        Self { table_state }
+        Self {
+            table_state,
+            stale_age: f64::INFINITY,
+        }
     }
 
     pub fn previous_item(&mut self) {
@@ -168,4 +259,20 @@ impl ParticipantTableState {
     pub fn toggle_sort(&mut self) {
         self.table_state.toggle_sort();
     }
+
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.table_state.set_max_rows(max_rows);
+    }
+
+    pub fn set_stale_age(&mut self, stale_age: f64) {
+        self.stale_age = stale_age;
+    }
+
+    pub fn table_state(&self) -> &XTableState {
+        &self.table_state
+    }
+
+    pub fn table_state_mut(&mut self) -> &mut XTableState {
+        &mut self.table_state
+    }
 }
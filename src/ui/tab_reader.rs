@@ -1,10 +1,18 @@
-use super::{value::Value, xtable::XTableState};
+use super::{
+    value::{TimeFormat, TimeValue, Value},
+    xtable::XTableState,
+};
 use crate::{
     state::{ReaderState, State},
     ui::xtable::XTable,
+    updater::qos_mismatches,
     utils::GUIDExt,
 };
-use ratatui::{prelude::*, widgets::StatefulWidget};
+use ratatui::{
+    prelude::*,
+    style::{Color, Style},
+    widgets::StatefulWidget,
+};
 use rustdds::GUID;
 
 /// The table that keeps a list of observed reader entities.
@@ -13,7 +21,7 @@ pub struct ReaderTable {
 }
 
 impl ReaderTable {
-    pub fn new(state: &State) -> Self {
+    pub fn new(state: &State, time_format: TimeFormat) -> Self {
         let readers = state.participants.iter().flat_map(|(&guid_prefix, part)| {
             part.readers.iter().map(move |(&entity_id, reader)| {
                 let guid = GUID::new(guid_prefix, entity_id);
@@ -28,30 +36,98 @@ impl ReaderTable {
                     last_sn,
                     total_acknack_count,
                     ref acknack_rate_stat,
-                    ref acknack,
+                    ref acked_sn,
+                    last_recv_time,
+                    age,
                     ..
                 } = *entity;
 
+                // Count the writers on the topic with the same type and
+                // compatible QoS, and the ones the reader acknowledges.
+                let matched_writers: Vec<GUID> = match entity
+                    .topic_name()
+                    .and_then(|topic_name| state.topics.get(topic_name))
+                {
+                    Some(topic) => topic
+                        .writers
+                        .iter()
+                        .filter(|writer_guid| {
+                            let writer = state
+                                .participants
+                                .get(&writer_guid.prefix)
+                                .and_then(|part| part.writers.get(&writer_guid.entity_id));
+
+                            let same_type =
+                                match (writer.and_then(|w| w.type_name()), entity.type_name()) {
+                                    (Some(lhs), Some(rhs)) => lhs == rhs,
+                                    _ => true,
+                                };
+                            let compatible_qos =
+                                match (writer.and_then(|w| w.data.as_ref()), &entity.data) {
+                                    (Some(writer_data), Some(reader_data)) => qos_mismatches(
+                                        &writer_data.publication_topic_data.qos(),
+                                        &reader_data.subscription_topic_data.qos(),
+                                    )
+                                    .is_empty(),
+                                    _ => true,
+                                };
+
+                            same_type && compatible_qos
+                        })
+                        .copied()
+                        .collect(),
+                    None => vec![],
+                };
+                let receiving_count = matched_writers
+                    .iter()
+                    .filter(|writer_guid| acked_sn.contains_key(writer_guid))
+                    .count();
+
                 let guid = format!("{}", guid.display()).into();
+                let entity_name = match &entity.entity_name {
+                    Some(name) => name.into(),
+                    None => Value::None,
+                };
                 let sn = match last_sn {
                     Some(sn) => sn.into(),
                     None => Value::None,
                 };
                 let type_name = entity.type_name().unwrap_or("").to_string().into();
                 let topic_name = entity.topic_name().unwrap_or("").to_string().into();
-                let missing_sn = match acknack {
-                    Some(acknack) => format!("{:?}", acknack.missing_sn).into(),
+                let longest_missing = entity.longest_missing();
+                let missing_since = longest_missing
+                    .and_then(|(_, acknack)| acknack.missing_since)
+                    .zip(entity.missing_age)
+                    .and_then(|(since, age)| TimeValue::from_capture_time(since, age, time_format))
+                    .into();
+                let age = last_recv_time
+                    .zip(age)
+                    .and_then(|(last, age)| TimeValue::from_capture_time(last, age, time_format))
+                    .into();
+                let missing_sn = match longest_missing {
+                    Some((_, acknack)) => format!("{:?}", acknack.missing_sn).into(),
                     None => Value::None,
                 };
                 let total_acks = total_acknack_count.try_into().unwrap();
-                let avg_ack_rate = acknack_rate_stat.stat().mean.into();
+                let matched_count = matched_writers.len().try_into().unwrap();
+                let receiving_count = receiving_count.try_into().unwrap();
+                let avg_ack_rate = Value::from(acknack_rate_stat);
+                let ack_rate_stdev = acknack_rate_stat.stat().stdev.into();
+                let ack_rate_peak = acknack_rate_stat.peak_mean().into();
 
                 vec![
                     guid,
+                    entity_name,
                     sn,
+                    age,
                     missing_sn,
+                    missing_since,
                     total_acks,
                     avg_ack_rate,
+                    ack_rate_stdev,
+                    ack_rate_peak,
+                    matched_count,
+                    receiving_count,
                     type_name,
                     topic_name,
                 ]
@@ -67,37 +143,69 @@ impl StatefulWidget for ReaderTable {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         const TITLE_GUID: &str = "GUID";
+        const TITLE_NAME: &str = "name";
         const TITLE_LAST_SN: &str = "sn";
+        const TITLE_LAST_SEEN: &str = "last_seen";
         const TITLE_MISSING_SN: &str = "missing_sn";
+        const TITLE_MISSING_SINCE: &str = "missing_since";
         const TITLE_TOTAL_ACKNACK_COUNT: &str = "acknacks";
         const TITLE_AVERAGE_ACKNACK_RATE: &str = "acknack rate";
+        const TITLE_ACKNACK_RATE_STDEV: &str = "acknack rate stdev";
+        const TITLE_ACKNACK_RATE_PEAK: &str = "acknack rate peak";
+        const TITLE_MATCHED_WRITERS: &str = "matched";
+        const TITLE_RECEIVING_WRITERS: &str = "receiving";
         const TITLE_TYPE: &str = "type";
         const TITLE_TOPIC: &str = "topic";
 
         let header = vec![
             TITLE_GUID,
+            TITLE_NAME,
             TITLE_LAST_SN,
+            TITLE_LAST_SEEN,
             TITLE_MISSING_SN,
+            TITLE_MISSING_SINCE,
             TITLE_TOTAL_ACKNACK_COUNT,
             TITLE_AVERAGE_ACKNACK_RATE,
+            TITLE_ACKNACK_RATE_STDEV,
+            TITLE_ACKNACK_RATE_PEAK,
+            TITLE_MATCHED_WRITERS,
+            TITLE_RECEIVING_WRITERS,
             TITLE_TYPE,
             TITLE_TOPIC,
         ];
 
-        let table = XTable::new("Readers", &header, &self.rows);
+        // Highlight the silent readers.
+        let last_seen_index = header
+            .iter()
+            .position(|&title| title == TITLE_LAST_SEEN)
+            .unwrap();
+        let stale_age = state.stale_age;
+        let row_style = |row: &[Value]| match &row[last_seen_index] {
+            Value::Time(time) if time.age_secs() > stale_age => Style::default().fg(Color::Red),
+            _ => Style::default(),
+        };
+
+        let table = XTable::new("Readers", &header, &self.rows)
+            .row_style(&row_style)
+            .hidden_by_default(&[TITLE_ACKNACK_RATE_STDEV, TITLE_ACKNACK_RATE_PEAK]);
         table.render(area, buf, &mut state.table_state);
     }
 }
 
 pub struct ReaderTableState {
     table_state: XTableState,
+    /// Readers silent for longer than these seconds are highlighted.
+    stale_age: f64,
 }
 
 impl ReaderTableState {
     pub fn new() -> Self {
         let table_state = XTableState::new();
 
-        Self { table_state }
+        Self {
+            table_state,
+            stale_age: f64::INFINITY,
+        }
     }
 
     pub fn previous_item(&mut self) {
@@ -147,4 +255,20 @@ impl ReaderTableState {
     pub fn toggle_sort(&mut self) {
         self.table_state.toggle_sort();
     }
+
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.table_state.set_max_rows(max_rows);
+    }
+
+    pub fn set_stale_age(&mut self, stale_age: f64) {
+        self.stale_age = stale_age;
+    }
+
+    pub fn table_state(&self) -> &XTableState {
+        &self.table_state
+    }
+
+    pub fn table_state_mut(&mut self) -> &mut XTableState {
+        &mut self.table_state
+    }
 }
@@ -1,5 +1,6 @@
 use super::{value::Value, xtable::XTableState};
 use crate::{
+    rtps::CaptureStats,
     state::{State, Statistics},
     ui::xtable::XTable,
 };
@@ -11,7 +12,7 @@ pub struct StatTable {
 }
 
 impl StatTable {
-    pub fn new(state: &State) -> Self {
+    pub fn new(state: &State, capture_stats: &CaptureStats) -> Self {
         let Statistics {
             packet_count,
             data_submsg_count,
@@ -20,6 +21,7 @@ impl StatTable {
             ackfrag_submsg_count,
             heartbeat_submsg_count,
             heartbeat_frag_submsg_count,
+            gap_submsg_count,
         } = state.stat;
 
         let rows = vec![
@@ -45,6 +47,23 @@ impl StatTable {
                 "heartbeat_frag submsg".into(),
                 format!("{heartbeat_frag_submsg_count}").into(),
             ],
+            vec!["gap submsg".into(), format!("{gap_submsg_count}").into()],
+            vec![
+                "incomplete datagrams".into(),
+                format!("{}", capture_stats.evicted_datagrams()).into(),
+            ],
+            vec![
+                "unparseable packets".into(),
+                format!("{}", capture_stats.parse_errors()).into(),
+            ],
+            vec![
+                "queued events".into(),
+                format!("{}", capture_stats.queue_depth()).into(),
+            ],
+            vec![
+                "dropped events".into(),
+                format!("{}", capture_stats.dropped_events()).into(),
+            ],
         ];
 
         Self { rows }
@@ -123,4 +142,16 @@ impl StatTableState {
     pub fn toggle_sort(&mut self) {
         self.table_state.toggle_sort();
     }
+
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.table_state.set_max_rows(max_rows);
+    }
+
+    pub fn table_state(&self) -> &XTableState {
+        &self.table_state
+    }
+
+    pub fn table_state_mut(&mut self) -> &mut XTableState {
+        &mut self.table_state
+    }
 }
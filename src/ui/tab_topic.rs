@@ -1,9 +1,13 @@
-use super::{value::Value, xtable::XTableState};
+use super::{topic_filter::TopicFilter, value::Value, xtable::XTableState};
 use crate::{
     state::{State, TopicState},
     ui::xtable::XTable,
 };
-use ratatui::{prelude::*, widgets::StatefulWidget};
+use ratatui::{
+    prelude::*,
+    style::{Color, Style},
+    widgets::StatefulWidget,
+};
 
 /// The table that keeps a list of observed topics.
 pub struct TopicTable {
@@ -11,8 +15,15 @@ pub struct TopicTable {
 }
 
 impl TopicTable {
-    pub fn new(state: &State) -> Self {
-        let mut topics: Vec<_> = state.topics.iter().collect();
+    pub fn new(state: &State, filter: Option<&TopicFilter>) -> Self {
+        let mut topics: Vec<_> = state
+            .topics
+            .iter()
+            .filter(|(topic_name, _)| match filter {
+                Some(filter) => filter.matches(topic_name),
+                None => true,
+            })
+            .collect();
         topics.sort_unstable_by(|(lname, _), (rname, _)| lname.cmp(rname));
 
         let rows: Vec<_> = topics
@@ -27,30 +38,55 @@ impl TopicTable {
                     ref acknack_rate_stat,
                     ref readers,
                     ref writers,
+                    written_sn_sum,
+                    acked_sn_sum,
+                    budget_usage,
+                    retx_byte_count,
+                    ..
                 } = *topic;
 
                 let topic_name = topic_name.clone().into();
+                let type_name = topic.type_name().unwrap_or("-").into();
                 let n_readers = readers.len().try_into().unwrap();
                 let n_writers = writers.len().try_into().unwrap();
 
+                let retx_percent = if total_byte_count > 0 {
+                    (retx_byte_count as f64 / total_byte_count as f64 * 100.0).into()
+                } else {
+                    Value::None
+                };
+
                 let total_msg_count = total_msg_count.try_into().unwrap();
                 let total_byte_count = total_byte_count.try_into().unwrap();
                 let total_acknack_count = total_acknack_count.try_into().unwrap();
 
-                let avg_msgrate = msg_rate_stat.stat().mean.into();
-                let avg_bitrate = bit_rate_stat.stat().mean.into();
-                let avg_acknack_rate = acknack_rate_stat.stat().mean.into();
+                let avg_msgrate = Value::from(msg_rate_stat);
+                let avg_bitrate = Value::from(bit_rate_stat);
+                let msgrate_stdev = msg_rate_stat.stat().stdev.into();
+                let msgrate_peak = msg_rate_stat.peak_mean().into();
+                let bitrate_stdev = bit_rate_stat.stat().stdev.into();
+                let bitrate_peak = bit_rate_stat.peak_mean().into();
+                let avg_acknack_rate = Value::from(acknack_rate_stat);
 
                 vec![
                     topic_name,
+                    type_name,
                     n_readers,
                     n_writers,
                     total_msg_count,
                     avg_msgrate,
+                    msgrate_stdev,
+                    msgrate_peak,
                     total_byte_count,
                     avg_bitrate,
+                    bitrate_stdev,
+                    bitrate_peak,
                     total_acknack_count,
                     avg_acknack_rate,
+                    written_sn_sum.into(),
+                    acked_sn_sum.into(),
+                    retx_percent,
+                    budget_usage.map(|usage| usage * 100.0).into(),
                 ]
             })
             .collect();
@@ -64,28 +100,61 @@ impl StatefulWidget for TopicTable {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         const TITLE_NAME: &str = "name";
+        const TITLE_TYPE: &str = "type";
         const TITLE_NUM_READERS: &str = "# readers";
         const TITLE_NUM_WRITERS: &str = "# writers";
         const TITLE_TOTAL_MSGS: &str = "msgs";
         const TITLE_AVG_MSGRATE: &str = "msgrate";
         const TITLE_TOTAL_BYTES: &str = "bytes";
         const TITLE_AVG_BITRATE: &str = "bitrate";
+        const TITLE_MSGRATE_STDEV: &str = "msgrate_stdev";
+        const TITLE_MSGRATE_PEAK: &str = "msgrate_peak";
+        const TITLE_BITRATE_STDEV: &str = "bitrate_stdev";
+        const TITLE_BITRATE_PEAK: &str = "bitrate_peak";
         const TITLE_TOTAL_ACKNACK: &str = "acks";
         const TITLE_AVG_ACKNACK_RATE: &str = "ack_rate";
+        const TITLE_WRITTEN_SAMPLES: &str = "written";
+        const TITLE_ACKED_SAMPLES: &str = "acked";
+        const TITLE_RETX_RATIO: &str = "retx%";
+        const TITLE_BUDGET_USAGE: &str = "budget%";
 
         let header = vec![
             TITLE_NAME,
+            TITLE_TYPE,
             TITLE_NUM_READERS,
             TITLE_NUM_WRITERS,
             TITLE_TOTAL_MSGS,
             TITLE_AVG_MSGRATE,
+            TITLE_MSGRATE_STDEV,
+            TITLE_MSGRATE_PEAK,
             TITLE_TOTAL_BYTES,
             TITLE_AVG_BITRATE,
+            TITLE_BITRATE_STDEV,
+            TITLE_BITRATE_PEAK,
             TITLE_TOTAL_ACKNACK,
             TITLE_AVG_ACKNACK_RATE,
+            TITLE_WRITTEN_SAMPLES,
+            TITLE_ACKED_SAMPLES,
+            TITLE_RETX_RATIO,
+            TITLE_BUDGET_USAGE,
         ];
-
-        let table = XTable::new("Topics", &header, &self.rows);
+        let budget_index = header.len() - 1;
+
+        // Color topics by the consumption of their bandwidth budgets.
+        let row_style = |row: &[Value]| match row[budget_index] {
+            Value::Float(percent) if percent > 100.0 => Style::default().fg(Color::Red),
+            Value::Float(percent) if percent >= 80.0 => Style::default().fg(Color::Yellow),
+            _ => Style::default(),
+        };
+
+        let table = XTable::new("Topics", &header, &self.rows)
+            .row_style(&row_style)
+            .hidden_by_default(&[
+                TITLE_MSGRATE_STDEV,
+                TITLE_MSGRATE_PEAK,
+                TITLE_BITRATE_STDEV,
+                TITLE_BITRATE_PEAK,
+            ]);
         table.render(area, buf, &mut state.table_state);
     }
 }
@@ -148,4 +217,20 @@ impl TopicTableState {
     pub fn toggle_sort(&mut self) {
         self.table_state.toggle_sort();
     }
+
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.table_state.set_max_rows(max_rows);
+    }
+
+    pub fn select_topic(&mut self, topic_name: String) {
+        self.table_state.select_key(topic_name);
+    }
+
+    pub fn table_state(&self) -> &XTableState {
+        &self.table_state
+    }
+
+    pub fn table_state_mut(&mut self) -> &mut XTableState {
+        &mut self.table_state
+    }
 }
@@ -1,10 +1,18 @@
-use super::{value::Value, xtable::XTableState};
+use super::{
+    topic_filter::TopicFilter,
+    value::{TimeFormat, TimeValue, Value},
+    xtable::XTableState,
+};
 use crate::{
     state::{HeartbeatState, State, WriterState},
     ui::xtable::XTable,
     utils::GUIDExt,
 };
-use ratatui::{prelude::*, widgets::StatefulWidget};
+use ratatui::{
+    prelude::*,
+    style::{Color, Style},
+    widgets::StatefulWidget,
+};
 use rustdds::GUID;
 
 /// The table that keeps a list of observed writer entities.
@@ -13,7 +21,7 @@ pub struct WriterTable {
 }
 
 impl WriterTable {
-    pub fn new(state: &State) -> Self {
+    pub fn new(state: &State, filter: Option<&TopicFilter>, time_format: TimeFormat) -> Self {
         let mut writers: Vec<_> = state
             .participants
             .iter()
@@ -23,6 +31,12 @@ impl WriterTable {
                     (guid, writer)
                 })
             })
+            .filter(|(_, writer)| match filter {
+                Some(filter) => writer
+                    .topic_name()
+                    .is_some_and(|topic_name| filter.matches(topic_name)),
+                None => true,
+            })
             .collect();
         writers.sort_unstable_by(|(lid, _), (rid, _)| lid.cmp(rid));
 
@@ -32,21 +46,46 @@ impl WriterTable {
                 let WriterState {
                     last_sn,
                     ref frag_messages,
+                    frag_timeout_count,
                     total_msg_count,
                     total_byte_count,
                     ref bit_rate_stat,
                     ref msg_rate_stat,
                     ref heartbeat,
+                    empty_msg_count,
+                    disposal_count,
+                    ref key_hashes,
+                    ref lost_sn,
+                    ref ack_latency_stat,
+                    last_recv_time,
+                    age,
                     ..
                 } = *writer;
 
                 let guid = format!("{}", guid.display()).into();
-                let topic_name = writer.topic_name().unwrap_or("").into();
+                let entity_name = match &writer.entity_name {
+                    Some(name) => name.into(),
+                    None => Value::None,
+                };
+                let topic_name = match writer.topic_name() {
+                    Some(topic_name) => topic_name.into(),
+                    None if writer.data_before_discovery => "(awaiting discovery)".into(),
+                    None => "".into(),
+                };
                 let type_name = writer.type_name().unwrap_or("-").into();
                 let byte_count = total_byte_count.try_into().unwrap();
                 let message_count = total_msg_count.try_into().unwrap();
-                let avg_msgrate = msg_rate_stat.stat().mean.into();
-                let avg_bitrate = bit_rate_stat.stat().mean.into();
+                let avg_msgrate = Value::from(msg_rate_stat);
+                let avg_bitrate = Value::from(bit_rate_stat);
+                let msgrate_stdev = msg_rate_stat.stat().stdev.into();
+                let msgrate_peak = msg_rate_stat.peak_mean().into();
+                let bitrate_stdev = bit_rate_stat.stat().stdev.into();
+                let bitrate_peak = bit_rate_stat.peak_mean().into();
+                let ack_latency = ack_latency_stat.median().into();
+                let age = last_recv_time
+                    .zip(age)
+                    .and_then(|(last, age)| TimeValue::from_capture_time(last, age, time_format))
+                    .into();
                 let frag_msg_count = if frag_messages.is_empty() {
                     Value::None
                 } else {
@@ -66,15 +105,39 @@ impl WriterTable {
                     None => Value::None,
                 };
 
+                let key_count = if key_hashes.is_empty() {
+                    Value::None
+                } else {
+                    key_hashes.len().try_into().unwrap()
+                };
+
+                let lost_sn = if lost_sn.is_empty() {
+                    Value::None
+                } else {
+                    lost_sn.to_string().into()
+                };
+
                 vec![
                     guid,
+                    entity_name,
                     last_sn,
+                    age,
                     message_count,
                     avg_msgrate,
+                    msgrate_stdev,
+                    msgrate_peak,
                     byte_count,
                     avg_bitrate,
+                    bitrate_stdev,
+                    bitrate_peak,
                     frag_msg_count,
+                    frag_timeout_count.try_into().unwrap(),
+                    empty_msg_count.try_into().unwrap(),
+                    disposal_count.try_into().unwrap(),
+                    key_count,
                     heartbeat_range,
+                    ack_latency,
+                    lost_sn,
                     type_name,
                     topic_name,
                 ]
@@ -90,43 +153,109 @@ impl StatefulWidget for WriterTable {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         const TITLE_GUID: &str = "GUID";
+        const TITLE_NAME: &str = "name";
         const TITLE_TOPIC: &str = "topic";
         const TITLE_TYPE: &str = "type";
         const TITLE_SERIAL_NUMBER: &str = "sn";
+        const TITLE_LAST_SEEN: &str = "last_seen";
         const TITLE_MESSAGE_COUNT: &str = "msgs";
         const TITLE_BYTE_COUNT: &str = "bytes";
         const TITLE_MSGRATE: &str = "msgrate";
         const TITLE_BITRATE: &str = "bitrate";
+        const TITLE_MSGRATE_STDEV: &str = "msgrate_stdev";
+        const TITLE_MSGRATE_PEAK: &str = "msgrate_peak";
+        const TITLE_BITRATE_STDEV: &str = "bitrate_stdev";
+        const TITLE_BITRATE_PEAK: &str = "bitrate_peak";
         const TITLE_NUM_FRAGMENTED_MESSAGES: &str = "unfrag_msgs";
+        const TITLE_FRAG_TIMEOUTS: &str = "frag_drops";
         const TITLE_HEARTBEAT: &str = "cached_sn";
+        const TITLE_EMPTY_MESSAGES: &str = "empty_msgs";
+        const TITLE_DISPOSALS: &str = "disposals";
+        const TITLE_KEYS: &str = "keys";
+        const TITLE_LOST_SN: &str = "lost_sn";
+        const TITLE_ACK_LATENCY: &str = "ack_latency";
 
         let header = vec![
             TITLE_GUID,
+            TITLE_NAME,
             TITLE_SERIAL_NUMBER,
+            TITLE_LAST_SEEN,
             TITLE_MESSAGE_COUNT,
             TITLE_MSGRATE,
+            TITLE_MSGRATE_STDEV,
+            TITLE_MSGRATE_PEAK,
             TITLE_BYTE_COUNT,
             TITLE_BITRATE,
+            TITLE_BITRATE_STDEV,
+            TITLE_BITRATE_PEAK,
             TITLE_NUM_FRAGMENTED_MESSAGES,
+            TITLE_FRAG_TIMEOUTS,
+            TITLE_EMPTY_MESSAGES,
+            TITLE_DISPOSALS,
+            TITLE_KEYS,
             TITLE_HEARTBEAT,
+            TITLE_ACK_LATENCY,
+            TITLE_LOST_SN,
             TITLE_TYPE,
             TITLE_TOPIC,
         ];
 
-        let table = XTable::new("Writers", &header, &self.rows);
+        let bitrate_index = header
+            .iter()
+            .position(|&title| title == TITLE_BITRATE)
+            .unwrap();
+        let last_seen_index = header
+            .iter()
+            .position(|&title| title == TITLE_LAST_SEEN)
+            .unwrap();
+
+        // Highlight the silent writers and the ones sending faster
+        // than the threshold.
+        let highlight_bitrate = state.highlight_bitrate;
+        let stale_age = state.stale_age;
+        let row_style = |row: &[Value]| match (
+            &row[last_seen_index],
+            &row[bitrate_index],
+            highlight_bitrate,
+        ) {
+            (Value::Time(time), _, _) if time.age_secs() > stale_age => {
+                Style::default().fg(Color::Red)
+            }
+            (_, Value::Stat(stat), Some(threshold)) if stat.mean > threshold => {
+                Style::default().fg(Color::Yellow)
+            }
+            _ => Style::default(),
+        };
+
+        let table = XTable::new("Writers", &header, &self.rows)
+            .row_style(&row_style)
+            .hidden_by_default(&[
+                TITLE_MSGRATE_STDEV,
+                TITLE_MSGRATE_PEAK,
+                TITLE_BITRATE_STDEV,
+                TITLE_BITRATE_PEAK,
+            ]);
         table.render(area, buf, &mut state.table_state);
     }
 }
 
 pub struct WriterTableState {
     table_state: XTableState,
+    /// Writers above this bit rate are highlighted.
+    highlight_bitrate: Option<f64>,
+    /// Writers silent for longer than these seconds are highlighted.
+    stale_age: f64,
 }
 
 impl WriterTableState {
     pub fn new() -> Self {
         let table_state = XTableState::new();
 
-        Self { table_state }
+        Self {
+            table_state,
+            highlight_bitrate: None,
+            stale_age: f64::INFINITY,
+        }
     }
 
     pub fn previous_item(&mut self) {
@@ -176,4 +305,24 @@ impl WriterTableState {
     pub fn toggle_sort(&mut self) {
         self.table_state.toggle_sort();
     }
+
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.table_state.set_max_rows(max_rows);
+    }
+
+    pub fn set_highlight_bitrate(&mut self, highlight_bitrate: Option<f64>) {
+        self.highlight_bitrate = highlight_bitrate;
+    }
+
+    pub fn set_stale_age(&mut self, stale_age: f64) {
+        self.stale_age = stale_age;
+    }
+
+    pub fn table_state(&self) -> &XTableState {
+        &self.table_state
+    }
+
+    pub fn table_state_mut(&mut self) -> &mut XTableState {
+        &mut self.table_state
+    }
 }
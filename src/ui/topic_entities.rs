@@ -0,0 +1,123 @@
+use super::{value::Value, xtable::XTableState};
+use crate::{state::State, ui::xtable::XTable, utils::GUIDExt};
+use ratatui::{prelude::*, widgets::StatefulWidget};
+
+/// The table that lists the writers and readers on a topic.
+pub struct TopicEntityTable {
+    title: String,
+    rows: Vec<Vec<Value>>,
+}
+
+impl TopicEntityTable {
+    pub fn new(state: &State, topic_name: &str) -> Self {
+        let title = format!("Topic {topic_name}");
+        let Some(topic) = state.topics.get(topic_name) else {
+            return Self {
+                title,
+                rows: vec![],
+            };
+        };
+
+        let writer_rows = topic.writers.iter().map(|guid| {
+            let writer = state
+                .participants
+                .get(&guid.prefix)
+                .and_then(|part| part.writers.get(&guid.entity_id));
+            let guid = format!("{}", guid.display()).into();
+
+            match writer {
+                Some(writer) => {
+                    let sn = match writer.last_sn {
+                        Some(sn) => sn.0.into(),
+                        None => Value::None,
+                    };
+                    vec![
+                        guid,
+                        "writer".into(),
+                        writer.total_msg_count.try_into().unwrap(),
+                        Value::from(&writer.msg_rate_stat),
+                        Value::from(&writer.bit_rate_stat),
+                        Value::None,
+                        sn,
+                    ]
+                }
+                None => vec![
+                    guid,
+                    "writer".into(),
+                    Value::None,
+                    Value::None,
+                    Value::None,
+                    Value::None,
+                    Value::None,
+                ],
+            }
+        });
+
+        let reader_rows = topic.readers.iter().map(|guid| {
+            let reader = state
+                .participants
+                .get(&guid.prefix)
+                .and_then(|part| part.readers.get(&guid.entity_id));
+            let guid = format!("{}", guid.display()).into();
+
+            match reader {
+                Some(reader) => {
+                    let sn = match reader.last_sn {
+                        Some(sn) => sn.into(),
+                        None => Value::None,
+                    };
+                    vec![
+                        guid,
+                        "reader".into(),
+                        Value::None,
+                        Value::None,
+                        Value::None,
+                        Value::from(&reader.acknack_rate_stat),
+                        sn,
+                    ]
+                }
+                None => vec![
+                    guid,
+                    "reader".into(),
+                    Value::None,
+                    Value::None,
+                    Value::None,
+                    Value::None,
+                    Value::None,
+                ],
+            }
+        });
+
+        let mut rows: Vec<_> = writer_rows.chain(reader_rows).collect();
+        rows.sort_unstable_by(|lrow, rrow| lrow[0].partial_cmp(&rrow[0]).unwrap());
+
+        Self { title, rows }
+    }
+}
+
+impl StatefulWidget for TopicEntityTable {
+    type State = XTableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        const TITLE_GUID: &str = "GUID";
+        const TITLE_KIND: &str = "kind";
+        const TITLE_TOTAL_MSGS: &str = "msgs";
+        const TITLE_AVG_MSGRATE: &str = "msgrate";
+        const TITLE_AVG_BITRATE: &str = "bitrate";
+        const TITLE_AVG_ACKNACK_RATE: &str = "ack_rate";
+        const TITLE_LAST_SN: &str = "sn";
+
+        let header = vec![
+            TITLE_GUID,
+            TITLE_KIND,
+            TITLE_TOTAL_MSGS,
+            TITLE_AVG_MSGRATE,
+            TITLE_AVG_BITRATE,
+            TITLE_AVG_ACKNACK_RATE,
+            TITLE_LAST_SN,
+        ];
+
+        let table = XTable::new(&self.title, &header, &self.rows);
+        table.render(area, buf, state);
+    }
+}
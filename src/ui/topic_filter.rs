@@ -0,0 +1,72 @@
+use regex::Regex;
+
+/// Selects topics by name. A pattern prefixed with `re:` is matched
+/// as a regular expression, and otherwise as a case-insensitive
+/// substring.
+#[derive(Debug, Clone)]
+pub enum TopicFilter {
+    Substring { pattern: String, lowercase: String },
+    Regex(Regex),
+}
+
+impl TopicFilter {
+    pub fn parse(pattern: &str) -> Result<Self, regex::Error> {
+        let filter = match pattern.strip_prefix("re:") {
+            Some(regex) => Self::Regex(Regex::new(regex)?),
+            None => Self::Substring {
+                pattern: pattern.to_string(),
+                lowercase: pattern.to_lowercase(),
+            },
+        };
+        Ok(filter)
+    }
+
+    pub fn matches(&self, topic_name: &str) -> bool {
+        match self {
+            TopicFilter::Substring { lowercase, .. } => {
+                topic_name.to_lowercase().contains(lowercase.as_str())
+            }
+            TopicFilter::Regex(regex) => regex.is_match(topic_name),
+        }
+    }
+
+    /// Gets the pattern as typed by the user.
+    pub fn pattern(&self) -> String {
+        match self {
+            TopicFilter::Substring { pattern, .. } => pattern.clone(),
+            TopicFilter::Regex(regex) => format!("re:{}", regex.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substring_matches_case_insensitively() {
+        let filter = TopicFilter::parse("Chat").unwrap();
+        assert!(filter.matches("rt/chatter"));
+        assert!(filter.matches("rt/CHATTER"));
+        assert!(!filter.matches("rt/parameter_events"));
+        assert_eq!(filter.pattern(), "Chat");
+    }
+
+    #[test]
+    fn regex_pattern_matches_with_prefix() {
+        let filter = TopicFilter::parse("re:^rt/.*_events$").unwrap();
+        assert!(filter.matches("rt/parameter_events"));
+        assert!(!filter.matches("rt/chatter"));
+        assert!(!filter.matches("rq/parameter_events"));
+        assert_eq!(filter.pattern(), "re:^rt/.*_events$");
+    }
+
+    #[test]
+    fn invalid_regex_is_rejected() {
+        assert!(TopicFilter::parse("re:rt/(chatter").is_err());
+
+        // Without the prefix, the same text is a plain substring.
+        let filter = TopicFilter::parse("rt/(chatter").unwrap();
+        assert!(filter.matches("rt/(chatter)"));
+    }
+}
@@ -0,0 +1,68 @@
+//! The table layouts that are kept across runs.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// The saved layouts of all tabs, keyed by the tab title.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UiState {
+    #[serde(default)]
+    pub tabs: BTreeMap<String, TableLayout>,
+}
+
+/// The hidden columns and the sort order of a table. Columns are
+/// referred by titles so that the layout survives column reordering.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableLayout {
+    #[serde(default)]
+    pub hidden: Vec<String>,
+    pub sort: Option<SortLayout>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SortLayout {
+    pub column: String,
+    pub ascending: bool,
+}
+
+impl UiState {
+    /// Gets the file path, which is `$XDG_CONFIG_HOME/ddshark/ui.toml`
+    /// or `~/.config/ddshark/ui.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(config_dir.join("ddshark").join("ui.toml"))
+    }
+
+    /// Loads the state from the file. A missing file gives an empty
+    /// state.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        let state =
+            toml::from_str(&text).with_context(|| format!("unable to parse {}", path.display()))?;
+        Ok(state)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("unable to create {}", dir.display()))?;
+        }
+
+        let text = toml::to_string(self)?;
+        fs::write(path, text).with_context(|| format!("unable to write {}", path.display()))?;
+        Ok(())
+    }
+}
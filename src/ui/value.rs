@@ -1,6 +1,9 @@
+use crate::utils::TimedStat;
+use chrono::{DateTime, Local};
 use std::{
     cmp::Ordering,
     fmt::{self, Display},
+    time::SystemTime,
 };
 
 /// The value type for the cells of [XTable](super::xtable::XTable).
@@ -11,6 +14,92 @@ pub enum Value {
     Integer(i64),
     Float(f64),
     Text(String),
+    Stat(StatSummary),
+    Time(TimeValue),
+}
+
+/// The summary of a [TimedStat] shown as its mean value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatSummary {
+    pub mean: f64,
+    pub stdev: f64,
+    pub num_samples: usize,
+}
+
+/// How the time columns are displayed, toggled across all tabs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeFormat {
+    /// The time elapsed since then, e.g. "12s ago".
+    #[default]
+    Relative,
+    /// The time in RFC 3339.
+    Absolute,
+}
+
+impl TimeFormat {
+    pub fn toggle(self) -> Self {
+        match self {
+            TimeFormat::Relative => TimeFormat::Absolute,
+            TimeFormat::Absolute => TimeFormat::Relative,
+        }
+    }
+}
+
+/// A point in time shown in the chosen [TimeFormat].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeValue {
+    pub when: DateTime<Local>,
+    /// The time elapsed since `when`.
+    pub age: chrono::Duration,
+    pub format: TimeFormat,
+}
+
+impl TimeValue {
+    /// Creates the value for a wall clock time.
+    pub fn new(when: DateTime<Local>, format: TimeFormat) -> Self {
+        Self {
+            when,
+            age: Local::now() - when,
+            format,
+        }
+    }
+
+    /// Creates the value for a capture time, which counts from the
+    /// UNIX epoch. The age is given since the capture time does not
+    /// follow the wall clock in offline replays.
+    pub fn from_capture_time(
+        recv_time: chrono::Duration,
+        age: chrono::Duration,
+        format: TimeFormat,
+    ) -> Option<Self> {
+        let when = SystemTime::UNIX_EPOCH + recv_time.to_std().ok()?;
+        Some(Self {
+            when: when.into(),
+            age,
+            format,
+        })
+    }
+
+    /// Gets the age in seconds.
+    pub fn age_secs(&self) -> f64 {
+        self.age.num_milliseconds() as f64 / 1000.0
+    }
+}
+
+impl Display for TimeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format {
+            TimeFormat::Absolute => write!(f, "{}", self.when.to_rfc3339()),
+            TimeFormat::Relative => {
+                let secs = self.age.num_seconds().max(0);
+                match secs {
+                    0..=59 => write!(f, "{secs}s ago"),
+                    60..=3599 => write!(f, "{}m{}s ago", secs / 60, secs % 60),
+                    _ => write!(f, "{}h{}m ago", secs / 3600, secs % 3600 / 60),
+                }
+            }
+        }
+    }
 }
 
 impl PartialOrd<Value> for Value {
@@ -23,6 +112,8 @@ impl PartialOrd<Value> for Value {
             (Value::Integer(lhs), Value::Integer(rhs)) => lhs.partial_cmp(rhs),
             (Value::Float(lhs), Value::Float(rhs)) => lhs.partial_cmp(rhs),
             (Value::Text(lhs), Value::Text(rhs)) => lhs.partial_cmp(rhs),
+            (Value::Stat(lhs), Value::Stat(rhs)) => lhs.mean.partial_cmp(&rhs.mean),
+            (Value::Time(lhs), Value::Time(rhs)) => lhs.when.partial_cmp(&rhs.when),
             _ => None,
         }
     }
@@ -58,6 +149,8 @@ impl Display for Value {
                 }
             }
             Value::Text(value) => write!(f, "{value}"),
+            Value::Stat(summary) => Value::Float(summary.mean).fmt(f),
+            Value::Time(time) => time.fmt(f),
         }
     }
 }
@@ -190,3 +283,29 @@ impl From<Option<f64>> for Value {
         }
     }
 }
+
+impl From<TimeValue> for Value {
+    fn from(value: TimeValue) -> Self {
+        Self::Time(value)
+    }
+}
+
+impl From<Option<TimeValue>> for Value {
+    fn from(value: Option<TimeValue>) -> Self {
+        match value {
+            Some(value) => Self::Time(value),
+            None => Self::None,
+        }
+    }
+}
+
+impl From<&TimedStat> for Value {
+    fn from(stat: &TimedStat) -> Self {
+        let summary = stat.stat();
+        Self::Stat(StatSummary {
+            mean: summary.mean,
+            stdev: summary.stdev,
+            num_samples: stat.num_samples(),
+        })
+    }
+}
@@ -1,4 +1,7 @@
-use super::value::Value;
+use super::{
+    ui_state::{SortLayout, TableLayout},
+    value::Value,
+};
 use itertools::izip;
 use ratatui::{
     layout::Constraint,
@@ -6,12 +9,21 @@ use ratatui::{
     style::{Color, Modifier, Style},
     widgets::{Block, Borders, Cell, Row, StatefulWidget, Table, TableState},
 };
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// The maximum width of a value in compact mode.
+const COMPACT_MAX_WIDTH: usize = 24;
 
 /// A table widget that supports extra browsing features.
 pub struct XTable<'a> {
     title: &'a str,
     header: &'a [&'a str],
     rows: &'a [Vec<Value>],
+    row_style: Option<&'a dyn Fn(&[Value]) -> Style>,
+    hidden_by_default: &'a [&'a str],
 }
 
 impl<'a> XTable<'a> {
@@ -20,15 +32,41 @@ impl<'a> XTable<'a> {
             header,
             rows,
             title,
+            row_style: None,
+            hidden_by_default: &[],
         }
     }
+
+    /// Sets the function that computes the style of a row from its
+    /// values.
+    pub fn row_style(mut self, row_style: &'a dyn Fn(&[Value]) -> Style) -> Self {
+        self.row_style = Some(row_style);
+        self
+    }
+
+    /// Sets the columns that stay hidden until the user shows them.
+    pub fn hidden_by_default(mut self, titles: &'a [&'a str]) -> Self {
+        self.hidden_by_default = titles;
+        self
+    }
 }
 
 impl<'a> StatefulWidget for XTable<'a> {
     type State = XTableState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        // Restore the saved layout once the column titles are known.
+        if let Some(layout) = state.pending_layout.take() {
+            state.apply_layout(self.header, &layout);
+        }
+
         let mut rows: Vec<_> = self.rows.iter().collect();
+
+        // Keep the rows with any value containing the filter text.
+        if let Some(filter) = &state.filter {
+            rows.retain(|row| row.iter().any(|value| value.to_string().contains(filter)));
+        }
+
         if let Some(sort) = &state.sort {
             rows.sort_unstable_by(|lrow, rrow| {
                 let lhs = &lrow[sort.column_index];
@@ -43,6 +81,16 @@ impl<'a> StatefulWidget for XTable<'a> {
             });
         }
 
+        // Cap the number of rendered rows after sorting.
+        let num_hidden = match state.max_rows {
+            Some(max_rows) if rows.len() > max_rows => {
+                let num_hidden = rows.len() - max_rows;
+                rows.truncate(max_rows);
+                num_hidden
+            }
+            _ => 0,
+        };
+
         let header: Vec<String> = izip!(0.., &state.show, self.header)
             .map(|(index, &show, title)| {
                 if show {
@@ -65,16 +113,62 @@ impl<'a> StatefulWidget for XTable<'a> {
             })
             .collect();
 
+        // Rows are identified by the value in the first column when
+        // they are compared against the previous render.
+        let row_keys: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                row.first()
+                    .map(|value| value.to_string())
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        // Select the requested row once it shows up.
+        if let Some(key) = &state.pending_select {
+            if let Some(index) = row_keys.iter().position(|row_key| row_key == key) {
+                state.table_state.select(Some(index));
+                state.pending_select = None;
+            }
+        }
+
+        state.row_keys = row_keys.clone();
+
+        // Write the displayed rows if an export is requested.
+        if let Some(path) = state.pending_export.take() {
+            let result = state.write_csv(&path, &rows);
+            state.export_result = Some((path, result));
+        }
+
+        // Remember the selected cell for the detail view.
+        state.selected_cell = match (state.table_state.selected(), state.column_index) {
+            (Some(row_index), Some(column_index)) => rows
+                .get(row_index)
+                .and_then(|row| row.get(column_index))
+                .cloned(),
+            _ => None,
+        };
+
+        let row_styles: Vec<Style> = rows
+            .iter()
+            .map(|row| match self.row_style {
+                Some(row_style) => row_style(row),
+                None => Style::default(),
+            })
+            .collect();
+
         let rows: Vec<Vec<String>> = rows
             .iter()
             .cloned()
             .map(|row| {
                 let row: Vec<String> = izip!(&state.show, row)
                     .map(|(&show, value)| {
-                        if show {
-                            value.to_string()
-                        } else {
+                        if !show {
                             "".to_string()
+                        } else if state.compact {
+                            truncate(value.to_string(), COMPACT_MAX_WIDTH)
+                        } else {
+                            value.to_string()
                         }
                     })
                     .collect();
@@ -82,8 +176,9 @@ impl<'a> StatefulWidget for XTable<'a> {
             })
             .collect();
 
-        let widths: Vec<_> = izip!(0.., &state.show, &header)
-            .map(|(idx, &show, title)| {
+        state.width_adjusts.resize(self.header.len(), 0);
+        let widths: Vec<_> = izip!(0.., &state.show, &header, &state.width_adjusts)
+            .map(|(idx, &show, title, &adjust)| {
                 if show {
                     let max_len = rows
                         .iter()
@@ -91,16 +186,45 @@ impl<'a> StatefulWidget for XTable<'a> {
                         .max()
                         .unwrap_or(0)
                         .max(title.len());
-                    Constraint::Max(max_len as u16)
+
+                    // Use a fixed width once the user adjusts the
+                    // column, so that it can grow beyond its content.
+                    if adjust == 0 {
+                        Constraint::Max(max_len as u16)
+                    } else {
+                        let width = (max_len as i32 + adjust).max(1);
+                        Constraint::Length(width as u16)
+                    }
                 } else {
                     Constraint::Max(1)
                 }
             })
             .collect();
 
-        let rows: Vec<_> = rows
-            .into_iter()
-            .map(|row| {
+        // Mark the cells that changed since the last render.
+        let changed: Vec<Vec<bool>> = if state.show_deltas {
+            izip!(&row_keys, &rows)
+                .map(|(key, row)| match state.prev_rows.get(key) {
+                    Some(prev_row) => izip!(row, prev_row)
+                        .map(|(cur, prev)| cur != prev)
+                        .collect(),
+                    None => vec![false; row.len()],
+                })
+                .collect()
+        } else {
+            vec![vec![]; rows.len()]
+        };
+
+        state.prev_rows = if state.show_deltas {
+            izip!(row_keys, &rows)
+                .map(|(key, row)| (key, row.clone()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut rows: Vec<_> = izip!(rows, row_styles, changed)
+            .map(|(row, row_style, changed)| {
                 let row: Vec<_> = row
                     .into_iter()
                     .enumerate()
@@ -111,12 +235,15 @@ impl<'a> StatefulWidget for XTable<'a> {
                         if Some(index) == state.column_index {
                             style = style.add_modifier(Modifier::BOLD);
                         }
+                        if changed.get(index).copied().unwrap_or(false) {
+                            style = style.add_modifier(Modifier::REVERSED);
+                        }
 
                         cell.style(style)
                     })
                     .collect();
 
-                Row::new(row)
+                Row::new(row).style(row_style)
             })
             .collect();
 
@@ -137,15 +264,33 @@ impl<'a> StatefulWidget for XTable<'a> {
 
         // Save the # of entires
         state.num_entries = rows.len();
+
+        if num_hidden > 0 {
+            let footer = Row::new(vec![Cell::from(format!("…and {num_hidden} more"))])
+                .style(Style::default().fg(Color::DarkGray));
+            rows.push(footer);
+        }
         state.page_height = (area.height as usize).saturating_sub(3).max(1);
         state.num_columns = self.header.len();
+        state.titles = self.header.iter().map(|title| title.to_string()).collect();
 
         if let Some(column_index) = state.column_index {
             if column_index >= self.header.len() {
                 state.column_index = None;
             }
         }
-        state.show.resize(self.header.len(), true);
+
+        // Show the newly seen columns unless they are hidden by
+        // default.
+        let num_known = state.show.len();
+        let hidden_by_default = self.hidden_by_default;
+        state.show.extend(
+            self.header
+                .iter()
+                .skip(num_known)
+                .map(|title| !hidden_by_default.contains(title)),
+        );
+        state.show.truncate(self.header.len());
 
         let table = Table::new(rows)
             .style(Style::default().fg(Color::White))
@@ -167,6 +312,23 @@ pub struct XTableState {
     column_index: Option<usize>,
     show: Vec<bool>,
     sort: Option<Sort>,
+    max_rows: Option<usize>,
+    titles: Vec<String>,
+    width_adjusts: Vec<i32>,
+    compact: bool,
+    show_deltas: bool,
+    prev_rows: HashMap<String, Vec<String>>,
+    pending_select: Option<String>,
+    filter: Option<String>,
+    row_keys: Vec<String>,
+    selected_cell: Option<Value>,
+    /// The saved layout to be applied on the next render.
+    pending_layout: Option<TableLayout>,
+    /// The CSV file that the displayed rows are written to on the
+    /// next render.
+    pending_export: Option<PathBuf>,
+    /// The outcome of the last export, taken by the caller.
+    export_result: Option<(PathBuf, Result<(), csv::Error>)>,
 }
 
 impl XTableState {
@@ -182,7 +344,145 @@ impl XTableState {
             column_index: None,
             show: vec![],
             sort: None,
+            max_rows: None,
+            titles: vec![],
+            width_adjusts: vec![],
+            compact: false,
+            show_deltas: false,
+            prev_rows: HashMap::new(),
+            pending_select: None,
+            filter: None,
+            row_keys: vec![],
+            selected_cell: None,
+            pending_layout: None,
+            pending_export: None,
+            export_result: None,
+        }
+    }
+
+    /// Requests to write the displayed rows to a CSV file on the next
+    /// render. The outcome is available from
+    /// [take_export_result](Self::take_export_result) afterwards.
+    pub fn export_csv(&mut self, path: PathBuf) {
+        self.pending_export = Some(path);
+    }
+
+    /// Takes the outcome of the last export.
+    pub fn take_export_result(&mut self) -> Option<(PathBuf, Result<(), csv::Error>)> {
+        self.export_result.take()
+    }
+
+    /// Writes the filtered and sorted rows to a CSV file. Hidden
+    /// columns are left out.
+    fn write_csv(&self, path: &Path, rows: &[&Vec<Value>]) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        let titles = izip!(&self.titles, &self.show)
+            .filter(|(_, &show)| show)
+            .map(|(title, _)| title.as_str());
+        writer.write_record(titles)?;
+
+        for row in rows {
+            let values = izip!(row.iter(), &self.show)
+                .filter(|(_, &show)| show)
+                .map(|(value, _)| value.to_string().trim().to_string());
+            writer.write_record(values)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Gets the hidden columns and the sort order by column titles.
+    pub fn layout(&self) -> TableLayout {
+        // The table was never rendered, so keep the saved layout as
+        // is.
+        if let Some(layout) = &self.pending_layout {
+            return layout.clone();
+        }
+
+        let hidden = izip!(&self.titles, &self.show)
+            .filter(|(_, &show)| !show)
+            .map(|(title, _)| title.clone())
+            .collect();
+        let sort = self.sort_column().map(|(column, ascending)| SortLayout {
+            column: column.to_string(),
+            ascending,
+        });
+        TableLayout { hidden, sort }
+    }
+
+    /// Restores the hidden columns and the sort order on the next
+    /// render. Unknown column titles are ignored.
+    pub fn set_layout(&mut self, layout: TableLayout) {
+        self.pending_layout = Some(layout);
+    }
+
+    fn apply_layout(&mut self, header: &[&str], layout: &TableLayout) {
+        self.show = header
+            .iter()
+            .map(|title| !layout.hidden.iter().any(|hidden| hidden == title))
+            .collect();
+        self.sort = layout.sort.as_ref().and_then(|sort| {
+            let column_index = header.iter().position(|title| *title == sort.column)?;
+            Some(Sort {
+                column_index,
+                ascending: sort.ascending,
+            })
+        });
+    }
+
+    /// Gets the number of rendered rows.
+    pub fn num_entries(&self) -> usize {
+        self.num_entries
+    }
+
+    /// Gets the index of the selected row.
+    pub fn selected(&self) -> Option<usize> {
+        if self.num_entries == 0 {
+            return None;
         }
+        self.table_state.selected()
+    }
+
+    /// Gets the title of the sorting column and whether the order is
+    /// ascending.
+    pub fn sort_column(&self) -> Option<(&str, bool)> {
+        let sort = self.sort.as_ref()?;
+        let title = self.titles.get(sort.column_index)?;
+        Some((title, sort.ascending))
+    }
+
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.max_rows = max_rows;
+    }
+
+    /// Gets the first column value of the selected row.
+    pub fn selected_key(&self) -> Option<&str> {
+        let index = self.selected()?;
+        self.row_keys.get(index).map(|key| key.as_str())
+    }
+
+    /// Gets the value of the cell at the selected row and column.
+    pub fn selected_cell(&self) -> Option<&Value> {
+        self.selected_cell.as_ref()
+    }
+
+    /// Gets the filter text.
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    /// Shows only the rows with any value containing the text.
+    pub fn set_filter(&mut self, filter: Option<String>) {
+        self.filter = filter;
+        self.first_item();
+    }
+
+    /// Selects the row whose first column has the given value. The
+    /// selection is deferred until such a row is rendered.
+    pub fn select_key(&mut self, key: String) {
+        self.pending_select = Some(key);
     }
 
     pub fn previous_item(&mut self) {
@@ -206,9 +506,25 @@ impl XTableState {
     }
 
     pub fn previous_page(&mut self) {
+        self.scroll_up(self.page_height);
+    }
+
+    pub fn next_page(&mut self) {
+        self.scroll_down(self.page_height);
+    }
+
+    pub fn previous_half_page(&mut self) {
+        self.scroll_up((self.page_height / 2).max(1));
+    }
+
+    pub fn next_half_page(&mut self) {
+        self.scroll_down((self.page_height / 2).max(1));
+    }
+
+    fn scroll_up(&mut self, n_rows: usize) {
         if self.num_entries > 0 {
             let orig_idx = self.table_state.selected().unwrap_or(0);
-            let new_idx = orig_idx.saturating_sub(self.page_height);
+            let new_idx = orig_idx.saturating_sub(n_rows);
             let diff = orig_idx - new_idx;
 
             self.table_state.select(Some(new_idx));
@@ -217,10 +533,10 @@ impl XTableState {
         }
     }
 
-    pub fn next_page(&mut self) {
+    fn scroll_down(&mut self, n_rows: usize) {
         if let Some(last_idx) = self.num_entries.checked_sub(1) {
             let orig_idx = self.table_state.selected().unwrap_or(0);
-            let new_idx = orig_idx.saturating_add(self.page_height).min(last_idx);
+            let new_idx = orig_idx.saturating_add(n_rows).min(last_idx);
             self.table_state.select(Some(new_idx));
             *self.table_state.offset_mut() += new_idx - orig_idx;
         }
@@ -276,6 +592,30 @@ impl XTableState {
         }
     }
 
+    pub fn grow_column(&mut self) {
+        self.adjust_column_width(1);
+    }
+
+    pub fn shrink_column(&mut self) {
+        self.adjust_column_width(-1);
+    }
+
+    fn adjust_column_width(&mut self, delta: i32) {
+        if let Some(column_index) = self.column_index {
+            if let Some(adjust) = self.width_adjusts.get_mut(column_index) {
+                *adjust += delta;
+            }
+        }
+    }
+
+    pub fn toggle_compact(&mut self) {
+        self.compact = !self.compact;
+    }
+
+    pub fn toggle_deltas(&mut self) {
+        self.show_deltas = !self.show_deltas;
+    }
+
     pub fn toggle_sort(&mut self) {
         if let Some(column_index) = self.column_index {
             if let Some(sort) = &mut self.sort {
@@ -302,3 +642,15 @@ struct Sort {
     pub column_index: usize,
     pub ascending: bool,
 }
+
+/// Truncates the text to at most `max_len` characters, marking the
+/// truncation with an ellipsis.
+fn truncate(text: String, max_len: usize) -> String {
+    if text.chars().count() <= max_len {
+        return text;
+    }
+
+    let mut truncated: String = text.chars().take(max_len.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
@@ -2,7 +2,11 @@
 //! singleton state.
 
 use crate::{
-    config::TICK_INTERVAL,
+    config::{
+        DEFAULT_LEASE_DURATION, DROPPED_EVENT_REPORT_INTERVAL, KEY_HASH_HISTORY_LEN,
+        PARSE_ERROR_REPORT_INTERVAL, SENT_SN_HISTORY_LEN, TICK_INTERVAL, UPDATE_BATCH_SIZE,
+    },
+    event_stream::EventStream,
     logger::Logger,
     message::{
         AckNackEvent, DataEvent, DataFragEvent, DataPayload, GapEvent, HeartbeatEvent,
@@ -11,24 +15,71 @@ use crate::{
     },
     opts::Opts,
     otlp,
-    state::{Abnormality, AckNackState, FragmentedMessage, HeartbeatState, State},
+    rtps::{CaptureStats, ReplayControl},
+    state::{
+        Abnormality, AckNackState, FragmentedMessage, HeartbeatState, NackFragState,
+        ParticipantState, ReaderState, Severity, State, TopicState, WriterState,
+    },
+    state_cleanup::StateCleanup,
+    utils::{EntityIdExt, GUIDExt, GuidPrefixExt, LocatorExt, SnRanges},
 };
 use anyhow::Result;
 use chrono::Local;
+use rustdds::{
+    policy::{Durability, Reliability},
+    structure::{
+        guid::{EntityId, EntityKind, GuidPrefix},
+        locator::Locator,
+    },
+    QosPolicies, SequenceNumber, GUID,
+};
 use std::{
-    sync::{Arc, Mutex},
-    time::Instant,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt::Write as _,
+    net::IpAddr,
+    ops::Range,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
 };
 use tokio::{select, time::MissedTickBehavior};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, warn};
+use tracing::{error, warn};
+
+/// The number of samples observed on a writer before its payload
+/// sizes are checked.
+const MIN_PAYLOAD_SIZE_SAMPLES: usize = 30;
 
 pub struct Updater {
     rx: flume::Receiver<UpdateEvent>,
     state: Arc<Mutex<State>>,
     otlp_handle: Option<otlp::TraceHandle>,
+    event_stream: Option<EventStream>,
     cancel_token: CancellationToken,
     logger: Option<Logger>,
+    replay: ReplayControl,
+    log_dir: PathBuf,
+    log_overwrite: bool,
+    max_ack_lag: i64,
+    max_disposal_rate: f64,
+    max_spdp_per_lease: usize,
+    payload_size_sigma: f64,
+    max_endpoints: usize,
+    max_retx_ratio: f64,
+    max_missing_age: f64,
+    max_abnormalities: usize,
+    /// Apply the queued events in batches under one state lock.
+    batch: bool,
+    budget: BandwidthBudget,
+    capture_stats: CaptureStats,
+    parse_error_reporter: CounterReporter,
+    dropped_event_reporter: CounterReporter,
+    /// Set once the poisoned state mutex is reported.
+    poison_reported: bool,
+    cleanup: StateCleanup,
+    /// The (reader, writer entity ID) pairs reported for ambiguous
+    /// writers addressed without INFO_DST.
+    ambiguous_writer_reported: HashSet<(GUID, EntityId)>,
 }
 
 impl Updater {
@@ -36,6 +87,8 @@ impl Updater {
         rx: flume::Receiver<UpdateEvent>,
         cancel_token: CancellationToken,
         state: Arc<Mutex<State>>,
+        replay: ReplayControl,
+        capture_stats: CaptureStats,
         opts: &Opts,
     ) -> Result<Self> {
         // Enable OTLP if `otlp_enable` is true.
@@ -44,8 +97,13 @@ impl Updater {
             false => None,
         };
 
+        let event_stream = match &opts.events_jsonl {
+            Some(path) => Some(EventStream::new(path)?),
+            None => None,
+        };
+
         let logger = if opts.log_on_start {
-            Some(Logger::new()?)
+            Some(Logger::new(&opts.log_dir, opts.log_overwrite)?)
         } else {
             None
         };
@@ -54,8 +112,31 @@ impl Updater {
             rx,
             state,
             otlp_handle,
+            event_stream,
             logger,
             cancel_token,
+            replay,
+            log_dir: opts.log_dir.clone(),
+            log_overwrite: opts.log_overwrite,
+            max_ack_lag: opts.max_ack_lag,
+            max_disposal_rate: opts.max_disposal_rate,
+            max_spdp_per_lease: opts.max_spdp_per_lease,
+            payload_size_sigma: opts.payload_size_sigma,
+            max_endpoints: opts.max_endpoints,
+            max_retx_ratio: opts.max_retx_ratio,
+            max_missing_age: opts.max_missing_age,
+            max_abnormalities: opts.max_abnormalities,
+            batch: opts.batch,
+            budget: BandwidthBudget::new(opts),
+            capture_stats,
+            parse_error_reporter: CounterReporter::new(PARSE_ERROR_REPORT_INTERVAL),
+            dropped_event_reporter: CounterReporter::new(DROPPED_EVENT_REPORT_INTERVAL),
+            poison_reported: false,
+            cleanup: StateCleanup::new(
+                Duration::from_secs(opts.cleanup_interval),
+                Duration::from_secs(opts.cleanup_retention),
+            ),
+            ambiguous_writer_reported: HashSet::new(),
         })
     }
 
@@ -75,17 +156,16 @@ impl Updater {
             };
 
             let state = self.state.clone();
-            let Ok(mut state) = state.lock() else {
-                panic!("INTERNAL ERROR Mutex poision error");
-            };
+            let mut state = lock_state(&state, &mut self.poison_reported);
 
             // Remember the difference b/w the current and receipt time.
             let now = Instant::now();
             let recv_time = match &message {
-                UpdateEvent::RtpsMsg(_) => todo!(),
                 UpdateEvent::RtpsSubmsg(msg) => msg.recv_time,
                 UpdateEvent::ParticipantInfo(msg) => msg.recv_time,
-                UpdateEvent::Tick(_) => unreachable!(),
+                // Ticks are generated below once the capture starts,
+                // so a stray one carries no capture time.
+                UpdateEvent::Tick(_) => continue,
                 UpdateEvent::ToggleLogging => {
                     self.toggle_logging()?;
                     continue;
@@ -107,8 +187,11 @@ impl Updater {
                     return Ok(());
                 }
                 now = interval.tick() => {
+                    // Follow the capture time of the replay so that
+                    // the statistics windows are measured in capture
+                    // time regardless of the replay speed and seeks.
                     let elapsed = now.duration_since(first_instant.into());
-                    let recv_time = first_recv_time + chrono::Duration::from_std(elapsed).unwrap();
+                    let recv_time = first_recv_time + self.replay.capture_elapsed(elapsed);
                     TickEvent {recv_time, when: now.into() }.into()
                 }
                 result = self.rx.recv_async() => {
@@ -120,12 +203,28 @@ impl Updater {
             };
 
             let state = self.state.clone();
-            let Ok(mut state) = state.lock() else {
-                error!("INTERNAL ERROR Mutex poision error");
-                break;
-            };
+            let mut state = lock_state(&state, &mut self.poison_reported);
 
             self.handle_message(&mut state, &message)?;
+
+            // Apply the events already queued without releasing the
+            // lock. Stop early on cancellation so that quitting is not
+            // delayed by a full batch.
+            if self.batch {
+                for _ in 1..UPDATE_BATCH_SIZE {
+                    if self.cancel_token.is_cancelled() {
+                        return Ok(());
+                    }
+                    let Ok(message) = self.rx.try_recv() else {
+                        break;
+                    };
+                    self.handle_message(&mut state, &message)?;
+                }
+            }
+        }
+
+        if let Some(stream) = &mut self.event_stream {
+            stream.flush()?;
         }
 
         // Turn off logging
@@ -136,44 +235,135 @@ impl Updater {
         Ok(())
     }
 
+    /// Applies the events to the state under a single lock without
+    /// waiting on the channel.
+    pub(crate) fn process(&mut self, messages: &[UpdateEvent]) -> Result<()> {
+        let state = self.state.clone();
+        let mut state = lock_state(&state, &mut self.poison_reported);
+        for message in messages {
+            self.handle_message(&mut state, message)?;
+        }
+        Ok(())
+    }
+
     fn handle_message(&mut self, state: &mut State, message: &UpdateEvent) -> Result<()> {
+        if let Some(stream) = &mut self.event_stream {
+            stream.write(message)?;
+        }
+
         match message {
             UpdateEvent::Tick(msg) => {
                 self.handle_tick(state, msg)?;
             }
-            UpdateEvent::RtpsMsg(_) => todo!(),
             UpdateEvent::ParticipantInfo(info) => {
                 self.handle_participant_info(state, info);
             }
-            UpdateEvent::RtpsSubmsg(msg) => match &msg.kind {
-                RtpsSubmsgEventKind::Data(event) => {
-                    self.handle_data_event(state, msg, event);
-                }
-                RtpsSubmsgEventKind::DataFrag(event) => {
-                    self.handle_data_frag_event(state, msg, event);
-                }
-                RtpsSubmsgEventKind::Gap(event) => {
-                    self.handle_gap_event(state, msg, event);
-                }
-                RtpsSubmsgEventKind::Heartbeat(event) => {
-                    self.handle_heartbeat_event(state, msg, event);
-                }
-                RtpsSubmsgEventKind::AckNack(event) => {
-                    self.handle_acknack_event(state, msg, event);
-                }
-                RtpsSubmsgEventKind::NackFrag(event) => {
-                    self.handle_nackfrag_event(state, msg, event);
+            UpdateEvent::RtpsSubmsg(msg) => {
+                if let Some(otlp_handle) = &self.otlp_handle {
+                    let writer_guid = msg.kind.writer_guid();
+                    let topic_name = state
+                        .participants
+                        .get(&writer_guid.prefix)
+                        .and_then(|part| part.writers.get(&writer_guid.entity_id))
+                        .and_then(|writer| writer.topic_name());
+                    otlp_handle.send_trace(msg, topic_name);
                 }
-                RtpsSubmsgEventKind::HeartbeatFrag(event) => {
-                    self.handle_heartbeatfrag_event(state, msg, event);
+
+                match &msg.kind {
+                    RtpsSubmsgEventKind::Data(event) => {
+                        self.handle_data_event(state, msg, event);
+                    }
+                    RtpsSubmsgEventKind::DataFrag(event) => {
+                        self.handle_data_frag_event(state, msg, event);
+                    }
+                    RtpsSubmsgEventKind::Gap(event) => {
+                        self.handle_gap_event(state, msg, event);
+                    }
+                    RtpsSubmsgEventKind::Heartbeat(event) => {
+                        self.handle_heartbeat_event(state, msg, event);
+                    }
+                    RtpsSubmsgEventKind::AckNack(event) => {
+                        if event.writer_guid.prefix != GuidPrefix::UNKNOWN {
+                            self.handle_acknack_event(state, msg, event);
+                        } else if let Some(writer_guid) =
+                            self.resolve_writer_guid(state, event.writer_guid, event.reader_guid)
+                        {
+                            let event = AckNackEvent {
+                                writer_guid,
+                                ..event.clone()
+                            };
+                            self.handle_acknack_event(state, msg, &event);
+                        }
+                    }
+                    RtpsSubmsgEventKind::NackFrag(event) => {
+                        if event.writer_guid.prefix != GuidPrefix::UNKNOWN {
+                            self.handle_nackfrag_event(state, msg, event);
+                        } else if let Some(writer_guid) =
+                            self.resolve_writer_guid(state, event.writer_guid, event.reader_guid)
+                        {
+                            let event = NackFragEvent {
+                                writer_guid,
+                                ..event.clone()
+                            };
+                            self.handle_nackfrag_event(state, msg, &event);
+                        }
+                    }
+                    RtpsSubmsgEventKind::HeartbeatFrag(event) => {
+                        self.handle_heartbeatfrag_event(state, msg, event);
+                    }
                 }
-            },
+            }
             UpdateEvent::ToggleLogging => self.toggle_logging()?,
         }
 
         Ok(())
     }
 
+    /// Finds the writer addressed by a reader submessage that arrives
+    /// without INFO_DST, whose writer GUID prefix is UNKNOWN. The
+    /// writer is resolved by its entity ID among the known writers.
+    /// An ambiguous entity ID is reported once per reader and writer
+    /// entity ID.
+    fn resolve_writer_guid(
+        &mut self,
+        state: &mut State,
+        writer_guid: GUID,
+        reader_guid: GUID,
+    ) -> Option<GUID> {
+        let candidates: Vec<GuidPrefix> = state
+            .participants
+            .iter()
+            .filter(|(_, part)| part.writers.contains_key(&writer_guid.entity_id))
+            .map(|(&guid_prefix, _)| guid_prefix)
+            .collect();
+
+        match *candidates.as_slice() {
+            [guid_prefix] => return Some(GUID::new(guid_prefix, writer_guid.entity_id)),
+            [] => return None,
+            _ => {}
+        }
+
+        let newly_seen = self
+            .ambiguous_writer_reported
+            .insert((reader_guid, writer_guid.entity_id));
+        if newly_seen {
+            state.abnormalities.push(Abnormality {
+                when: Local::now(),
+                writer_guid: None,
+                reader_guid: Some(reader_guid),
+                topic_name: None,
+                severity: Severity::Warning,
+                desc: format!(
+                    "reader addresses writer entity {} without INFO_DST, \
+                     which matches {} participants",
+                    writer_guid.entity_id.display(),
+                    candidates.len()
+                ),
+            });
+        }
+        None
+    }
+
     fn handle_tick(&mut self, state: &mut State, msg: &TickEvent) -> Result<()> {
         state.tick_since = msg.when;
 
@@ -184,30 +374,219 @@ impl Updater {
             participant.msg_rate_stat.set_last_ts(ts);
             participant.acknack_rate_stat.set_last_ts(ts);
 
+            participant.age = participant.last_recv_time.map(|last| ts - last);
+
             for writer in participant.writers.values_mut() {
+                writer.age = writer.last_recv_time.map(|last| ts - last);
                 writer.bit_rate_stat.set_last_ts(ts);
                 writer.msg_rate_stat.set_last_ts(ts);
+                writer.disposal_rate_stat.set_last_ts(ts);
+                writer.ack_latency_stat.set_last_ts(ts);
+                writer
+                    .bit_rate_history
+                    .push(writer.bit_rate_stat.stat().mean);
             }
 
             for reader in participant.readers.values_mut() {
+                reader.age = reader.last_recv_time.map(|last| ts - last);
                 reader.acknack_rate_stat.set_last_ts(ts);
+                reader.missing_age = reader
+                    .longest_missing()
+                    .and_then(|(_, acknack)| acknack.missing_since)
+                    .map(|since| ts - since);
             }
         }
 
+        self.report_parse_errors(state);
+        self.report_dropped_events(state);
+        trim_abnormalities(state, self.max_abnormalities);
+        if self.cleanup.should_cleanup(ts) {
+            self.cleanup.cleanup(state, ts);
+        }
+
         for topic in state.topics.values_mut() {
             topic.msg_rate_stat.set_last_ts(ts);
             topic.bit_rate_stat.set_last_ts(ts);
             topic.acknack_rate_stat.set_last_ts(ts);
+            topic.bit_rate_history.push(topic.bit_rate_stat.stat().mean);
         }
 
+        self.reconcile_topic_samples(state);
+        self.check_bandwidth_budget(state);
+        self.check_retransmissions(state);
+        self.check_stuck_readers(state);
+
+        // Stop logging on failures, e.g. a full disk, rather than
+        // the whole updater.
         if let Some(logger) = &mut self.logger {
-            logger.save(state)?;
+            if let Err(err) = logger.save(state) {
+                error!("logging stopped: {err}");
+                self.logger = None;
+            }
+        }
+
+        // Flush periodically so that readers of the stream keep up.
+        if let Some(stream) = &mut self.event_stream {
+            stream.flush()?;
         }
 
         Ok(())
     }
 
+    /// Compares the samples written on each topic against the
+    /// samples acknowledged by its readers.
+    fn reconcile_topic_samples(&self, state: &mut State) {
+        let State {
+            participants,
+            topics,
+            abnormalities,
+            ..
+        } = state;
+
+        for (topic_name, topic) in topics.iter_mut() {
+            let mut written_sn_sum = 0;
+            let mut acked_sn_sum = 0;
+
+            for reader_guid in &topic.readers {
+                let Some(reader) = participants
+                    .get(&reader_guid.prefix)
+                    .and_then(|part| part.readers.get(&reader_guid.entity_id))
+                else {
+                    continue;
+                };
+
+                for (writer_guid, &acked_sn) in &reader.acked_sn {
+                    if !topic.writers.contains(writer_guid) {
+                        continue;
+                    }
+
+                    let Some(last_sn) = participants
+                        .get(&writer_guid.prefix)
+                        .and_then(|part| part.writers.get(&writer_guid.entity_id))
+                        .and_then(|writer| writer.last_sn)
+                    else {
+                        continue;
+                    };
+
+                    written_sn_sum += last_sn.0;
+                    acked_sn_sum += acked_sn;
+                }
+            }
+
+            topic.written_sn_sum = written_sn_sum;
+            topic.acked_sn_sum = acked_sn_sum;
+
+            // Report once when the lag exceeds the limit, and re-arm
+            // after it recovers.
+            let lag = written_sn_sum - acked_sn_sum;
+            if lag > self.max_ack_lag {
+                if !topic.ack_lag_reported {
+                    topic.ack_lag_reported = true;
+                    abnormalities.push(Abnormality {
+                        when: Local::now(),
+                        writer_guid: None,
+                        reader_guid: None,
+                        topic_name: Some(topic_name.clone()),
+                        severity: Severity::Warning,
+                        desc: format!(
+                            "readers lag behind writers by {lag} samples \
+                             ({written_sn_sum} written, {acked_sn_sum} acknowledged)"
+                        ),
+                    });
+                }
+            } else {
+                topic.ack_lag_reported = false;
+            }
+        }
+    }
+
+    /// Computes the budget usage on each topic and reports topics
+    /// exceeding their budgets.
+    fn check_bandwidth_budget(&mut self, state: &mut State) {
+        let mut total_bitrate = 0.0;
+
+        for (topic_name, topic) in &mut state.topics {
+            let bitrate = topic.bit_rate_stat.stat().mean;
+            total_bitrate += bitrate;
+
+            let Some(budget) = self.budget.topic_budget(topic_name) else {
+                topic.budget_usage = None;
+                continue;
+            };
+            let usage = bitrate / budget;
+            topic.budget_usage = Some(usage);
+
+            if usage > 1.0 {
+                if !topic.budget_reported {
+                    topic.budget_reported = true;
+                    state.abnormalities.push(Abnormality {
+                        when: Local::now(),
+                        writer_guid: None,
+                        reader_guid: None,
+                        topic_name: Some(topic_name.clone()),
+                        severity: Severity::Warning,
+                        desc: format!("bit rate {bitrate:.0}bps exceeds the budget {budget:.0}bps"),
+                    });
+                }
+            } else {
+                topic.budget_reported = false;
+            }
+        }
+
+        if let Some(total_budget) = self.budget.total {
+            if total_bitrate > total_budget {
+                if !self.budget.total_reported {
+                    self.budget.total_reported = true;
+                    state.abnormalities.push(Abnormality {
+                        when: Local::now(),
+                        writer_guid: None,
+                        reader_guid: None,
+                        topic_name: None,
+                        severity: Severity::Warning,
+                        desc: format!(
+                            "total bit rate {total_bitrate:.0}bps exceeds \
+                             the budget {total_budget:.0}bps"
+                        ),
+                    });
+                }
+            } else {
+                self.budget.total_reported = false;
+            }
+        }
+    }
+
+    /// Reports topics where likely retransmissions take a large share
+    /// of the traffic.
+    fn check_retransmissions(&self, state: &mut State) {
+        for (topic_name, topic) in &mut state.topics {
+            if topic.total_byte_count == 0 {
+                continue;
+            }
+            let ratio = topic.retx_byte_count as f64 / topic.total_byte_count as f64;
+
+            if ratio > self.max_retx_ratio {
+                if !topic.retx_reported {
+                    topic.retx_reported = true;
+                    state.abnormalities.push(Abnormality {
+                        when: Local::now(),
+                        writer_guid: None,
+                        reader_guid: None,
+                        topic_name: Some(topic_name.clone()),
+                        severity: Severity::Warning,
+                        desc: format!(
+                            "{:.1}% of the traffic is likely retransmitted",
+                            ratio * 100.0
+                        ),
+                    });
+                }
+            } else {
+                topic.retx_reported = false;
+            }
+        }
+    }
+
     fn handle_data_event(&self, state: &mut State, msg: &RtpsSubmsgEvent, event: &DataEvent) {
+        let rate_window = state.rate_window;
         // println!(
         //     "{}\t{}\t{:.2}bps",
         //     event.writer_id.display(),
@@ -217,10 +596,15 @@ impl Updater {
 
         if let Some(payload) = &event.payload {
             match payload {
-                DataPayload::Topic(_data) => {
-                    debug!("DiscoveredTopic not yet implemented");
-                    // let topic_name = data.topic_data.name.clone();
-                    // TODO
+                DataPayload::Topic(data) => {
+                    // List the topic even before any writer or reader
+                    // is discovered.
+                    let topic_name = data.topic_data.name.clone();
+                    let topic_state = state
+                        .topics
+                        .entry(topic_name)
+                        .or_insert_with(|| TopicState::new(rate_window));
+                    topic_state.data = Some((**data).clone());
                 }
                 DataPayload::Writer(data) => {
                     let remote_writer_guid = data.writer_proxy.remote_writer_guid;
@@ -230,11 +614,18 @@ impl Updater {
                     let participant = state
                         .participants
                         .entry(remote_writer_guid.prefix)
-                        .or_default();
+                        .or_insert_with(|| ParticipantState::new(rate_window));
+                    if !self.admit_endpoint(
+                        participant,
+                        &mut state.abnormalities,
+                        remote_writer_guid,
+                    ) {
+                        return;
+                    }
                     let writer = participant
                         .writers
                         .entry(remote_writer_guid.entity_id)
-                        .or_default();
+                        .or_insert_with(|| WriterState::new(rate_window));
 
                     // Update discovered data in state.entities
                     {
@@ -248,19 +639,36 @@ impl Updater {
                                     writer_guid: Some(event.writer_guid),
                                     reader_guid: None,
                                     topic_name: None,
+                                    severity: Severity::Warning,
                                     desc: "topic name changed in DiscoveredWriterData".to_string(),
                                 });
                             }
                         }
 
                         writer.data = Some((**data).clone());
+                        writer.data_before_discovery = false;
+
+                        if let Some(name) = vendor_entity_name(event) {
+                            writer.entity_name = Some(name);
+                        }
                     }
 
                     // Update stats on associated topic
                     {
                         let topic_name = data.publication_topic_data.topic_name.clone();
-                        let topic_state = state.topics.entry(topic_name.clone()).or_default();
+                        let topic_state = state
+                            .topics
+                            .entry(topic_name.clone())
+                            .or_insert_with(|| TopicState::new(rate_window));
                         topic_state.writers.insert(remote_writer_guid);
+                        check_type_name(
+                            state,
+                            &topic_name,
+                            &data.publication_topic_data.type_name,
+                            Some(remote_writer_guid),
+                            None,
+                        );
+                        check_qos_compatibility(state, &topic_name);
                     }
                 }
                 DataPayload::Reader(data) => {
@@ -276,12 +684,19 @@ impl Updater {
                     let participant = state
                         .participants
                         .entry(remote_reader_guid.prefix)
-                        .or_default();
+                        .or_insert_with(|| ParticipantState::new(rate_window));
+                    if !self.admit_endpoint(
+                        participant,
+                        &mut state.abnormalities,
+                        remote_reader_guid,
+                    ) {
+                        return;
+                    }
 
                     let reader = participant
                         .readers
                         .entry(remote_reader_guid.entity_id)
-                        .or_default();
+                        .or_insert_with(|| ReaderState::new(rate_window));
 
                     // Update discovered data in state.entities
                     {
@@ -295,24 +710,114 @@ impl Updater {
                                     writer_guid: Some(event.writer_guid),
                                     reader_guid: None,
                                     topic_name: None,
+                                    severity: Severity::Warning,
                                     desc: "topic name changed in DiscoveredWriterData".to_string(),
                                 });
                             }
                         }
 
                         reader.data = Some((**data).clone());
+
+                        if let Some(name) = vendor_entity_name(event) {
+                            reader.entity_name = Some(name);
+                        }
                     }
 
                     // Update stats on associated topic
                     {
                         let topic_name = data.subscription_topic_data.topic_name().clone();
-                        let topic_state = state.topics.entry(topic_name.clone()).or_default();
+                        let topic_state = state
+                            .topics
+                            .entry(topic_name.clone())
+                            .or_insert_with(|| TopicState::new(rate_window));
                         topic_state.readers.insert(remote_reader_guid);
+                        check_type_name(
+                            state,
+                            &topic_name,
+                            data.subscription_topic_data.type_name(),
+                            None,
+                            Some(remote_reader_guid),
+                        );
+                        check_qos_compatibility(state, &topic_name);
                     }
                 }
-                DataPayload::Participant(_data) => {
-                    debug!("DiscoveredParticipant not yet implemented");
-                    // TODO
+                DataPayload::Participant(data) => {
+                    let participant = state
+                        .participants
+                        .entry(event.writer_guid.prefix)
+                        .or_insert_with(|| ParticipantState::new(rate_window));
+
+                    participant.vendor_id = Some(data.vendor_id.vendor_id);
+                    participant.protocol_version =
+                        Some((data.protocol_version.major, data.protocol_version.minor));
+                    if let Some(name) = &data.entity_name {
+                        participant.entity_name = Some(name.clone());
+                    }
+
+                    // The locators in SPDP data are more complete than
+                    // the packet source.
+                    for locators in [
+                        &data.default_unicast_locators,
+                        &data.metatraffic_unicast_locators,
+                    ] {
+                        merge_locators(&mut participant.unicast_locator_list, locators);
+                    }
+                    for locators in [
+                        &data.default_multicast_locators,
+                        &data.metatraffic_multicast_locators,
+                    ] {
+                        merge_locators(&mut participant.multicast_locator_list, locators);
+                    }
+
+                    if let Some(lease) = data.lease_duration {
+                        participant.lease_duration =
+                            chrono::Duration::from_std(lease.to_std()).ok();
+                    }
+                    participant.spdp_count += 1;
+
+                    // Count announcements within each lease period.
+                    // Report once when the count exceeds the limit,
+                    // and re-arm after a normal period.
+                    let lease = participant.lease_duration.unwrap_or_else(|| {
+                        chrono::Duration::from_std(DEFAULT_LEASE_DURATION).unwrap()
+                    });
+                    let period_ended = match participant.spdp_period_start {
+                        Some(start) => msg.recv_time - start >= lease,
+                        None => true,
+                    };
+                    if period_ended {
+                        if participant.spdp_period_count <= self.max_spdp_per_lease {
+                            participant.spdp_flood_reported = false;
+                        }
+                        participant.spdp_period_start = Some(msg.recv_time);
+                        participant.spdp_period_count = 0;
+                    }
+                    participant.spdp_period_count += 1;
+
+                    let count = participant.spdp_period_count;
+                    if count > self.max_spdp_per_lease && !participant.spdp_flood_reported {
+                        participant.spdp_flood_reported = true;
+                        state.abnormalities.push(Abnormality {
+                            when: Local::now(),
+                            writer_guid: Some(event.writer_guid),
+                            reader_guid: None,
+                            topic_name: None,
+                            severity: Severity::Warning,
+                            desc: format!(
+                                "SPDP flood: {count} announcements within the lease duration of {}s",
+                                lease.num_seconds()
+                            ),
+                        });
+                    }
+
+                    if let Some(params) = &event.vendor_params {
+                        if let Some(name) = params.display_name() {
+                            participant.entity_name = Some(name.to_string());
+                        }
+                        if let Some(version) = params.product_version {
+                            participant.product_version = Some(version);
+                        }
+                    }
                 }
             }
         }
@@ -325,11 +830,33 @@ impl Updater {
             let participant = state
                 .participants
                 .entry(event.writer_guid.prefix)
-                .or_default();
+                .or_insert_with(|| ParticipantState::new(rate_window));
+            if !self.admit_endpoint(participant, &mut state.abnormalities, event.writer_guid) {
+                return;
+            }
             let writer = participant
                 .writers
                 .entry(event.writer_guid.entity_id)
-                .or_default();
+                .or_insert_with(|| WriterState::new(rate_window));
+            writer.last_recv_time = Some(msg.recv_time);
+
+            // Flag user data that arrives before the writer is
+            // discovered, which leaves the topic unknown for a while.
+            let is_user_writer = matches!(
+                event.writer_guid.entity_id.entity_kind,
+                EntityKind::WRITER_WITH_KEY_USER_DEFINED | EntityKind::WRITER_NO_KEY_USER_DEFINED
+            );
+            if is_user_writer && writer.data.is_none() && !writer.data_before_discovery {
+                writer.data_before_discovery = true;
+                state.abnormalities.push(Abnormality {
+                    when: Local::now(),
+                    writer_guid: Some(event.writer_guid),
+                    reader_guid: None,
+                    topic_name: None,
+                    severity: Severity::Info,
+                    desc: "data received before the writer is discovered".to_string(),
+                });
+            }
 
             // Update the participant state
             {
@@ -342,14 +869,36 @@ impl Updater {
                     .push(msg.recv_time, (event.payload_size * 8) as f64);
             }
 
+            // A sequence number not beyond the highest one sent to
+            // the destination is likely a retransmission.
+            let is_retx = is_retransmission(writer, event.writer_sn, event.destination);
+            if is_retx {
+                writer.retx_byte_count += event.payload_size;
+            }
+
             // Update the writer state
             {
                 writer.last_sn = Some(event.writer_sn);
 
+                record_sent_time(writer, event.writer_sn, msg.recv_time);
+
                 // Increase message count on the writer state
                 writer.total_msg_count += 1;
                 writer.msg_rate_stat.push(msg.recv_time, 1f64);
 
+                // Zero-length samples are counted as messages but
+                // tracked separately from data-bearing ones.
+                if event.payload_size == 0 {
+                    writer.empty_msg_count += 1;
+                } else {
+                    self.check_payload_size(
+                        &mut state.abnormalities,
+                        event.writer_guid,
+                        writer,
+                        event.payload_size,
+                    );
+                }
+
                 // Increase byte count on the writer state
                 writer.total_byte_count += event.payload_size;
                 writer
@@ -357,6 +906,42 @@ impl Updater {
                     .push(msg.recv_time, (event.payload_size * 8) as f64);
             }
 
+            if let Some(key_hash) = event.inline_qos.as_ref().and_then(|qos| qos.key_hash) {
+                writer.last_key_hash = Some(key_hash);
+                record_key_hash(writer, key_hash, msg.recv_time);
+            }
+
+            // Track disposed and unregistered instances
+            if let Some(status_info) = event.inline_qos.as_ref().and_then(|qos| qos.status_info) {
+                writer.last_status_info = Some(status_info);
+                if status_info.disposed {
+                    writer.disposal_count += 1;
+                }
+                if status_info.unregistered {
+                    writer.unregister_count += 1;
+                }
+                if status_info.disposed || status_info.unregistered {
+                    writer.disposal_rate_stat.push(msg.recv_time, 1f64);
+                }
+
+                let rate = writer.disposal_rate_stat.stat().mean;
+                if rate > self.max_disposal_rate {
+                    if !writer.disposal_storm_reported {
+                        writer.disposal_storm_reported = true;
+                        state.abnormalities.push(Abnormality {
+                            when: Local::now(),
+                            writer_guid: Some(event.writer_guid),
+                            reader_guid: None,
+                            topic_name: writer.topic_name().map(|t| t.to_string()),
+                            severity: Severity::Warning,
+                            desc: format!("disposal storm at {rate:.1} instances/s"),
+                        });
+                    }
+                } else {
+                    writer.disposal_storm_reported = false;
+                }
+            }
+
             // Update the stat on associated topic.
             if let Some(topic_name) = writer.topic_name() {
                 let topic = state.topics.get_mut(topic_name).unwrap();
@@ -368,6 +953,10 @@ impl Updater {
                 topic
                     .bit_rate_stat
                     .push(msg.recv_time, (event.payload_size * 8) as f64);
+
+                if is_retx {
+                    topic.retx_byte_count += event.payload_size;
+                }
             }
         }
     }
@@ -378,6 +967,7 @@ impl Updater {
         msg: &RtpsSubmsgEvent,
         event: &DataFragEvent,
     ) {
+        let rate_window = state.rate_window;
         state.stat.packet_count += 1;
         state.stat.datafrag_submsg_count += 1;
 
@@ -392,11 +982,18 @@ impl Updater {
             ..
         } = *event;
 
-        let participant = state.participants.entry(writer_guid.prefix).or_default();
+        let participant = state
+            .participants
+            .entry(writer_guid.prefix)
+            .or_insert_with(|| ParticipantState::new(rate_window));
+        if !self.admit_endpoint(participant, &mut state.abnormalities, writer_guid) {
+            return;
+        }
         let writer = participant
             .writers
             .entry(writer_guid.entity_id)
-            .or_default();
+            .or_insert_with(|| WriterState::new(rate_window));
+        writer.last_recv_time = Some(msg.recv_time);
 
         // println!(
         //     "{}\t{}\t{:.2}bps",
@@ -405,10 +1002,17 @@ impl Updater {
         //     entity.recv_bitrate()
         // );
 
+        record_sent_time(writer, writer_sn, msg.recv_time);
+
         // let topic_name = entity.topic_name().map(|t| t.to_string());
         let frag_msg = writer.frag_messages.entry(writer_sn).or_insert_with(|| {
-            FragmentedMessage::new(event.data_size as usize, event.fragment_size as usize)
+            FragmentedMessage::new(
+                event.data_size as usize,
+                event.fragment_size as usize,
+                msg.recv_time,
+            )
         });
+        frag_msg.last_update = msg.recv_time;
 
         if event.data_size as usize != frag_msg.data_size {
             let desc = format!(
@@ -421,6 +1025,7 @@ impl Updater {
                 writer_guid: Some(writer_guid),
                 reader_guid: None,
                 topic_name: writer.topic_name().map(|t| t.to_string()),
+                severity: Severity::Error,
                 desc,
             });
             return;
@@ -448,7 +1053,11 @@ impl Updater {
         match prev_hash {
             Some(prev_hash) => {
                 if prev_hash != event.payload_hash {
-                    warn!("DataFrag payload data differs in range {range:?}");
+                    warn!(
+                        writer_guid = %writer_guid.display(),
+                        topic_name = writer.topic_name().unwrap_or(""),
+                        "DataFrag payload data differs in range {range:?}"
+                    );
                 }
             }
             None => {
@@ -463,17 +1072,21 @@ impl Updater {
                 let defrag_buf = &mut frag_msg.defrag_buf;
                 // let topic_name = topic_name.unwrap_or("<none>".to_string());
 
-                if let Err(_err) = defrag_buf.insert(range.clone()) {
-                    // warn!("Unable to insert interval {range:?}");
-                    // warn!("{err}");
-                    // let free_intervals: Vec<_> = defrag_buf.free_intervals().collect();
+                if let Err(err) = defrag_buf.insert(range.clone()) {
+                    let free_intervals: Vec<_> = defrag_buf.free_intervals().collect();
+                    let collisions = occupied_parts(range.clone(), &free_intervals);
 
                     state.abnormalities.push(Abnormality {
                         when: Local::now(),
                         writer_guid: Some(writer_guid),
                         reader_guid: None,
                         topic_name: writer.topic_name().map(|t| t.to_string()),
-                        desc: format!("unable to insert fragment {range:?} into defrag buffer"),
+                        severity: Severity::Error,
+                        desc: format!(
+                            "unable to insert fragment {range:?} of sn {} into defrag buffer: \
+                             {err}; collides with {collisions:?}, free intervals {free_intervals:?}",
+                            writer_sn.0
+                        ),
                     });
 
                     // println!(
@@ -504,6 +1117,18 @@ impl Updater {
                             .push(msg.recv_time, (event.payload_size * 8) as f64);
                     }
 
+                    self.check_payload_size(
+                        &mut state.abnormalities,
+                        writer_guid,
+                        writer,
+                        event.data_size as usize,
+                    );
+
+                    let is_retx = is_retransmission(writer, event.writer_sn, event.destination);
+                    if is_retx {
+                        writer.retx_byte_count += event.payload_size;
+                    }
+
                     // Update the writer state
                     {
                         writer.frag_messages.remove(&event.writer_sn).unwrap();
@@ -523,67 +1148,185 @@ impl Updater {
                     if let Some(topic_name) = writer.topic_name() {
                         let topic = state.topics.get_mut(topic_name).unwrap();
 
-                        writer.total_msg_count += 1;
-                        writer.msg_rate_stat.push(msg.recv_time, 1.0);
+                        topic.total_msg_count += 1;
+                        topic.msg_rate_stat.push(msg.recv_time, 1.0);
 
                         topic.total_byte_count += event.payload_size;
                         topic
                             .bit_rate_stat
                             .push(msg.recv_time, (event.payload_size * 8) as f64);
+
+                        if is_retx {
+                            topic.retx_byte_count += event.payload_size;
+                        }
                     }
+
+                    // The readers no longer wait for the fragments.
+                    state.clear_nackfrag(writer_guid, event.writer_sn);
                 }
             }
         }
     }
 
-    fn handle_gap_event(&self, state: &mut State, _msg: &RtpsSubmsgEvent, _event: &GapEvent) {
-        state.stat.packet_count += 1;
+    /// Checks if the endpoint can be tracked on the participant. New
+    /// endpoints are refused once the participant reaches the
+    /// endpoint limit, which is reported once.
+    fn admit_endpoint(
+        &self,
+        participant: &mut ParticipantState,
+        abnormalities: &mut Vec<Abnormality>,
+        guid: GUID,
+    ) -> bool {
+        let entity_id = &guid.entity_id;
+        if participant.writers.contains_key(entity_id)
+            || participant.readers.contains_key(entity_id)
+        {
+            return true;
+        }
 
-        // let GapEvent {
-        //     writer_id,
-        //     gap_start,
-        //     ref gap_list,
-        //     ..
-        // } = *event;
+        let num_endpoints = participant.writers.len() + participant.readers.len();
+        if num_endpoints < self.max_endpoints {
+            return true;
+        }
 
-        // let participant = state.participants.entry(writer_id.prefix).or_default();
-        // let entity = participant.entities.entry(writer_id.entity_id).or_default();
+        if participant.dropped_endpoint_count == 0 {
+            abnormalities.push(Abnormality {
+                when: Local::now(),
+                writer_guid: None,
+                reader_guid: None,
+                topic_name: None,
+                severity: Severity::Error,
+                desc: format!(
+                    "participant {} exceeds the limit of {} endpoints",
+                    guid.prefix.display(),
+                    self.max_endpoints
+                ),
+            });
+        }
+        participant.dropped_endpoint_count += 1;
 
-        // let gaps: Vec<_> = chain!([gap_start], gap_list.iter())
-        //     .map(|sn| sn.0)
-        //     .collect();
-        // println!("{}\t{gaps:?}", writer_id.display());
+        false
+    }
 
-        // gap_list.iter();
-        // todo!();
+    /// Reports a sample whose payload size deviates from the running
+    /// mean of the writer, and then accumulates the size.
+    fn check_payload_size(
+        &self,
+        abnormalities: &mut Vec<Abnormality>,
+        writer_guid: GUID,
+        writer: &mut WriterState,
+        size: usize,
+    ) {
+        let stat = &writer.payload_size_stat;
+        let size = size as f64;
+
+        // Wait for enough samples before the statistics is trusted.
+        // The deviation is floored to a fraction of the mean so that
+        // writers with constant-sized samples are not reported for
+        // small variations.
+        if stat.count() >= MIN_PAYLOAD_SIZE_SAMPLES {
+            let mean = stat.mean();
+            let stdev = stat.stdev().max(mean * 0.1);
+            let sigma = (size - mean).abs() / stdev;
+
+            if sigma > self.payload_size_sigma {
+                if !writer.payload_size_reported {
+                    writer.payload_size_reported = true;
+                    abnormalities.push(Abnormality {
+                        when: Local::now(),
+                        writer_guid: Some(writer_guid),
+                        reader_guid: None,
+                        topic_name: writer.topic_name().map(|t| t.to_string()),
+                        severity: Severity::Warning,
+                        desc: format!(
+                            "payload size {size:.0} bytes deviates from the mean \
+                             {mean:.0} bytes by {sigma:.1} sigma"
+                        ),
+                    });
+                }
+            } else {
+                writer.payload_size_reported = false;
+            }
+        }
+
+        writer.payload_size_stat.push(size);
+    }
+
+    fn handle_gap_event(&self, state: &mut State, msg: &RtpsSubmsgEvent, event: &GapEvent) {
+        let rate_window = state.rate_window;
+        state.stat.packet_count += 1;
+        state.stat.gap_submsg_count += 1;
+
+        let GapEvent {
+            writer_guid,
+            gap_start,
+            ref gap_list,
+            ..
+        } = *event;
+
+        let participant = state
+            .participants
+            .entry(writer_guid.prefix)
+            .or_insert_with(|| ParticipantState::new(rate_window));
+        if !self.admit_endpoint(participant, &mut state.abnormalities, writer_guid) {
+            return;
+        }
+        let writer = participant
+            .writers
+            .entry(writer_guid.entity_id)
+            .or_insert_with(|| WriterState::new(rate_window));
+        writer.last_recv_time = Some(msg.recv_time);
+
+        // The sequence numbers in gap_start..gap_list.base and the
+        // ones in gap_list are irrelevant to the reader.
+        writer
+            .lost_sn
+            .insert_range(gap_start.0, gap_list.base().0 - 1);
+        for sn in gap_list.iter() {
+            writer.lost_sn.insert(sn.0);
+        }
     }
 
     fn handle_heartbeat_event(
         &self,
         state: &mut State,
-        _msg: &RtpsSubmsgEvent,
+        msg: &RtpsSubmsgEvent,
         event: &HeartbeatEvent,
     ) {
+        let rate_window = state.rate_window;
         state.stat.packet_count += 1;
         state.stat.heartbeat_submsg_count += 1;
 
         let participant = state
             .participants
             .entry(event.writer_guid.prefix)
-            .or_default();
+            .or_insert_with(|| ParticipantState::new(rate_window));
+        if !self.admit_endpoint(participant, &mut state.abnormalities, event.writer_guid) {
+            return;
+        }
         let writer = participant
             .writers
             .entry(event.writer_guid.entity_id)
-            .or_default();
+            .or_insert_with(|| WriterState::new(rate_window));
+        writer.last_recv_time = Some(msg.recv_time);
 
         if let Some(heartbeat) = &mut writer.heartbeat {
             if heartbeat.count < event.count {
+                // The available sequence numbers of a writer should
+                // never go backwards.
+                let mut regressions = vec![];
                 if heartbeat.first_sn > event.first_sn.0 {
-                    // TODO: warn
+                    regressions.push(format!(
+                        "first_sn regresses from {} to {}",
+                        heartbeat.first_sn, event.first_sn.0
+                    ));
                 }
 
                 if heartbeat.last_sn > event.last_sn.0 {
-                    // TODO: warn
+                    regressions.push(format!(
+                        "last_sn regresses from {} to {}",
+                        heartbeat.last_sn, event.last_sn.0
+                    ));
                 }
 
                 *heartbeat = HeartbeatState {
@@ -592,11 +1335,22 @@ impl Updater {
                     count: event.count,
                     since: Instant::now(),
                 };
+
+                if !regressions.is_empty() {
+                    state.abnormalities.push(Abnormality {
+                        when: Local::now(),
+                        writer_guid: Some(event.writer_guid),
+                        reader_guid: None,
+                        topic_name: writer.topic_name().map(|name| name.to_string()),
+                        severity: Severity::Warning,
+                        desc: format!("heartbeat {}", regressions.join(", ")),
+                    });
+                }
             }
         } else {
             writer.heartbeat = Some(HeartbeatState {
                 first_sn: event.first_sn.0,
-                last_sn: event.first_sn.0,
+                last_sn: event.last_sn.0,
                 count: event.count,
                 since: Instant::now(),
             });
@@ -604,6 +1358,7 @@ impl Updater {
     }
 
     fn handle_acknack_event(&self, state: &mut State, msg: &RtpsSubmsgEvent, event: &AckNackEvent) {
+        let rate_window = state.rate_window;
         // Update statistics
         state.stat.packet_count += 1;
         state.stat.acknack_submsg_count += 1;
@@ -612,11 +1367,15 @@ impl Updater {
         let participant = state
             .participants
             .entry(event.reader_guid.prefix)
-            .or_default();
+            .or_insert_with(|| ParticipantState::new(rate_window));
+        if !self.admit_endpoint(participant, &mut state.abnormalities, event.reader_guid) {
+            return;
+        }
         let reader = participant
             .readers
             .entry(event.reader_guid.entity_id)
-            .or_default();
+            .or_insert_with(|| ReaderState::new(rate_window));
+        reader.last_recv_time = Some(msg.recv_time);
 
         // Update participant state.
         {
@@ -630,24 +1389,43 @@ impl Updater {
             reader.acknack_rate_stat.push(msg.recv_time, 1f64);
         }
 
-        // Save missing sequence numbers
+        // Save missing sequence numbers. The count and the missing
+        // samples are tracked separately for each writer.
         {
-            if let Some(acknack) = &reader.acknack {
-                if acknack.count >= event.count {
+            let prev = reader.acknack.get(&event.writer_guid);
+            if let Some(prev) = prev {
+                if prev.count >= event.count {
                     return;
                 }
             }
 
-            reader.acknack = Some(AckNackState {
-                missing_sn: event.missing_sn.to_vec(),
-                count: event.count,
-                since: Instant::now(),
-            });
+            // Keep the time of the oldest missing sn if the previous
+            // ACKNACK to the writer already requested it.
+            let oldest_sn = event.missing_sn.iter().min();
+            let missing_since = match (prev, oldest_sn) {
+                (_, None) => None,
+                (Some(prev), Some(sn)) if prev.missing_sn.contains(sn) => prev.missing_since,
+                (_, Some(_)) => Some(msg.recv_time),
+            };
+
+            reader.acknack.insert(
+                event.writer_guid,
+                AckNackState {
+                    missing_sn: event.missing_sn.to_vec(),
+                    count: event.count,
+                    since: Instant::now(),
+                    missing_since,
+                },
+            );
         }
 
         // Update last sn
         reader.last_sn = Some(event.base_sn);
 
+        // The base sn is the first sn not yet received, thus all
+        // preceding samples are acknowledged.
+        let prev_acked_sn = reader.acked_sn.insert(event.writer_guid, event.base_sn - 1);
+
         // Update the stat on associated topic.
         if let Some(topic_name) = reader.topic_name() {
             let topic = state.topics.get_mut(topic_name).unwrap();
@@ -655,16 +1433,137 @@ impl Updater {
             topic.total_acknack_count += 1;
             topic.acknack_rate_stat.push(msg.recv_time, 1f64);
         }
+
+        record_ack_latency(state, msg, event, prev_acked_sn);
+        self.check_unrecoverable_samples(state, event);
+    }
+
+    /// Reports the readers whose oldest missing sample stays missing
+    /// beyond the threshold, which means the reader is stuck or
+    /// falling behind.
+    fn check_stuck_readers(&self, state: &mut State) {
+        for (&guid_prefix, participant) in &mut state.participants {
+            for (&entity_id, reader) in &mut participant.readers {
+                let age_secs = reader
+                    .missing_age
+                    .map(|age| age.num_milliseconds() as f64 / 1000.0)
+                    .filter(|&age_secs| age_secs > self.max_missing_age);
+                let Some(age_secs) = age_secs else {
+                    reader.stuck_reported = false;
+                    continue;
+                };
+
+                if !reader.stuck_reported {
+                    reader.stuck_reported = true;
+                    let longest_missing = reader.longest_missing();
+                    let writer_guid = longest_missing.map(|(writer_guid, _)| writer_guid);
+                    let oldest_sn = longest_missing
+                        .and_then(|(_, acknack)| acknack.missing_sn.iter().min().copied());
+                    state.abnormalities.push(Abnormality {
+                        when: Local::now(),
+                        writer_guid,
+                        reader_guid: Some(GUID::new(guid_prefix, entity_id)),
+                        topic_name: reader.topic_name().map(|name| name.to_string()),
+                        severity: Severity::Warning,
+                        desc: format!(
+                            "reader keeps requesting sample {} for {age_secs:.1}s",
+                            oldest_sn.unwrap_or_default()
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Reports once when a reader requests samples that the writer
+    /// has declared lost by GAP, and re-arms after the requests stop.
+    fn check_unrecoverable_samples(&self, state: &mut State, event: &AckNackEvent) {
+        let Some(writer) = state
+            .participants
+            .get(&event.writer_guid.prefix)
+            .and_then(|part| part.writers.get(&event.writer_guid.entity_id))
+        else {
+            return;
+        };
+
+        let mut unrecoverable = SnRanges::new();
+        for &sn in &event.missing_sn {
+            if writer.lost_sn.contains(sn) {
+                unrecoverable.insert(sn);
+            }
+        }
+        let topic_name = writer.topic_name().map(|name| name.to_string());
+
+        let Some(reader) = state
+            .participants
+            .get_mut(&event.reader_guid.prefix)
+            .and_then(|part| part.readers.get_mut(&event.reader_guid.entity_id))
+        else {
+            return;
+        };
+
+        if unrecoverable.is_empty() {
+            reader.unrecoverable_reported = false;
+            return;
+        }
+
+        if !reader.unrecoverable_reported {
+            reader.unrecoverable_reported = true;
+            state.abnormalities.push(Abnormality {
+                when: Local::now(),
+                writer_guid: Some(event.writer_guid),
+                reader_guid: Some(event.reader_guid),
+                topic_name,
+                severity: Severity::Warning,
+                desc: format!(
+                    "reader requests samples {unrecoverable} that were declared lost by GAP"
+                ),
+            });
+        }
     }
 
     fn handle_nackfrag_event(
         &self,
         state: &mut State,
-        _msg: &RtpsSubmsgEvent,
-        _event: &NackFragEvent,
+        msg: &RtpsSubmsgEvent,
+        event: &NackFragEvent,
     ) {
+        let rate_window = state.rate_window;
         state.stat.packet_count += 1;
         state.stat.ackfrag_submsg_count += 1;
+
+        let participant = state
+            .participants
+            .entry(event.reader_guid.prefix)
+            .or_insert_with(|| ParticipantState::new(rate_window));
+        if !self.admit_endpoint(participant, &mut state.abnormalities, event.reader_guid) {
+            return;
+        }
+        let reader = participant
+            .readers
+            .entry(event.reader_guid.entity_id)
+            .or_insert_with(|| ReaderState::new(rate_window));
+        reader.last_recv_time = Some(msg.recv_time);
+
+        // Skip the repeated or out-of-order NACK_FRAGs.
+        if let Some(nackfrag) = reader.nackfrag.get(&event.writer_guid) {
+            if nackfrag.count >= event.count {
+                return;
+            }
+        }
+
+        let mut missing_frags = SnRanges::new();
+        for &num in &event.fragment_numbers {
+            missing_frags.insert(num as i64);
+        }
+        reader.nackfrag.insert(
+            event.writer_guid,
+            NackFragState {
+                writer_sn: event.writer_sn.0,
+                missing_frags,
+                count: event.count,
+            },
+        );
     }
 
     fn handle_heartbeatfrag_event(
@@ -678,25 +1577,810 @@ impl Updater {
     }
 
     fn handle_participant_info(&self, state: &mut State, info: &ParticipantInfo) {
+        let rate_window = state.rate_window;
         let ParticipantInfo {
+            recv_time,
             guid_prefix,
             ref unicast_locator_list,
             ref multicast_locator_list,
-            ..
+            source_addr,
         } = *info;
 
-        let participant = state.participants.entry(guid_prefix).or_default();
-        participant.unicast_locator_list = Some(unicast_locator_list.clone());
-        participant.multicast_locator_list = multicast_locator_list.clone();
+        // The same participant is seen on both multicast and unicast
+        // with partial locator lists. Accumulate the locators instead
+        // of overwriting them.
+        let participant = state
+            .participants
+            .entry(guid_prefix)
+            .or_insert_with(|| ParticipantState::new(rate_window));
+        participant.last_recv_time = Some(recv_time);
+        merge_locators(&mut participant.unicast_locator_list, unicast_locator_list);
+        if let Some(multicast_locator_list) = multicast_locator_list {
+            merge_locators(
+                &mut participant.multicast_locator_list,
+                multicast_locator_list,
+            );
+        }
+
+        // Check if the advertised unicast locators agree with the
+        // packet source. Only UDP locators of the same IP version as
+        // the source are compared.
+        let Some(source_addr) = source_addr else {
+            return;
+        };
+        let advertised_addrs: Vec<_> = unicast_locator_list
+            .iter()
+            .filter_map(|locator| match locator {
+                Locator::UdpV4(addr) => Some(IpAddr::from(*addr.ip())),
+                Locator::UdpV6(addr) => Some(IpAddr::from(*addr.ip())),
+                _ => None,
+            })
+            .filter(|addr| addr.is_ipv4() == source_addr.is_ipv4())
+            .collect();
+
+        if advertised_addrs.is_empty() || advertised_addrs.contains(&source_addr) {
+            return;
+        }
+
+        if !participant.locator_mismatch_reported {
+            participant.locator_mismatch_reported = true;
+
+            let advertised: Vec<_> = unicast_locator_list
+                .iter()
+                .map(|locator| locator.display().to_string())
+                .collect();
+            state.abnormalities.push(Abnormality {
+                when: Local::now(),
+                writer_guid: None,
+                reader_guid: None,
+                topic_name: None,
+                severity: Severity::Warning,
+                desc: format!(
+                    "participant {} sends from {source_addr} but advertises [{}]",
+                    guid_prefix.display(),
+                    advertised.join(", ")
+                ),
+            });
+        }
+    }
+
+    /// Reports the RTPS messages that failed to parse since the last
+    /// report, at most once per [PARSE_ERROR_REPORT_INTERVAL].
+    fn report_parse_errors(&mut self, state: &mut State) {
+        let count = self.capture_stats.parse_errors();
+        let capture_stats = &self.capture_stats;
+
+        self.parse_error_reporter.report(state, count, |increase| {
+            let mut desc = format!("{increase} RTPS packets failed to parse");
+            if let Some(source) = capture_stats.last_parse_error() {
+                let [major, minor] = source.vendor_id;
+                write!(
+                    desc,
+                    ", last from vendor {major:02x}.{minor:02x} with GUID prefix {}",
+                    source.guid_prefix.display()
+                )
+                .unwrap();
+            }
+            desc
+        });
+    }
+
+    /// Reports the events dropped on congestion since the last
+    /// report, at most once per [DROPPED_EVENT_REPORT_INTERVAL].
+    fn report_dropped_events(&mut self, state: &mut State) {
+        let count = self.capture_stats.dropped_events();
+        self.dropped_event_reporter
+            .report(state, count, |increase| {
+                format!(
+                    "{increase} events dropped because the updater cannot keep up with the traffic"
+                )
+            });
     }
 
     fn toggle_logging(&mut self) -> Result<()> {
         if let Some(logger) = self.logger.take() {
             logger.close()?;
         } else {
-            self.logger = Some(Logger::new()?);
+            match Logger::new(&self.log_dir, self.log_overwrite) {
+                Ok(logger) => self.logger = Some(logger),
+                Err(err) => error!("unable to start logging: {err}"),
+            }
         }
 
         Ok(())
     }
 }
+
+/// Drops the oldest abnormalities beyond `max_len`. They are pushed in
+/// time order, so the oldest ones come first.
+fn trim_abnormalities(state: &mut State, max_len: usize) {
+    let Some(n_dropped) = state.abnormalities.len().checked_sub(max_len) else {
+        return;
+    };
+
+    for report in state.abnormalities.drain(..n_dropped) {
+        state.dropped_max_severity = state.dropped_max_severity.max(Some(report.severity));
+    }
+    state.dropped_abnormality_count += n_dropped;
+}
+
+/// Records when the sample is first sent for the acknowledgment
+/// latency. Only the latest [SENT_SN_HISTORY_LEN] samples are kept.
+fn record_sent_time(writer: &mut WriterState, sn: SequenceNumber, recv_time: chrono::Duration) {
+    writer.sent_times.entry(sn.0).or_insert(recv_time);
+    while writer.sent_times.len() > SENT_SN_HISTORY_LEN {
+        writer.sent_times.pop_first();
+    }
+}
+
+/// Records the key hash seen on the writer. The least recently seen
+/// key hash is evicted when more than [KEY_HASH_HISTORY_LEN] are
+/// kept, which bounds the memory on high-cardinality keyed topics.
+fn record_key_hash(writer: &mut WriterState, key_hash: [u8; 16], recv_time: chrono::Duration) {
+    writer.key_hashes.insert(key_hash, recv_time);
+
+    if writer.key_hashes.len() > KEY_HASH_HISTORY_LEN {
+        let oldest = writer
+            .key_hashes
+            .iter()
+            .min_by_key(|(_, &seen)| seen)
+            .map(|(&key_hash, _)| key_hash);
+        if let Some(oldest) = oldest {
+            writer.key_hashes.remove(&oldest);
+            writer.evicted_key_hash_count += 1;
+        }
+    }
+}
+
+/// Gets the parts of the fragment range that are not covered by the
+/// free intervals, i.e. where the range collides with fragments
+/// already received.
+fn occupied_parts(range: Range<usize>, free_intervals: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut occupied = vec![];
+    let mut cursor = range.start;
+
+    for free in free_intervals {
+        if free.end <= cursor {
+            continue;
+        }
+        if free.start >= range.end {
+            break;
+        }
+        if free.start > cursor {
+            occupied.push(cursor..free.start);
+        }
+        cursor = free.end;
+    }
+    if cursor < range.end {
+        occupied.push(cursor..range.end);
+    }
+
+    occupied
+}
+
+/// Locks the state. If another thread, e.g. the TUI, panicked while
+/// holding the lock, the state is taken over as is so that the
+/// capture keeps being analyzed.
+fn lock_state<'a>(state: &'a Mutex<State>, poison_reported: &mut bool) -> MutexGuard<'a, State> {
+    state.lock().unwrap_or_else(|err| {
+        if !*poison_reported {
+            *poison_reported = true;
+            error!(
+                "the state lock is poisoned by a panicked thread, continuing with the state as is"
+            );
+        }
+        err.into_inner()
+    })
+}
+
+/// Checks if the sequence number repeats one sent to the same
+/// destination, and otherwise advances the highest sequence number of
+/// the destination. Each unicast reader gets its own copy of a sample,
+/// and best-effort writers never retransmit.
+fn is_retransmission(writer: &mut WriterState, sn: SequenceNumber, destination: Locator) -> bool {
+    if writer.is_best_effort() {
+        return false;
+    }
+
+    match writer.highest_sn.entry(destination) {
+        Entry::Occupied(entry) if sn <= *entry.get() => true,
+        Entry::Occupied(mut entry) => {
+            entry.insert(sn);
+            false
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(sn);
+            false
+        }
+    }
+}
+
+/// Adds the locators to the list if they are not present yet.
+fn merge_locators(list: &mut Option<Vec<Locator>>, locators: &[Locator]) {
+    let list = list.get_or_insert_with(Vec::new);
+    for locator in locators {
+        if !list.contains(locator) {
+            list.push(*locator);
+        }
+    }
+}
+
+/// Measures the time from sending each newly acknowledged sample to
+/// the ACKNACK.
+fn record_ack_latency(
+    state: &mut State,
+    msg: &RtpsSubmsgEvent,
+    event: &AckNackEvent,
+    prev_acked_sn: Option<i64>,
+) {
+    let Some(writer) = state
+        .participants
+        .get_mut(&event.writer_guid.prefix)
+        .and_then(|part| part.writers.get_mut(&event.writer_guid.entity_id))
+    else {
+        return;
+    };
+
+    // Without a previous ACKNACK, only the last acknowledged sample
+    // is known to be acknowledged just now.
+    let first_sn = match prev_acked_sn {
+        Some(sn) => sn + 1,
+        None => event.base_sn - 1,
+    };
+    if first_sn >= event.base_sn {
+        return;
+    }
+
+    let latencies: Vec<f64> = writer
+        .sent_times
+        .range(first_sn..event.base_sn)
+        .filter_map(|(_, &sent_time)| {
+            let latency = (msg.recv_time - sent_time).to_std().ok()?;
+            Some(latency.as_secs_f64())
+        })
+        .collect();
+    for latency in latencies {
+        writer.ack_latency_stat.push(msg.recv_time, latency);
+    }
+}
+
+/// Records the type name advertised by an endpoint on the topic, and
+/// reports the first time it conflicts with the topic type name.
+fn check_type_name(
+    state: &mut State,
+    topic_name: &str,
+    type_name: &str,
+    writer_guid: Option<GUID>,
+    reader_guid: Option<GUID>,
+) {
+    let Some(topic) = state.topics.get_mut(topic_name) else {
+        return;
+    };
+    if topic.advertised_type_name.is_none() {
+        topic.advertised_type_name = Some(type_name.to_string());
+    }
+
+    let Some(topic_type_name) = topic.type_name() else {
+        return;
+    };
+    if topic_type_name == type_name || topic.type_conflict_reported {
+        return;
+    }
+
+    let desc = format!("type name '{type_name}' conflicts with '{topic_type_name}' on the topic");
+    topic.type_conflict_reported = true;
+    state.abnormalities.push(Abnormality {
+        when: Local::now(),
+        writer_guid,
+        reader_guid,
+        topic_name: Some(topic_name.to_string()),
+        severity: Severity::Error,
+        desc,
+    });
+}
+
+/// Reports the writer-reader pairs on the topic whose QoS are
+/// incompatible. Each pair is reported once until its QoS becomes
+/// compatible again.
+fn check_qos_compatibility(state: &mut State, topic_name: &str) {
+    let State {
+        participants,
+        topics,
+        abnormalities,
+        ..
+    } = state;
+    let Some(topic) = topics.get_mut(topic_name) else {
+        return;
+    };
+
+    for &writer_guid in &topic.writers {
+        let Some(writer_data) = participants
+            .get(&writer_guid.prefix)
+            .and_then(|part| part.writers.get(&writer_guid.entity_id))
+            .and_then(|writer| writer.data.as_ref())
+        else {
+            continue;
+        };
+        let offered = writer_data.publication_topic_data.qos();
+
+        for &reader_guid in &topic.readers {
+            let Some(reader_data) = participants
+                .get(&reader_guid.prefix)
+                .and_then(|part| part.readers.get(&reader_guid.entity_id))
+                .and_then(|reader| reader.data.as_ref())
+            else {
+                continue;
+            };
+            let requested = reader_data.subscription_topic_data.qos();
+
+            let mismatches = qos_mismatches(&offered, &requested);
+            let pair = (writer_guid, reader_guid);
+
+            if mismatches.is_empty() {
+                topic.qos_mismatch_reported.remove(&pair);
+            } else if topic.qos_mismatch_reported.insert(pair) {
+                abnormalities.push(Abnormality {
+                    when: Local::now(),
+                    writer_guid: Some(writer_guid),
+                    reader_guid: Some(reader_guid),
+                    topic_name: Some(topic_name.to_string()),
+                    severity: Severity::Error,
+                    desc: format!("incompatible QoS: {}", mismatches.join(", ")),
+                });
+            }
+        }
+    }
+}
+
+/// Compares the QoS offered by a writer against the QoS requested by
+/// a reader according to the DDS request-offered rules. Absent
+/// policies take the default values in the DDS specification.
+pub(crate) fn qos_mismatches(offered: &QosPolicies, requested: &QosPolicies) -> Vec<String> {
+    let mut mismatches = vec![];
+
+    // Writers are reliable and readers are best-effort by default.
+    let offered_reliable = !matches!(offered.reliability(), Some(Reliability::BestEffort));
+    let requested_reliable = matches!(requested.reliability(), Some(Reliability::Reliable { .. }));
+    if requested_reliable && !offered_reliable {
+        mismatches.push("BEST_EFFORT writer with RELIABLE reader".to_string());
+    }
+
+    let offered_durability = offered.durability().unwrap_or(Durability::Volatile);
+    let requested_durability = requested.durability().unwrap_or(Durability::Volatile);
+    if offered_durability < requested_durability {
+        mismatches.push(format!(
+            "{offered_durability:?} writer with {requested_durability:?} reader"
+        ));
+    }
+
+    // An absent deadline is infinite.
+    match (offered.deadline(), requested.deadline()) {
+        (None, Some(requested)) => {
+            mismatches.push(format!(
+                "writer without deadline with reader deadline {:?}",
+                requested.0
+            ));
+        }
+        (Some(offered), Some(requested)) if offered.0 > requested.0 => {
+            mismatches.push(format!(
+                "writer deadline {:?} longer than reader deadline {:?}",
+                offered.0, requested.0
+            ));
+        }
+        _ => {}
+    }
+
+    mismatches
+}
+
+/// Gets the human-friendly entity name carried in vendor-specific
+/// discovery parameters.
+fn vendor_entity_name(event: &DataEvent) -> Option<String> {
+    let name = event.vendor_params.as_ref()?.display_name()?;
+    Some(name.to_string())
+}
+
+/// Reports the increase of an ever-growing counter in abnormalities,
+/// at most once per interval.
+struct CounterReporter {
+    interval: Duration,
+    /// The counter value in the last report.
+    reported: usize,
+    reported_at: Option<Instant>,
+}
+
+impl CounterReporter {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            reported: 0,
+            reported_at: None,
+        }
+    }
+
+    /// Adds a warning described by `desc` with the increase since the
+    /// last report, unless the counter is unchanged or the interval is
+    /// not over yet.
+    fn report<F>(&mut self, state: &mut State, count: usize, desc: F)
+    where
+        F: FnOnce(usize) -> String,
+    {
+        if count == self.reported {
+            return;
+        }
+        if let Some(since) = self.reported_at {
+            if since.elapsed() < self.interval {
+                return;
+            }
+        }
+
+        state.abnormalities.push(Abnormality {
+            when: Local::now(),
+            writer_guid: None,
+            reader_guid: None,
+            topic_name: None,
+            severity: Severity::Warning,
+            desc: desc(count - self.reported),
+        });
+        self.reported = count;
+        self.reported_at = Some(Instant::now());
+    }
+}
+
+/// The bandwidth budgets in bits per second.
+struct BandwidthBudget {
+    topics: HashMap<String, f64>,
+    default: Option<f64>,
+    total: Option<f64>,
+    total_reported: bool,
+}
+
+impl BandwidthBudget {
+    fn new(opts: &Opts) -> Self {
+        let mut topics: HashMap<_, _> = opts.topic_budget.iter().cloned().collect();
+        let default = topics.remove("*");
+
+        Self {
+            topics,
+            default,
+            total: opts.total_budget,
+            total_reported: false,
+        }
+    }
+
+    fn topic_budget(&self, topic_name: &str) -> Option<f64> {
+        self.topics.get(topic_name).copied().or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use rustdds::{
+        discovery::sedp_messages::{
+            DiscoveredWriterData, PublicationBuiltinTopicData, WriterProxy,
+        },
+        QosPolicyBuilder, Timestamp,
+    };
+
+    const TOPIC_NAME: &str = "rt/chatter";
+
+    fn destination(port: u16) -> Locator {
+        Locator::UdpV4(std::net::SocketAddrV4::new([10, 0, 0, 2].into(), port))
+    }
+
+    fn new_updater() -> Updater {
+        let opts = Opts::try_parse_from(["ddshark"]).unwrap();
+        let state = Arc::new(Mutex::new(State::new(opts.rate_window())));
+        let (_tx, rx) = flume::unbounded();
+        Updater::new(
+            rx,
+            CancellationToken::new(),
+            state,
+            ReplayControl::new(),
+            CaptureStats::new(),
+            &opts,
+        )
+        .unwrap()
+    }
+
+    fn new_state() -> State {
+        State::new(chrono::Duration::seconds(1))
+    }
+
+    fn writer_guid(prefix: u8) -> GUID {
+        GUID::new(
+            GuidPrefix::new(&[prefix; 12]),
+            EntityId::new([0, 0, 1], EntityKind::WRITER_NO_KEY_USER_DEFINED),
+        )
+    }
+
+    /// Adds a discovered writer on [TOPIC_NAME] to the state.
+    fn add_writer(state: &mut State, guid: GUID) {
+        let rate_window = state.rate_window;
+        let participant_guid = GUID::new(guid.prefix, EntityId::PARTICIPANT);
+        let data = DiscoveredWriterData {
+            last_updated: Instant::now(),
+            writer_proxy: WriterProxy::new(guid, vec![], vec![]),
+            publication_topic_data: PublicationBuiltinTopicData::new(
+                guid,
+                participant_guid,
+                TOPIC_NAME,
+                "std_msgs::msg::dds_::String_",
+                &QosPolicies::qos_none(),
+            ),
+        };
+
+        let mut writer = WriterState::new(rate_window);
+        writer.data = Some(data);
+        state
+            .participants
+            .entry(guid.prefix)
+            .or_insert_with(|| ParticipantState::new(rate_window))
+            .writers
+            .insert(guid.entity_id, writer);
+        state
+            .topics
+            .entry(TOPIC_NAME.to_string())
+            .or_insert_with(|| TopicState::new(rate_window))
+            .writers
+            .insert(guid);
+    }
+
+    fn submsg(recv_time: chrono::Duration, kind: RtpsSubmsgEventKind) -> RtpsSubmsgEvent {
+        RtpsSubmsgEvent {
+            recv_time,
+            rtps_time: Timestamp::INVALID,
+            kind,
+        }
+    }
+
+    #[test]
+    fn fragmented_message_is_counted_once_on_writer_and_topic() {
+        let updater = new_updater();
+        let mut state = new_state();
+        let guid = writer_guid(1);
+        add_writer(&mut state, guid);
+
+        // A 300-byte sample in 100-byte fragments, sent as fragments
+        // 1-2 and then fragment 3.
+        let fragments = [(1, 2, 200), (3, 1, 100)];
+        for (i, (fragment_starting_num, fragments_in_submessage, payload_size)) in
+            fragments.into_iter().enumerate()
+        {
+            let event = DataFragEvent {
+                writer_guid: guid,
+                writer_sn: SequenceNumber::from(1),
+                fragment_starting_num,
+                fragments_in_submessage,
+                data_size: 300,
+                fragment_size: 100,
+                payload_size,
+                payload_hash: i as u64,
+                destination: destination(7410),
+            };
+            let recv_time = chrono::Duration::milliseconds(i as i64);
+            let msg = submsg(
+                recv_time,
+                RtpsSubmsgEventKind::DataFrag(Box::new(event.clone())),
+            );
+            updater.handle_data_frag_event(&mut state, &msg, &event);
+        }
+
+        let writer = &state.participants[&guid.prefix].writers[&guid.entity_id];
+        let topic = &state.topics[TOPIC_NAME];
+        assert!(writer.frag_messages.is_empty());
+        assert_eq!(writer.total_msg_count, 1);
+        assert_eq!(topic.total_msg_count, writer.total_msg_count);
+
+        // The sample is sent when its first fragment arrives.
+        assert_eq!(writer.sent_times.get(&1), Some(&chrono::Duration::zero()));
+    }
+
+    fn reader_guid(prefix: u8) -> GUID {
+        GUID::new(
+            GuidPrefix::new(&[prefix; 12]),
+            EntityId::new([0, 0, 2], EntityKind::READER_NO_KEY_USER_DEFINED),
+        )
+    }
+
+    /// Builds an ACKNACK that arrives without INFO_DST, so the writer
+    /// GUID prefix is unknown.
+    fn acknack_without_info_dst(reader_guid: GUID) -> UpdateEvent {
+        let event = AckNackEvent {
+            writer_guid: GUID::new(GuidPrefix::UNKNOWN, writer_guid(1).entity_id),
+            reader_guid,
+            count: 1,
+            base_sn: 1,
+            missing_sn: vec![],
+        };
+        submsg(
+            chrono::Duration::zero(),
+            RtpsSubmsgEventKind::AckNack(event),
+        )
+        .into()
+    }
+
+    #[test]
+    fn acknack_without_info_dst_resolves_unique_writer() {
+        let mut updater = new_updater();
+        let mut state = new_state();
+        let writer = writer_guid(1);
+        add_writer(&mut state, writer);
+
+        let reader = reader_guid(9);
+        updater
+            .handle_message(&mut state, &acknack_without_info_dst(reader))
+            .unwrap();
+
+        let reader_state = &state.participants[&reader.prefix].readers[&reader.entity_id];
+        assert!(reader_state.acked_sn.contains_key(&writer));
+        assert!(state.abnormalities.is_empty());
+    }
+
+    #[test]
+    fn acknack_without_info_dst_ignores_unknown_writer() {
+        let mut updater = new_updater();
+        let mut state = new_state();
+
+        let reader = reader_guid(9);
+        updater
+            .handle_message(&mut state, &acknack_without_info_dst(reader))
+            .unwrap();
+
+        assert!(!state.participants.contains_key(&reader.prefix));
+        assert!(state.abnormalities.is_empty());
+    }
+
+    #[test]
+    fn acknack_without_info_dst_reports_ambiguous_writer_once() {
+        let mut updater = new_updater();
+        let mut state = new_state();
+        add_writer(&mut state, writer_guid(1));
+        add_writer(&mut state, writer_guid(2));
+
+        // Repeated ACKNACKs of the same reader are reported once.
+        let reader = reader_guid(9);
+        for _ in 0..3 {
+            updater
+                .handle_message(&mut state, &acknack_without_info_dst(reader))
+                .unwrap();
+        }
+        assert_eq!(state.abnormalities.len(), 1);
+        assert_eq!(state.abnormalities[0].reader_guid, Some(reader));
+        assert!(!state.participants.contains_key(&reader.prefix));
+
+        // Another reader gets its own report.
+        let other_reader = reader_guid(10);
+        updater
+            .handle_message(&mut state, &acknack_without_info_dst(other_reader))
+            .unwrap();
+        assert_eq!(state.abnormalities.len(), 2);
+    }
+
+    #[test]
+    fn missing_samples_are_tracked_per_writer() {
+        let updater = new_updater();
+        let mut state = new_state();
+        let writer_1 = writer_guid(1);
+        let writer_2 = writer_guid(2);
+        add_writer(&mut state, writer_1);
+        add_writer(&mut state, writer_2);
+        let reader = reader_guid(9);
+
+        // The reader misses sample 5 of the first writer, while it is
+        // up to date with the second one.
+        let acknacks = [
+            (0, writer_1, 1, vec![5]),
+            (1, writer_2, 1, vec![]),
+            (2, writer_1, 2, vec![5, 6]),
+            (3, writer_2, 2, vec![]),
+        ];
+        for (secs, writer_guid, count, missing_sn) in acknacks {
+            let event = AckNackEvent {
+                writer_guid,
+                reader_guid: reader,
+                count,
+                base_sn: 5,
+                missing_sn,
+            };
+            let msg = submsg(
+                chrono::Duration::seconds(secs),
+                RtpsSubmsgEventKind::AckNack(event.clone()),
+            );
+            updater.handle_acknack_event(&mut state, &msg, &event);
+        }
+
+        let reader_state = &state.participants[&reader.prefix].readers[&reader.entity_id];
+        assert_eq!(reader_state.acknack.len(), 2);
+        assert_eq!(reader_state.acknack[&writer_2].missing_since, None);
+
+        // The ACKNACKs to the second writer neither reset nor restart
+        // the missing time of the first writer.
+        let (writer_guid, acknack) = reader_state.longest_missing().unwrap();
+        assert_eq!(writer_guid, writer_1);
+        assert_eq!(acknack.missing_since, Some(chrono::Duration::zero()));
+    }
+
+    #[test]
+    fn unicast_copies_are_not_retransmissions() {
+        let updater = new_updater();
+        let mut state = new_state();
+        let guid = writer_guid(1);
+        add_writer(&mut state, guid);
+
+        // Sample 1 is sent to two unicast readers, and then resent to
+        // the first one.
+        let sends = [(0, 7410), (1, 7412), (2, 7410)];
+        for (millis, port) in sends {
+            let event = DataEvent {
+                writer_guid: guid,
+                writer_sn: SequenceNumber::from(1),
+                payload_size: 100,
+                destination: destination(port),
+                payload: None,
+                vendor_params: None,
+                inline_qos: None,
+            };
+            let msg = submsg(
+                chrono::Duration::milliseconds(millis),
+                RtpsSubmsgEventKind::Data(Box::new(event.clone())),
+            );
+            updater.handle_data_event(&mut state, &msg, &event);
+        }
+
+        let writer = &state.participants[&guid.prefix].writers[&guid.entity_id];
+        assert_eq!(writer.retx_byte_count, 100);
+        assert_eq!(state.topics[TOPIC_NAME].retx_byte_count, 100);
+    }
+
+    fn reliable() -> Reliability {
+        Reliability::Reliable {
+            max_blocking_time: rustdds::Duration::from_millis(100),
+        }
+    }
+
+    #[test]
+    fn qos_mismatches_reports_reliability() {
+        let offered = QosPolicyBuilder::new()
+            .reliability(Reliability::BestEffort)
+            .build();
+        let requested = QosPolicyBuilder::new().reliability(reliable()).build();
+
+        assert_eq!(
+            qos_mismatches(&offered, &requested),
+            ["BEST_EFFORT writer with RELIABLE reader"]
+        );
+    }
+
+    #[test]
+    fn qos_mismatches_reports_durability() {
+        let offered = QosPolicyBuilder::new()
+            .durability(Durability::Volatile)
+            .build();
+        let requested = QosPolicyBuilder::new()
+            .durability(Durability::TransientLocal)
+            .build();
+
+        assert_eq!(
+            qos_mismatches(&offered, &requested),
+            ["Volatile writer with TransientLocal reader"]
+        );
+    }
+
+    #[test]
+    fn qos_mismatches_accepts_compatible_qos() {
+        let offered = QosPolicyBuilder::new()
+            .reliability(reliable())
+            .durability(Durability::TransientLocal)
+            .build();
+        let requested = QosPolicyBuilder::new()
+            .reliability(Reliability::BestEffort)
+            .durability(Durability::Volatile)
+            .build();
+
+        assert!(qos_mismatches(&offered, &requested).is_empty());
+        assert!(qos_mismatches(&QosPolicies::qos_none(), &QosPolicies::qos_none()).is_empty());
+    }
+}
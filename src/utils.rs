@@ -5,6 +5,9 @@ mod entity_kind;
 mod guid;
 mod guid_prefix;
 mod locator;
+mod rate_history;
+mod running_stat;
+mod sn_ranges;
 mod timed_stat;
 mod vec;
 
@@ -13,6 +16,9 @@ pub use entity_kind::*;
 pub use guid::*;
 pub use guid_prefix::*;
 pub use locator::*;
+pub use rate_history::*;
+pub use running_stat::*;
+pub use sn_ranges::*;
 pub use timed_stat::*;
 pub use vec::*;
 
@@ -0,0 +1,30 @@
+use std::collections::VecDeque;
+
+/// Keeps the most recent rate samples up to a fixed capacity.
+#[derive(Debug, Clone)]
+pub struct RateHistory {
+    samples: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl RateHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Appends a sample, dropping the oldest one when full.
+    pub fn push(&mut self, sample: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample.max(0.0) as u64);
+    }
+
+    /// Gets the samples from the oldest to the newest.
+    pub fn samples(&self) -> Vec<u64> {
+        self.samples.iter().copied().collect()
+    }
+}
@@ -0,0 +1,36 @@
+/// Computes the running mean and variance of all pushed values using
+/// Welford's algorithm.
+#[derive(Debug, Clone, Default)]
+pub struct RunningStat {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn stdev(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64).sqrt()
+    }
+}
@@ -0,0 +1,134 @@
+use std::{collections::BTreeMap, fmt};
+
+/// A set of sequence numbers stored as coalesced inclusive ranges.
+#[derive(Debug, Clone, Default)]
+pub struct SnRanges {
+    /// Maps the start of each range to its inclusive end.
+    ranges: BTreeMap<i64, i64>,
+}
+
+impl SnRanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, sn: i64) {
+        self.insert_range(sn, sn);
+    }
+
+    /// Inserts the inclusive range `start..=end`, merging it with
+    /// overlapping or adjacent ranges.
+    pub fn insert_range(&mut self, mut start: i64, mut end: i64) {
+        if start > end {
+            return;
+        }
+
+        // Merge with the range that begins before `start`.
+        if let Some((&prev_start, &prev_end)) = self.ranges.range(..start).next_back() {
+            if prev_end.saturating_add(1) >= start {
+                start = prev_start;
+                end = end.max(prev_end);
+                self.ranges.remove(&prev_start);
+            }
+        }
+
+        // Merge with the ranges that begin within or right after the
+        // new range.
+        let upper = end.saturating_add(1);
+        let overlapped: Vec<_> = self
+            .ranges
+            .range(start..=upper)
+            .map(|(&start, &end)| (start, end))
+            .collect();
+        for (next_start, next_end) in overlapped {
+            end = end.max(next_end);
+            self.ranges.remove(&next_start);
+        }
+
+        self.ranges.insert(start, end);
+    }
+
+    pub fn contains(&self, sn: i64) -> bool {
+        match self.ranges.range(..=sn).next_back() {
+            Some((_, &end)) => sn <= end,
+            None => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+impl fmt::Display for SnRanges {
+    /// Formats the set like "105-110, 120".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (&start, &end)) in self.ranges.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+
+            if start == end {
+                write!(f, "{start}")?;
+            } else {
+                write!(f, "{start}-{end}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjacent_numbers_are_merged() {
+        let mut ranges = SnRanges::new();
+        ranges.insert(1);
+        ranges.insert(2);
+        ranges.insert(3);
+        ranges.insert_range(4, 6);
+        assert_eq!(ranges.to_string(), "1-6");
+    }
+
+    #[test]
+    fn overlapping_ranges_are_merged() {
+        let mut ranges = SnRanges::new();
+        ranges.insert_range(1, 5);
+        ranges.insert_range(10, 15);
+        ranges.insert_range(3, 12);
+        assert_eq!(ranges.to_string(), "1-15");
+
+        // A range within an existing one changes nothing.
+        ranges.insert_range(4, 8);
+        assert_eq!(ranges.to_string(), "1-15");
+    }
+
+    #[test]
+    fn out_of_order_inserts_are_merged() {
+        let mut ranges = SnRanges::new();
+        ranges.insert(5);
+        ranges.insert(3);
+        ranges.insert(1);
+        assert_eq!(ranges.to_string(), "1, 3, 5");
+
+        ranges.insert(4);
+        ranges.insert(2);
+        assert_eq!(ranges.to_string(), "1-5");
+        assert!(ranges.contains(1));
+        assert!(ranges.contains(5));
+        assert!(!ranges.contains(6));
+    }
+
+    #[test]
+    fn display_lists_ranges_and_single_numbers() {
+        let mut ranges = SnRanges::new();
+        assert!(ranges.is_empty());
+        assert_eq!(ranges.to_string(), "");
+
+        ranges.insert_range(105, 110);
+        ranges.insert(120);
+        assert_eq!(ranges.to_string(), "105-110, 120");
+    }
+}
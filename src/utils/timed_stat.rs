@@ -1,4 +1,4 @@
-use std::{cmp::Ordering, collections::BinaryHeap};
+use std::{cell::Cell, cmp::Ordering, collections::BinaryHeap};
 
 /// Computes the running average and variance of time series values.
 #[derive(Debug, Clone)]
@@ -7,6 +7,11 @@ pub struct TimedStat {
     last_ts: Option<chrono::Duration>,
     stat: Stat,
     window: chrono::Duration,
+    /// The highest mean ever computed.
+    peak_mean: f64,
+    /// The median of the current window, or `None` if it is not
+    /// computed since the values changed.
+    median: Cell<Option<f64>>,
 }
 
 impl TimedStat {
@@ -18,6 +23,8 @@ impl TimedStat {
             values: BinaryHeap::new(),
             last_ts: None,
             stat: Stat::default(),
+            peak_mean: 0.0,
+            median: Cell::new(None),
         }
     }
 
@@ -35,6 +42,7 @@ impl TimedStat {
             }
 
             self.values.pop();
+            self.median.set(None);
             discarded.push((ts, value));
             stat.sum -= value;
             stat.sum_squares -= value.powi(2);
@@ -53,6 +61,7 @@ impl TimedStat {
             time: ts,
             value: new_value,
         });
+        self.median.set(None);
 
         let stat = &mut self.stat;
         stat.sum += new_value;
@@ -66,6 +75,34 @@ impl TimedStat {
         &self.stat
     }
 
+    /// Gets the highest mean since the stat was created.
+    pub fn peak_mean(&self) -> f64 {
+        self.peak_mean
+    }
+
+    /// Gets the median of the values in the current window. It is
+    /// cached until the window changes, since the TUI asks for it on
+    /// every render.
+    pub fn median(&self) -> Option<f64> {
+        if let Some(median) = self.median.get() {
+            return Some(median);
+        }
+
+        let mut values: Vec<f64> = self.values.iter().map(|entry| entry.value).collect();
+        if values.is_empty() {
+            return None;
+        }
+        let mid = values.len() / 2;
+        let (_, &mut median, _) = values.select_nth_unstable_by(mid, |lhs, rhs| lhs.total_cmp(rhs));
+        self.median.set(Some(median));
+        Some(median)
+    }
+
+    /// Gets the number of values in the current window.
+    pub fn num_samples(&self) -> usize {
+        self.values.len()
+    }
+
     fn update_stat(&mut self) {
         let stat = &mut self.stat;
         let window_secs = self.window.to_std().unwrap().as_secs_f64();
@@ -74,6 +111,7 @@ impl TimedStat {
         stat.mean = mean;
         stat.var = var;
         stat.stdev = var.sqrt();
+        self.peak_mean = self.peak_mean.max(mean);
     }
 }
 